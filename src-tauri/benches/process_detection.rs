@@ -0,0 +1,59 @@
+//! Compares game detection with a full process table refresh on every check against
+//! tracking the PID of the found game.
+//!
+//! The benchmark process itself stands in for the game. Run with
+//! `cargo bench --bench process_detection`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use switch_life_manager_lib::domain::models::{GameDefinition, ProcessRule};
+use switch_life_manager_lib::infrastructure::process_monitor::{ProcessMonitor, SysinfoProcessMonitor};
+
+const CHECKS: u32 = 200;
+
+/// Matched by executable path, since Linux truncates process names to 15 characters.
+fn own_process_game() -> Vec<GameDefinition> {
+    let exe = std::env::current_exe().expect("current executable");
+    vec![GameDefinition {
+        rules: vec![ProcessRule {
+            executable_path: Some(exe.to_string_lossy().to_string()),
+            ..ProcessRule::new("*")
+        }],
+        ..GameDefinition::new("bench", "Benchmark", &[])
+    }]
+}
+
+fn measure(label: &str, mut monitor: SysinfoProcessMonitor, games: &[GameDefinition]) {
+    // The first check is always a full scan
+    assert!(monitor.match_game(games).is_some(), "benchmark process not found");
+
+    let start = Instant::now();
+    for _ in 0..CHECKS {
+        black_box(monitor.match_game(black_box(games)));
+    }
+    let per_check = start.elapsed() / CHECKS;
+    println!("{:<32} {:>10.1} us/check", label, per_check.as_secs_f64() * 1e6);
+}
+
+fn main() {
+    let games = own_process_game();
+    let processes = sysinfo::System::new_all().processes().len();
+    println!("{} processes, {} checks each\n", processes, CHECKS);
+
+    measure(
+        "full scan every check",
+        SysinfoProcessMonitor::new()
+            .with_pid_tracking(false)
+            .with_full_scan_interval(Duration::ZERO),
+        &games,
+    );
+    measure(
+        "tracked PID lookup",
+        SysinfoProcessMonitor::new().with_exit_notification(false),
+        &games,
+    );
+    if cfg!(target_os = "linux") {
+        measure("tracked PID with pidfd", SysinfoProcessMonitor::new(), &games);
+    }
+}
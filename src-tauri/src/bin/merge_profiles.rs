@@ -0,0 +1,103 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use switch_life_manager_lib::infrastructure::persistence::{self, ConfigRepository, FileConfigRepository};
+use switch_life_manager_lib::usecase::profile_merge::{MergeConflict, ProfileMerger};
+
+const USAGE: &str = "Usage: merge_profiles <local profile.json> <remote profile.json> <controller id> \
+[--remote-id <id>] [--baseline <bundle.json>] [--out <merged profile.json>]";
+
+struct Args {
+    local: PathBuf,
+    remote: PathBuf,
+    controller_id: String,
+    remote_id: Option<String>,
+    baseline: Option<PathBuf>,
+    out: Option<PathBuf>,
+}
+
+fn parse_args() -> Option<Args> {
+    let mut positional = Vec::new();
+    let mut remote_id = None;
+    let mut baseline = None;
+    let mut out = None;
+
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--remote-id" => remote_id = Some(iter.next()?),
+            "--baseline" => baseline = Some(PathBuf::from(iter.next()?)),
+            "--out" => out = Some(PathBuf::from(iter.next()?)),
+            _ => positional.push(arg),
+        }
+    }
+
+    if positional.len() != 3 {
+        return None;
+    }
+    let controller_id = positional.pop()?;
+    let remote = PathBuf::from(positional.pop()?);
+    let local = PathBuf::from(positional.pop()?);
+
+    Some(Args { local, remote, controller_id, remote_id, baseline, out })
+}
+
+fn main() -> ExitCode {
+    let Some(args) = parse_args() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: Args) -> anyhow::Result<()> {
+    let mut local = FileConfigRepository::new(&args.local).load()?;
+    let remote = FileConfigRepository::new(&args.remote).load()?;
+    let baseline = match &args.baseline {
+        Some(path) => Some(persistence::load_bundle(path)?.profile),
+        None => None,
+    };
+
+    let remote_id = args.remote_id.as_deref().unwrap_or(&args.controller_id);
+    let remote_profile = remote
+        .controllers
+        .get(remote_id)
+        .ok_or_else(|| anyhow::anyhow!("Controller {} not found in {:?}", remote_id, args.remote))?;
+    let local_profile = local
+        .controllers
+        .get_mut(&args.controller_id)
+        .ok_or_else(|| anyhow::anyhow!("Controller {} not found in {:?}", args.controller_id, args.local))?;
+
+    let outcome = ProfileMerger::merge(baseline.as_ref(), local_profile, remote_profile);
+    *local_profile = outcome.merged;
+
+    if baseline.is_none() {
+        println!("No baseline given: counters take the larger value of both sides.");
+    }
+    for conflict in &outcome.conflicts {
+        match conflict {
+            MergeConflict::SwitchModel { key, local_model_id, remote_model_id, kept_model_id } => {
+                println!(
+                    "Conflict on {}: local model {}, remote model {} -> kept {}",
+                    key, local_model_id, remote_model_id, kept_model_id
+                );
+            }
+        }
+    }
+
+    match &args.out {
+        Some(out) => {
+            FileConfigRepository::new(out).save(&local)?;
+            println!("Merged profile written to {:?}", out);
+        }
+        None => println!("{}", serde_json::to_string_pretty(&local)?),
+    }
+    Ok(())
+}
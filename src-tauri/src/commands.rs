@@ -1,9 +1,13 @@
 use tauri::State;
 use crate::app_state::AppState;
 use crate::usecase::monitor::MonitorCommand;
+use crate::usecase::play_stats::{PlayRollup, PlayStatsAggregator, RollupPeriod};
+use crate::usecase::session_report::{ReportFormat, SessionReport, SessionReporter};
 use crate::usecase::state_publisher::MonitorSharedState;
-use crate::domain::models::{LogicalKey, AppConfig, ButtonMap};
+use crate::usecase::stats_export::{ExportFormat, ExportKind};
+use crate::domain::models::{LogicalKey, AppConfig, ButtonLayout, ButtonMap, ImportMode};
 use chrono::{DateTime, Utc};
+use std::path::PathBuf;
 
 #[tauri::command]
 pub fn get_snapshot(state: State<'_, AppState>) -> MonitorSharedState {
@@ -18,7 +22,7 @@ pub fn force_save(state: State<'_, AppState>) {
 
 #[tauri::command]
 pub fn update_config(state: State<'_, AppState>, config: AppConfig) {
-    let _ = state.command_tx.send(MonitorCommand::UpdateConfig(config));
+    let _ = state.command_tx.send(MonitorCommand::UpdateConfig(Box::new(config)));
 }
 
 #[tauri::command]
@@ -26,13 +30,20 @@ pub fn set_target_controller(state: State<'_, AppState>, index: u32) {
     let guard = state.shared_state.load();
     let mut config = guard.config.clone();
     config.target_controller_index = index;
-    let _ = state.command_tx.send(MonitorCommand::UpdateConfig(config));
+    config.target_controller_id = None;
+    let _ = state.command_tx.send(MonitorCommand::UpdateConfig(Box::new(config)));
 }
 
 #[tauri::command]
 pub fn reset_to_default_mapping(state: State<'_, AppState>) {
     let default = ButtonMap::default();
     let _ = state.command_tx.send(MonitorCommand::UpdateMapping(default.profile_name, default.bindings));
+    let _ = state.command_tx.send(MonitorCommand::SetButtonLayout(default.layout));
+}
+
+#[tauri::command]
+pub fn set_button_layout(state: State<'_, AppState>, layout: ButtonLayout) {
+    let _ = state.command_tx.send(MonitorCommand::SetButtonLayout(layout));
 }
 
 #[tauri::command]
@@ -57,5 +68,108 @@ pub fn set_last_replaced_date(state: State<'_, AppState>, key: LogicalKey, date:
 
 #[tauri::command]
 pub fn set_active_controller(state: State<'_, AppState>, id: String) {
+    // Read input from the same physical controller whose profile is selected
+    let guard = state.shared_state.load();
+    if guard.connected_controllers.iter().any(|c| c.id == id) {
+        let mut config = guard.config.clone();
+        config.target_controller_id = Some(id.clone());
+        let _ = state.command_tx.send(MonitorCommand::UpdateConfig(Box::new(config)));
+    }
     let _ = state.command_tx.send(MonitorCommand::SetActiveController(id));
 }
+
+#[tauri::command]
+pub fn export_profile(state: State<'_, AppState>, controller_id: String, path: PathBuf) {
+    let controller_name = state
+        .shared_state
+        .load()
+        .connected_controllers
+        .iter()
+        .find(|c| c.id == controller_id)
+        .map(|c| c.name.clone());
+    let _ = state.command_tx.send(MonitorCommand::ExportProfile { controller_id, controller_name, path });
+}
+
+#[tauri::command]
+pub fn import_profile(state: State<'_, AppState>, path: PathBuf, mode: ImportMode) {
+    let _ = state.command_tx.send(MonitorCommand::ImportProfile { path, mode });
+}
+
+#[tauri::command]
+pub fn export_data(state: State<'_, AppState>, kind: ExportKind, format: ExportFormat, path: PathBuf) {
+    let _ = state.command_tx.send(MonitorCommand::ExportData { kind, format, path });
+}
+
+/// Report on the active controller's session that started at `start_time`, or its latest one.
+#[tauri::command]
+pub fn get_session_report(state: State<'_, AppState>, start_time: Option<DateTime<Utc>>) -> Option<SessionReport> {
+    let guard = state.shared_state.load();
+    SessionReporter::build_for(&guard.session_history, start_time, &guard.switches, &guard.config)
+}
+
+#[tauri::command]
+pub fn render_session_report(
+    state: State<'_, AppState>,
+    start_time: Option<DateTime<Utc>>,
+    format: ReportFormat,
+) -> Option<String> {
+    get_session_report(state, start_time).map(|report| SessionReporter::render(&report, format))
+}
+
+#[tauri::command]
+pub fn export_session_report(
+    state: State<'_, AppState>,
+    start_time: Option<DateTime<Utc>>,
+    format: ReportFormat,
+    path: PathBuf,
+) {
+    let _ = state.command_tx.send(MonitorCommand::ExportSessionReport { start_time, format, path });
+}
+
+/// Calendar rollups of the active controller's sessions, e.g. for a heatmap.
+/// `utc_offset_minutes` is the viewer's offset; without it the system timezone is used.
+#[tauri::command]
+pub fn get_play_stats(
+    state: State<'_, AppState>,
+    period: RollupPeriod,
+    utc_offset_minutes: Option<i32>,
+) -> Vec<PlayRollup> {
+    let guard = state.shared_state.load();
+    PlayStatsAggregator::rollup_at_offset(&guard.session_history, &guard.counter_snapshots, period, utc_offset_minutes)
+}
+
+#[tauri::command]
+pub fn export_play_stats(
+    state: State<'_, AppState>,
+    period: RollupPeriod,
+    utc_offset_minutes: Option<i32>,
+    format: ExportFormat,
+    path: PathBuf,
+) {
+    let _ = state.command_tx.send(MonitorCommand::ExportPlayStats { period, utc_offset_minutes, format, path });
+}
+
+#[tauri::command]
+pub fn start_session(state: State<'_, AppState>) {
+    let _ = state.command_tx.send(MonitorCommand::StartSession);
+}
+
+#[tauri::command]
+pub fn stop_session(state: State<'_, AppState>) {
+    let _ = state.command_tx.send(MonitorCommand::StopSession);
+}
+
+#[tauri::command]
+pub fn pause_session(state: State<'_, AppState>) {
+    let _ = state.command_tx.send(MonitorCommand::PauseSession);
+}
+
+#[tauri::command]
+pub fn resume_session(state: State<'_, AppState>) {
+    let _ = state.command_tx.send(MonitorCommand::ResumeSession);
+}
+
+#[tauri::command]
+pub fn acknowledge_alert(state: State<'_, AppState>, id: u64) {
+    let _ = state.command_tx.send(MonitorCommand::AcknowledgeAlert(id));
+}
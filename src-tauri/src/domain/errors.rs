@@ -10,3 +10,13 @@ pub enum InputError {
 
 // Since anyhow::Error is not Clone, we cannot derive Clone for InputError easily.
 // For testing purposes, we might need a workaround or just return new errors.
+
+#[derive(Debug, Error)]
+pub enum ProfileTransferError {
+    #[error("Unsupported bundle format version: {0}")]
+    UnsupportedVersion(u32),
+    #[error("Controller profile not found: {0}")]
+    ControllerNotFound(String),
+    #[error("Controller profile already exists: {0}")]
+    ControllerExists(String),
+}
@@ -1,5 +1,15 @@
+use std::collections::BTreeMap;
+
 use crate::domain::errors::InputError;
-use crate::domain::models::{InputMethod, ControllerInfo};
+use crate::domain::models::{ButtonLayout, InputMethod, ControllerInfo, MidiConfig};
+
+/// Button bitmask as it was at a specific moment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputSnapshot {
+    pub buttons: u32,
+    /// Unix time in milliseconds at which the device reported this state.
+    pub timestamp_ms: u64,
+}
 
 /// Abstraction for getting input state.
 pub trait InputSource: Send {
@@ -7,11 +17,49 @@ pub trait InputSource: Send {
     /// Returns Err(InputError::Disconnected) if device is disconnected.
     fn get_state(&mut self, controller_index: u32) -> Result<u32, InputError>;
 
+    /// Returns every button state change since the previous call, oldest first.
+    /// The last snapshot is the current state, so the result is never empty.
+    ///
+    /// The default implementation falls back to polling `get_state` once.
+    fn poll_events(&mut self, controller_index: u32) -> Result<Vec<InputSnapshot>, InputError> {
+        let buttons = self.get_state(controller_index)?;
+        Ok(vec![InputSnapshot {
+            buttons,
+            timestamp_ms: unix_time_ms(std::time::SystemTime::now()),
+        }])
+    }
+
     /// Optional: updates the input method if the source supports switching.
     fn set_input_method(&mut self, _method: InputMethod) {}
 
+    /// Optional: selects how buttons are numbered, for sources that support both layouts.
+    fn set_button_layout(&mut self, _layout: ButtonLayout) {}
+
+    /// Optional: key name to bit table for sources that read keyboards.
+    fn set_keyboard_bindings(&mut self, _bindings: &BTreeMap<String, u32>) {}
+
+    /// Optional: note and turntable mapping for sources that read MIDI.
+    fn set_midi_config(&mut self, _config: &MidiConfig) {}
+
     /// Enumerates connected controllers.
     fn enumerate_controllers(&mut self) -> Result<Vec<ControllerInfo>, InputError> {
         Ok(Vec::new()) // Default implementation
     }
+
+    /// Finds the current index of the controller with the given `ControllerInfo::id`.
+    /// Returns `None` if it is not connected.
+    fn find_controller_index(&mut self, controller_id: &str) -> Option<u32> {
+        self.enumerate_controllers()
+            .ok()?
+            .iter()
+            .position(|c| c.id == controller_id)
+            .map(|i| i as u32)
+    }
+}
+
+/// Converts a `SystemTime` to the millisecond timestamps used by `InputSnapshot`.
+pub fn unix_time_ms(time: std::time::SystemTime) -> u64 {
+    time.duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
 }
@@ -0,0 +1,174 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::LogicalKey;
+
+/// Maximum number of alerts kept in `AlertLog`. The oldest resolved ones go first.
+pub const MAX_ALERTS: usize = 100;
+
+/// A condition the monitor raises an alert for.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum AlertRule {
+    /// Chatters per press of a key over its last `window_presses` presses.
+    ChatterRate { percent: f64, window_presses: u64 },
+    /// Presses of a switch as a share of its rated lifespan.
+    Lifespan { percent: f64 },
+    /// Chatters of a key in the running session.
+    SessionChatters { count: u64 },
+    /// The active controller disconnected while a session was running.
+    DisconnectedInSession,
+}
+
+impl AlertRule {
+    pub fn kind(&self) -> AlertKind {
+        match self {
+            AlertRule::ChatterRate { .. } => AlertKind::ChatterRate,
+            AlertRule::Lifespan { .. } => AlertKind::Lifespan,
+            AlertRule::SessionChatters { .. } => AlertKind::SessionChatters,
+            AlertRule::DisconnectedInSession => AlertKind::DisconnectedInSession,
+        }
+    }
+}
+
+pub fn default_alert_rules() -> Vec<AlertRule> {
+    vec![
+        AlertRule::ChatterRate { percent: 2.0, window_presses: 500 },
+        AlertRule::Lifespan { percent: 90.0 },
+        AlertRule::SessionChatters { count: 20 },
+        AlertRule::DisconnectedInSession,
+    ]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AlertKind {
+    ChatterRate,
+    Lifespan,
+    SessionChatters,
+    DisconnectedInSession,
+    /// Saving the profile failed. Raised by the monitor itself, not by a rule.
+    SaveFailed,
+}
+
+/// A raised alert. It stays active until its condition clears.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Alert {
+    pub id: u64,
+    pub kind: AlertKind,
+    /// Empty for alerts that are not about one controller, like `SaveFailed`.
+    pub controller_id: String,
+    pub key: Option<LogicalKey>,
+    pub message: String,
+    pub raised_at: DateTime<Utc>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+impl Alert {
+    pub fn is_active(&self) -> bool {
+        self.resolved_at.is_none()
+    }
+}
+
+/// Active and recently resolved alerts.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AlertLog {
+    pub next_id: u64,
+    pub alerts: Vec<Alert>,
+}
+
+impl AlertLog {
+    /// Raise an alert unless the same one is still active, acknowledged or not.
+    /// Returns the new alert.
+    pub fn raise(
+        &mut self,
+        kind: AlertKind,
+        controller_id: &str,
+        key: Option<&LogicalKey>,
+        message: String,
+        now: DateTime<Utc>,
+    ) -> Option<&Alert> {
+        if self.find_active(kind, controller_id, key).is_some() {
+            return None;
+        }
+
+        self.next_id += 1;
+        self.alerts.push(Alert {
+            id: self.next_id,
+            kind,
+            controller_id: controller_id.to_string(),
+            key: key.cloned(),
+            message,
+            raised_at: now,
+            acknowledged_at: None,
+            resolved_at: None,
+        });
+        if self.alerts.len() > MAX_ALERTS {
+            let index = self.alerts.iter().position(|a| !a.is_active()).unwrap_or(0);
+            self.alerts.remove(index);
+        }
+        self.alerts.last()
+    }
+
+    /// Mark the active alert as resolved. Returns false if there was none.
+    pub fn resolve(
+        &mut self,
+        kind: AlertKind,
+        controller_id: &str,
+        key: Option<&LogicalKey>,
+        now: DateTime<Utc>,
+    ) -> bool {
+        match self.find_active(kind, controller_id, key) {
+            Some(index) => {
+                self.alerts[index].resolved_at = Some(now);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns false if there is no unacknowledged alert with that id.
+    pub fn acknowledge(&mut self, id: u64, now: DateTime<Utc>) -> bool {
+        match self.alerts.iter_mut().find(|a| a.id == id && a.acknowledged_at.is_none()) {
+            Some(alert) => {
+                alert.acknowledged_at = Some(now);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn active(&self) -> impl Iterator<Item = &Alert> {
+        self.alerts.iter().filter(|a| a.is_active())
+    }
+
+    fn find_active(&self, kind: AlertKind, controller_id: &str, key: Option<&LogicalKey>) -> Option<usize> {
+        self.alerts.iter().position(|a| {
+            a.is_active() && a.kind == kind && a.controller_id == controller_id && a.key.as_ref() == key
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alert_raised_once_until_resolved() {
+        let mut log = AlertLog::default();
+        let now = Utc::now();
+        let key = Some(&LogicalKey::Key1);
+
+        let id = log.raise(AlertKind::Lifespan, "c1", key, "worn".to_string(), now).unwrap().id;
+        assert!(log.acknowledge(id, now));
+        assert!(!log.acknowledge(id, now));
+        // Acknowledged alerts stay active and are not raised again
+        assert!(log.raise(AlertKind::Lifespan, "c1", key, "worn".to_string(), now).is_none());
+        // Same kind on another key is a separate alert
+        assert!(log.raise(AlertKind::Lifespan, "c1", Some(&LogicalKey::Key2), "worn".to_string(), now).is_some());
+
+        assert!(log.resolve(AlertKind::Lifespan, "c1", key, now));
+        assert_eq!(log.active().count(), 1);
+        assert!(log.raise(AlertKind::Lifespan, "c1", key, "worn again".to_string(), now).is_some());
+    }
+}
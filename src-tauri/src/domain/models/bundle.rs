@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use std::path::PathBuf;
+
+use super::ControllerProfile;
+
+/// Current format version of exported profile bundles.
+pub const PROFILE_BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// A single `ControllerProfile` packaged with metadata for moving it between machines.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileBundle {
+    pub format_version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub app_version: String,
+    pub controller_id: String,
+    #[serde(default)]
+    pub controller_name: Option<String>,
+    pub profile: ControllerProfile,
+}
+
+/// How an imported bundle is applied to the local `UserProfile`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode")]
+pub enum ImportMode {
+    /// Add the bundle as a new controller. Uses the bundle's id when `controller_id` is `None`.
+    AddNew { controller_id: Option<String> },
+    /// Merge the bundle into an existing controller profile.
+    /// `baseline_path` points to an earlier bundle of the same controller that both sides started from.
+    MergeInto {
+        controller_id: String,
+        #[serde(default)]
+        baseline_path: Option<PathBuf>,
+    },
+}
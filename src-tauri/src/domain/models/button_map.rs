@@ -4,33 +4,49 @@ use std::collections::HashMap;
 
 use super::LogicalKey;
 
+/// How DirectInput and XInput number buttons in the bitmask.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ButtonLayout {
+    /// Bits follow gilrs' standard gamepad buttons (South = bit 0, East = bit 1, ...),
+    /// so only 19 buttons are usable. XInput reports its `wButtons` flags unchanged.
+    /// Profiles saved before `RawIndex` existed use this.
+    #[default]
+    StandardButtons,
+    /// Bit N is physical HID button N + 1, with all 32 bits usable. XInput buttons are
+    /// numbered the way DirectInput numbers an Xbox pad (A = bit 0).
+    RawIndex,
+}
+
 #[serde_as]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ButtonMap {
     pub profile_name: String,
     #[serde_as(as = "HashMap<serde_with::DisplayFromStr, _>")]
     pub bindings: HashMap<LogicalKey, u32>,
+    #[serde(default)]
+    pub layout: ButtonLayout,
 }
 
 impl Default for ButtonMap {
     fn default() -> Self {
         let mut bindings = HashMap::new();
-        // Default mapping based on user's PhoenixWAN configuration
-        bindings.insert(LogicalKey::Key1, 8);
-        bindings.insert(LogicalKey::Key2, 1);
-        bindings.insert(LogicalKey::Key3, 2);
-        bindings.insert(LogicalKey::Key4, 4);
-        bindings.insert(LogicalKey::Key5, 64);
-        bindings.insert(LogicalKey::Key6, 256);
-        bindings.insert(LogicalKey::Key7, 128);
-        bindings.insert(LogicalKey::E1, 1024);
-        bindings.insert(LogicalKey::E2, 2048);
-        bindings.insert(LogicalKey::E3, 8192);
-        bindings.insert(LogicalKey::E4, 16384);
+        // Default mapping: physical buttons 1-7 are the keys, E1-E4 follow after button 8
+        bindings.insert(LogicalKey::Key1, 1 << 0);
+        bindings.insert(LogicalKey::Key2, 1 << 1);
+        bindings.insert(LogicalKey::Key3, 1 << 2);
+        bindings.insert(LogicalKey::Key4, 1 << 3);
+        bindings.insert(LogicalKey::Key5, 1 << 4);
+        bindings.insert(LogicalKey::Key6, 1 << 5);
+        bindings.insert(LogicalKey::Key7, 1 << 6);
+        bindings.insert(LogicalKey::E1, 1 << 8);
+        bindings.insert(LogicalKey::E2, 1 << 9);
+        bindings.insert(LogicalKey::E3, 1 << 10);
+        bindings.insert(LogicalKey::E4, 1 << 11);
 
         Self {
             profile_name: "Default".to_string(),
             bindings,
+            layout: ButtonLayout::RawIndex,
         }
     }
 }
@@ -1,9 +1,20 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::game::{default_game_definitions, GameDefinition};
+use super::{default_alert_rules, AlertKind, AlertRule, LogicalKey, SleepAction};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum InputMethod {
     XInput,
     DirectInput,
+    /// Native Linux evdev (`/dev/input/event*`). Falls back to DirectInput elsewhere.
+    Evdev,
+    /// Keyboard keys turned into bits via `AppConfig::keyboard_bindings` (Linux evdev only).
+    /// Falls back to DirectInput elsewhere.
+    Keyboard,
+    /// MIDI controllers, mapped through `AppConfig::midi`.
+    Midi,
 }
 
 impl Default for InputMethod {
@@ -15,22 +26,204 @@ impl Default for InputMethod {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
     pub target_controller_index: u32,
+    /// Controller to monitor, by `ControllerInfo::id`. Resolved to a runtime index on every
+    /// poll; when set, `target_controller_index` is ignored. XInput always uses the index,
+    /// since its slots have no stable identity.
+    #[serde(default)]
+    pub target_controller_id: Option<String>,
     pub input_method: InputMethod,
     pub chatter_threshold_ms: u64,
     pub polling_rate_ms_connected: u64,
     pub polling_rate_ms_disconnected: u64,
+    /// Single game process used when `games` is empty (configs from before `games` existed).
     pub target_process_name: String,
+    /// Games whose processes start a session, checked in order.
+    #[serde(default)]
+    pub games: Vec<GameDefinition>,
+    /// Address for the OpenMetrics endpoint (e.g. `127.0.0.1:9464`). Disabled when `None`.
+    #[serde(default)]
+    pub metrics_listen_addr: Option<String>,
+    /// Activate the profile of a controller automatically when it connects. Off by default,
+    /// and ignored with XInput, which reads pads by slot rather than by controller id.
+    #[serde(default)]
+    pub auto_switch_controller: bool,
+    /// Controller ids in order of preference when several known controllers are connected.
+    #[serde(default)]
+    pub controller_priority: Vec<String>,
+    /// Keyboard key name (Linux key name without `KEY_`, e.g. `Z`, `LEFTSHIFT`) to the
+    /// bit it sets in the bitmask, for `InputMethod::Keyboard`.
+    #[serde(default = "default_keyboard_bindings")]
+    pub keyboard_bindings: BTreeMap<String, u32>,
+    #[serde(default)]
+    pub midi: MidiConfig,
+    /// Button combo on the active controller that starts or stops a session. Disabled when `None`.
+    #[serde(default)]
+    pub session_hotkey: Option<SessionHotkey>,
+    /// Sessions shorter than this are dropped instead of recorded.
+    #[serde(default = "default_min_session_secs")]
+    pub min_session_secs: u64,
+    /// A game that starts again within this many seconds after exiting continues the same
+    /// session. Zero ends the session as soon as the game exits.
+    #[serde(default = "default_session_grace_secs")]
+    pub session_grace_secs: u64,
+    /// A session saved by a previous run continues if the app starts again within this many
+    /// seconds of its last activity; otherwise it is closed at the last activity.
+    #[serde(default = "default_session_resume_secs")]
+    pub session_resume_secs: u64,
+    #[serde(default)]
+    pub sleep_action: SleepAction,
+    /// How often per-key totals are snapshotted if they changed. 0 disables snapshots.
+    #[serde(default = "default_snapshot_interval_secs")]
+    pub snapshot_interval_secs: u64,
+    /// Conditions the monitor raises alerts for. Remove a rule to disable it.
+    #[serde(default = "default_alert_rules")]
+    pub alert_rules: Vec<AlertRule>,
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+}
+
+/// Desktop notifications for raised alerts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationConfig {
+    pub enabled: bool,
+    /// Alerts of one kind notify at most once per this many seconds. Alerts raised in
+    /// between notify together once it has passed.
+    pub min_interval_secs: u64,
+    /// Alert kinds that never notify.
+    pub muted_kinds: Vec<AlertKind>,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_interval_secs: 300,
+            muted_kinds: Vec::new(),
+        }
+    }
+}
+
+/// Keys held together for `hold_ms` to toggle a session, e.g. E1+E2 for 2 s.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionHotkey {
+    pub keys: Vec<LogicalKey>,
+    pub hold_ms: u64,
+}
+
+/// How MIDI messages become bits, for `InputMethod::Midi`. Messages on all channels are used.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MidiConfig {
+    /// Note number to bit. Note-on presses; note-off or note-on with velocity 0 releases.
+    pub note_bits: BTreeMap<u8, u32>,
+    /// Control change sent by the turntable as a relative encoder, if any.
+    pub turntable_cc: Option<u8>,
+    /// Bit held while the turntable turns with values 1-63.
+    pub turntable_up_bit: u32,
+    /// Bit held while the turntable turns with values 65-127.
+    pub turntable_down_bit: u32,
+    /// How long a turntable direction stays pressed after its last movement.
+    pub turntable_hold_ms: u64,
+    /// Open a virtual input port that other software can send to (not available on Windows).
+    pub virtual_port: bool,
+}
+
+impl Default for MidiConfig {
+    /// Keys 1-7 on notes 48-54 and E1-E4 on notes 56-59 (bits as in the default `ButtonMap`),
+    /// turntable on CC 16.
+    fn default() -> Self {
+        let keys = (48..=54).zip(0..=6);
+        let extras = (56..=59).zip(8..=11);
+        Self {
+            note_bits: keys.chain(extras).collect(),
+            turntable_cc: Some(16),
+            turntable_up_bit: 12,
+            turntable_down_bit: 13,
+            turntable_hold_ms: 100,
+            virtual_port: false,
+        }
+    }
+}
+
+impl AppConfig {
+    /// Chatter rate in percent of the first `AlertRule::ChatterRate`, above which keys are
+    /// also flagged in session reports.
+    pub fn chatter_alert_percent(&self) -> Option<f64> {
+        self.alert_rules.iter().find_map(|rule| match *rule {
+            AlertRule::ChatterRate { percent, .. } => Some(percent),
+            _ => None,
+        })
+    }
+
+    /// The games to detect. Without `games`, `target_process_name` is used, taking over the
+    /// id of a built-in game with that process so sessions are tagged consistently.
+    pub fn game_definitions(&self) -> Vec<GameDefinition> {
+        if !self.games.is_empty() {
+            return self.games.clone();
+        }
+        let legacy = default_game_definitions()
+            .into_iter()
+            .find(|g| g.matches(&self.target_process_name, None));
+        vec![legacy.unwrap_or_else(|| {
+            GameDefinition::new(&self.target_process_name, &self.target_process_name, &[&self.target_process_name])
+        })]
+    }
+}
+
+fn default_min_session_secs() -> u64 {
+    60
+}
+
+fn default_session_grace_secs() -> u64 {
+    60
+}
+
+fn default_session_resume_secs() -> u64 {
+    300
+}
+
+fn default_snapshot_interval_secs() -> u64 {
+    3600
+}
+
+/// The common IIDX keyboard layout: Z S X D C F V for keys 1-7, Q W E R for E1-E4
+/// (matching the default `ButtonMap` bits), and the scratch keys after them.
+fn default_keyboard_bindings() -> BTreeMap<String, u32> {
+    [
+        ("Z", 0), ("S", 1), ("X", 2), ("D", 3), ("C", 4), ("F", 5), ("V", 6),
+        ("Q", 8), ("W", 9), ("E", 10), ("R", 11),
+        ("LEFTSHIFT", 12), ("LEFTCTRL", 13),
+    ]
+    .into_iter()
+    .map(|(key, bit)| (key.to_string(), bit))
+    .collect()
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
             target_controller_index: 0,
+            target_controller_id: None,
             input_method: InputMethod::default(),
             chatter_threshold_ms: 15,
             polling_rate_ms_connected: 1,
             polling_rate_ms_disconnected: 1000,
             target_process_name: "bm2dx.exe".to_string(),
+            games: default_game_definitions(),
+            metrics_listen_addr: None,
+            auto_switch_controller: false,
+            controller_priority: Vec::new(),
+            keyboard_bindings: default_keyboard_bindings(),
+            midi: MidiConfig::default(),
+            session_hotkey: None,
+            min_session_secs: default_min_session_secs(),
+            session_grace_secs: default_session_grace_secs(),
+            session_resume_secs: default_session_resume_secs(),
+            sleep_action: SleepAction::default(),
+            snapshot_interval_secs: default_snapshot_interval_secs(),
+            alert_rules: default_alert_rules(),
+            notifications: NotificationConfig::default(),
         }
     }
 }
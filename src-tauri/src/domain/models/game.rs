@@ -0,0 +1,270 @@
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+use std::collections::HashMap;
+
+use super::{LogicalKey, SessionKeyStats};
+
+/// A game whose running process starts a session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameDefinition {
+    /// Stable id, stored in `SessionRecord::game_id`.
+    pub id: String,
+    pub display_name: String,
+    /// Process name patterns, case-insensitive. `*` matches any run of characters, `?` one.
+    pub process_names: Vec<String>,
+    /// Pattern the full executable path must also match, e.g. `*\LR2\*`.
+    #[serde(default)]
+    pub executable_path: Option<String>,
+    /// Additional rules for games that `process_names` cannot tell apart,
+    /// e.g. a Java game running as `javaw.exe`.
+    #[serde(default)]
+    pub rules: Vec<ProcessRule>,
+}
+
+/// One way to recognize a game process. Every pattern that is set must match.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessRule {
+    /// Process name pattern, e.g. `java*`.
+    pub process_name: String,
+    #[serde(default)]
+    pub executable_path: Option<String>,
+    /// Pattern for the command line, arguments joined by spaces, e.g. `*beatoraja*`.
+    #[serde(default)]
+    pub command_line: Option<String>,
+    /// Pattern for the title of one of the process's windows. Titles are only read on
+    /// Windows; elsewhere a rule with a title never matches.
+    #[serde(default)]
+    pub window_title: Option<String>,
+}
+
+impl ProcessRule {
+    pub fn new(process_name: &str) -> Self {
+        Self {
+            process_name: process_name.to_string(),
+            executable_path: None,
+            command_line: None,
+            window_title: None,
+        }
+    }
+
+    pub fn matches(&self, process: &ProcessInfo) -> bool {
+        fn optional(pattern: &Option<String>, value: Option<&str>) -> bool {
+            match pattern {
+                Some(pattern) => value.is_some_and(|v| wildcard_match(pattern, v)),
+                None => true,
+            }
+        }
+
+        wildcard_match(&self.process_name, &process.name)
+            && optional(&self.executable_path, process.executable_path.as_deref())
+            && optional(&self.command_line, process.command_line.as_deref())
+            && match &self.window_title {
+                Some(pattern) => process.window_titles.iter().any(|t| wildcard_match(pattern, t)),
+                None => true,
+            }
+    }
+
+    /// Whether only the process name is checked, so a lookup by name is enough.
+    pub fn is_name_only(&self) -> bool {
+        self.executable_path.is_none() && self.command_line.is_none() && self.window_title.is_none()
+    }
+
+    /// Whether the rule needs window titles, which are expensive to collect.
+    pub fn needs_window_title(&self) -> bool {
+        self.window_title.is_some()
+    }
+}
+
+/// What is known about a running process when matching rules.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    pub executable_path: Option<String>,
+    pub command_line: Option<String>,
+    pub window_titles: Vec<String>,
+}
+
+/// A running game and the rule that recognized it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameMatch {
+    pub game_id: String,
+    pub rule: ProcessRule,
+    /// `None` when the process was found by name only.
+    pub pid: Option<u32>,
+}
+
+impl GameDefinition {
+    pub fn new(id: &str, display_name: &str, process_names: &[&str]) -> Self {
+        Self {
+            id: id.to_string(),
+            display_name: display_name.to_string(),
+            process_names: process_names.iter().map(|p| p.to_string()).collect(),
+            executable_path: None,
+            rules: Vec::new(),
+        }
+    }
+
+    /// `process_names` (with `executable_path`) as rules, followed by `rules`.
+    pub fn process_rules(&self) -> Vec<ProcessRule> {
+        let by_name = self.process_names.iter().map(|name| ProcessRule {
+            executable_path: self.executable_path.clone(),
+            ..ProcessRule::new(name)
+        });
+        by_name.chain(self.rules.iter().cloned()).collect()
+    }
+
+    /// The first rule matching the process, if any.
+    pub fn matching_rule(&self, process: &ProcessInfo) -> Option<ProcessRule> {
+        self.process_rules().into_iter().find(|rule| rule.matches(process))
+    }
+
+    /// Whether a process with this name (and executable path, if known) belongs to the game.
+    pub fn matches(&self, process_name: &str, executable_path: Option<&str>) -> bool {
+        let process = ProcessInfo {
+            name: process_name.to_string(),
+            executable_path: executable_path.map(str::to_string),
+            ..Default::default()
+        };
+        self.matching_rule(&process).is_some()
+    }
+}
+
+/// The first game in `games` with a matching process. Games are checked in order, so an
+/// earlier definition wins when several match.
+pub fn find_game_match(games: &[GameDefinition], processes: &[ProcessInfo]) -> Option<GameMatch> {
+    games.iter().find_map(|game| {
+        processes.iter().find_map(|process| {
+            game.matching_rule(process).map(|rule| GameMatch {
+                game_id: game.id.clone(),
+                rule,
+                pid: Some(process.pid),
+            })
+        })
+    })
+}
+
+/// The games known out of the box.
+pub fn default_game_definitions() -> Vec<GameDefinition> {
+    vec![
+        GameDefinition::new("iidx", "beatmania IIDX INFINITAS", &["bm2dx.exe"]),
+        GameDefinition::new("lr2", "Lunatic Rave 2", &["LR2body.exe", "LRHbody.exe"]),
+        GameDefinition::new("sdvx", "SOUND VOLTEX", &["sv6c.exe", "soundvoltex.exe"]),
+        GameDefinition {
+            rules: vec![ProcessRule {
+                command_line: Some("*beatoraja*".to_string()),
+                ..ProcessRule::new("java*")
+            }],
+            ..GameDefinition::new("beatoraja", "beatoraja", &[])
+        },
+    ]
+}
+
+/// Case-insensitive match of `text` against a pattern with `*` and `?` wildcards.
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    // Iterative matching with backtracking to the last `*`
+    let (mut p, mut t) = (0, 0);
+    let mut last_star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            last_star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = last_star {
+            p = star_p + 1;
+            t = star_t + 1;
+            last_star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Totals of all sessions played in one game on one controller.
+#[serde_as]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GameStats {
+    pub sessions: u64,
+    pub play_time_secs: u64,
+    #[serde_as(as = "HashMap<serde_with::DisplayFromStr, _>")]
+    #[serde(default)]
+    pub keys: HashMap<LogicalKey, SessionKeyStats>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::AppConfig;
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("bm2dx.exe", "BM2DX.EXE"));
+        assert!(wildcard_match("LR*body.exe", "LRHbody.exe"));
+        assert!(wildcard_match("sv?c.exe", "sv6c.exe"));
+        assert!(wildcard_match("*\\LR2\\*", "C:\\Games\\LR2\\LR2body.exe"));
+        assert!(!wildcard_match("bm2dx.exe", "bm2dx.exe.bak"));
+        assert!(!wildcard_match("sv?c.exe", "svc.exe"));
+    }
+
+    #[test]
+    fn test_executable_path_must_match_when_set() {
+        let mut game = GameDefinition::new("lr2", "LR2", &["LR2body.exe"]);
+        game.executable_path = Some("*\\LR2\\*".to_string());
+
+        assert!(game.matches("LR2body.exe", Some("D:\\LR2\\LR2body.exe")));
+        assert!(!game.matches("LR2body.exe", Some("D:\\Other\\LR2body.exe")));
+        assert!(!game.matches("LR2body.exe", None));
+    }
+
+    #[test]
+    fn test_java_game_matched_by_command_line() {
+        let java = |pid: u32, command_line: &str| ProcessInfo {
+            pid,
+            name: "javaw.exe".to_string(),
+            command_line: Some(command_line.to_string()),
+            ..Default::default()
+        };
+        let games = default_game_definitions();
+
+        let other_app = java(10, "javaw.exe -jar minecraft.jar");
+        assert_eq!(find_game_match(&games, &[other_app.clone()]), None);
+
+        let found = find_game_match(&games, &[other_app, java(11, "javaw.exe -jar beatoraja.jar")]).unwrap();
+        assert_eq!(found.game_id, "beatoraja");
+        assert_eq!(found.pid, Some(11));
+        assert_eq!(found.rule.command_line.as_deref(), Some("*beatoraja*"));
+    }
+
+    #[test]
+    fn test_window_title_rule_needs_a_title() {
+        let rule = ProcessRule {
+            window_title: Some("LR2 *".to_string()),
+            ..ProcessRule::new("*")
+        };
+        let process = |titles: &[&str]| ProcessInfo {
+            name: "game.exe".to_string(),
+            window_titles: titles.iter().map(|t| t.to_string()).collect(),
+            ..Default::default()
+        };
+        assert!(!rule.matches(&process(&[])));
+        assert!(rule.matches(&process(&["Settings", "LR2 HD ver"])));
+    }
+
+    #[test]
+    fn test_legacy_target_process_reuses_builtin_id() {
+        let mut config = AppConfig { games: Vec::new(), ..Default::default() };
+        config.target_process_name = "LR2body.exe".to_string();
+        assert_eq!(config.game_definitions()[0].id, "lr2");
+
+        config.target_process_name = "custom.exe".to_string();
+        let games = config.game_definitions();
+        assert_eq!(games.len(), 1);
+        assert!(games[0].matches("CUSTOM.EXE", None));
+    }
+}
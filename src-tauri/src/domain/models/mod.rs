@@ -4,14 +4,28 @@ mod button_map;
 mod switch;
 mod session;
 mod profile;
+mod bundle;
+mod game;
+mod alert;
 
 // Re-export all public types so that `use crate::domain::models::*` continues to work.
 pub use logical_key::LogicalKey;
-pub use config::{InputMethod, AppConfig};
-pub use button_map::ButtonMap;
-pub use switch::{ButtonStats, SwitchData, SwitchModelInfo, get_default_switch_models};
-pub use session::{SwitchHistoryEntry, SessionRecord, SessionKeyStats};
-pub use profile::{ControllerInfo, ControllerProfile, UserProfile};
+pub use config::{InputMethod, AppConfig, MidiConfig, NotificationConfig, SessionHotkey};
+pub use button_map::{ButtonLayout, ButtonMap};
+pub use switch::{ButtonStats, CounterSnapshot, SwitchData, SwitchModelInfo, get_default_switch_models};
+pub use session::{
+    ActiveSession, SessionKeyStats, SessionOrigin, SessionRecord, SessionTimeline, SleepAction,
+    SwitchHistoryEntry, MAX_RECENT_SESSIONS, MAX_TIMELINE_BUCKETS,
+};
+pub use profile::{
+    ControllerInfo, ControllerProfile, ControllerSwitchEntry, ControllerSwitchReason, UserProfile,
+};
+pub use game::{
+    default_game_definitions, find_game_match, wildcard_match, GameDefinition, GameMatch, GameStats,
+    ProcessInfo, ProcessRule,
+};
+pub use bundle::{ImportMode, ProfileBundle, PROFILE_BUNDLE_FORMAT_VERSION};
+pub use alert::{default_alert_rules, Alert, AlertKind, AlertLog, AlertRule, MAX_ALERTS};
 
 #[cfg(test)]
 mod tests {
@@ -79,4 +93,12 @@ mod tests {
         assert!(json.contains("\"switch_model_id\": \"omron\""));
         assert!(json.contains("\"target_process_name\": \"bm2dx.exe\""));
     }
+
+    #[test]
+    fn test_mapping_without_layout_is_legacy() {
+        let json = r#"{"profile_name": "Old", "bindings": {"Key1": 8}}"#;
+        let map: ButtonMap = serde_json::from_str(json).unwrap();
+        assert_eq!(map.layout, ButtonLayout::StandardButtons);
+        assert_eq!(ButtonMap::default().layout, ButtonLayout::RawIndex);
+    }
 }
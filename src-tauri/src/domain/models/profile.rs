@@ -1,9 +1,11 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use super::{
-    AppConfig, ButtonMap, SwitchData, SwitchHistoryEntry, SessionRecord, LogicalKey,
+    ActiveSession, AlertLog, AppConfig, ButtonMap, CounterSnapshot, GameStats, SwitchData, SwitchHistoryEntry, SessionRecord,
+    SessionTimeline, LogicalKey, MAX_RECENT_SESSIONS,
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -20,8 +22,19 @@ pub struct ControllerProfile {
     pub switches: HashMap<LogicalKey, SwitchData>,
     #[serde(default)]
     pub switch_history: Vec<SwitchHistoryEntry>,
+    /// Totals per `GameDefinition::id`, kept beyond `session_history`.
     #[serde(default)]
-    pub recent_sessions: Vec<SessionRecord>,
+    pub game_stats: BTreeMap<String, GameStats>,
+    /// Every recorded session, oldest first. Only the newest `MAX_RECENT_SESSIONS` keep
+    /// their timeline.
+    #[serde(default)]
+    pub session_history: Vec<SessionRecord>,
+    /// Periodic per-key totals, oldest first, taken when they changed.
+    #[serde(default)]
+    pub counter_snapshots: Vec<CounterSnapshot>,
+    /// Timeline of the running session, moved into its `SessionRecord` when it ends.
+    #[serde(default)]
+    pub session_timeline: SessionTimeline,
 }
 
 impl Default for ControllerProfile {
@@ -30,12 +43,37 @@ impl Default for ControllerProfile {
             mapping: ButtonMap::default(),
             switches: HashMap::new(),
             switch_history: Vec::new(),
-            recent_sessions: Vec::new(),
+            game_stats: BTreeMap::new(),
+            session_history: Vec::new(),
+            counter_snapshots: Vec::new(),
+            session_timeline: SessionTimeline::default(),
         }
     }
 }
 
+impl ControllerProfile {
+    /// The newest `MAX_RECENT_SESSIONS` sessions, oldest first.
+    pub fn recent_sessions(&self) -> &[SessionRecord] {
+        let start = self.session_history.len().saturating_sub(MAX_RECENT_SESSIONS);
+        &self.session_history[start..]
+    }
+}
+
+/// Why the active controller changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ControllerSwitchReason {
+    Manual,
+    AutoConnect,
+}
 
+/// A change of the active controller profile.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControllerSwitchEntry {
+    pub date: DateTime<Utc>,
+    pub from_controller_id: String,
+    pub to_controller_id: String,
+    pub reason: ControllerSwitchReason,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserProfile {
@@ -45,6 +83,14 @@ pub struct UserProfile {
     pub active_controller_id: String,
     #[serde(default)]
     pub controllers: HashMap<String, ControllerProfile>,
+    #[serde(default)]
+    pub controller_switch_history: Vec<ControllerSwitchEntry>,
+    /// The running session, saved with the profile so it survives a crash.
+    #[serde(default)]
+    pub active_session: Option<ActiveSession>,
+    /// Active and recently resolved alerts, with their acknowledgements.
+    #[serde(default)]
+    pub alerts: AlertLog,
 }
 
 impl Default for UserProfile {
@@ -58,6 +104,9 @@ impl Default for UserProfile {
             config: AppConfig::default(),
             active_controller_id: default_id,
             controllers,
+            controller_switch_history: Vec::new(),
+            active_session: None,
+            alerts: AlertLog::default(),
         }
     }
 }
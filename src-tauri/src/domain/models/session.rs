@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 
@@ -22,10 +23,235 @@ pub struct SessionRecord {
     pub duration_secs: u64,
     #[serde(default)]
     pub stats: HashMap<LogicalKey, SessionKeyStats>,
+    /// `GameDefinition::id` of the game that was running.
+    #[serde(default)]
+    pub game_id: Option<String>,
+    #[serde(default)]
+    pub started_by: SessionOrigin,
+    /// The game exited and was restarted within the grace period, so the record covers
+    /// more than one run.
+    #[serde(default)]
+    pub merged: bool,
+    #[serde(default, skip_serializing_if = "SessionTimeline::is_empty")]
+    pub timeline: SessionTimeline,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// What started a session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SessionOrigin {
+    /// A game process was detected.
+    #[default]
+    Process,
+    /// The start session command.
+    Manual,
+    /// The controller button combo from `AppConfig::session_hotkey`.
+    Hotkey,
+}
+
+/// What happens to a running session when the computer goes to sleep.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SleepAction {
+    /// End the session when the computer went to sleep.
+    #[default]
+    End,
+    /// Keep the session running after wake, leaving the time asleep out of it.
+    Pause,
+}
+
+/// A session that has started and not ended yet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActiveSession {
+    pub start_time: DateTime<Utc>,
+    pub game_id: Option<String>,
+    pub started_by: SessionOrigin,
+    /// Input is not counted into the session while paused.
+    pub paused: bool,
+    /// When the current pause began.
+    #[serde(default)]
+    pub paused_at: Option<DateTime<Utc>>,
+    /// Length of the pauses that already ended.
+    #[serde(default)]
+    pub paused_secs: u64,
+    /// When the game exited. The session ends here unless the game is back within the
+    /// grace period.
+    #[serde(default)]
+    pub exited_at: Option<DateTime<Utc>>,
+    /// The game restarted during the grace period at least once.
+    #[serde(default)]
+    pub merged: bool,
+    /// Last time a key was held during the session. A session interrupted by a crash is
+    /// closed here.
+    #[serde(default)]
+    pub last_activity_at: Option<DateTime<Utc>>,
+}
+
+impl ActiveSession {
+    pub fn new(start_time: DateTime<Utc>, game_id: Option<String>, started_by: SessionOrigin) -> Self {
+        Self {
+            start_time,
+            game_id,
+            started_by,
+            paused: false,
+            paused_at: None,
+            paused_secs: 0,
+            exited_at: None,
+            merged: false,
+            last_activity_at: None,
+        }
+    }
+
+    /// Pause or resume the session at `now`. Returns false if it already was in that state.
+    pub fn set_paused(&mut self, paused: bool, now: DateTime<Utc>) -> bool {
+        if self.paused == paused {
+            return false;
+        }
+        self.paused = paused;
+        if paused {
+            self.paused_at = Some(now);
+        } else if let Some(paused_at) = self.paused_at.take() {
+            self.paused_secs += (now - paused_at).num_seconds().max(0) as u64;
+        }
+        true
+    }
+
+    /// Seconds the session ran until `end_time`, not counting the time it was paused.
+    pub fn duration_secs(&self, end_time: DateTime<Utc>) -> u64 {
+        let elapsed = (end_time - self.start_time).num_seconds().max(0) as u64;
+        let pausing = self.paused_at.map_or(0, |at| (end_time - at).num_seconds().max(0) as u64);
+        elapsed.saturating_sub(self.paused_secs + pausing)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SessionKeyStats {
     pub presses: u64,
     pub chatters: u64,
 }
+
+/// Number of the newest `ControllerProfile::session_history` records that keep their timeline.
+pub const MAX_RECENT_SESSIONS: usize = 10;
+
+/// Number of buckets a timeline keeps. Longer sessions merge neighbouring buckets.
+pub const MAX_TIMELINE_BUCKETS: usize = 240;
+
+const DEFAULT_TIMELINE_BUCKET_SECS: u64 = 60;
+
+/// Presses and chatters per key over the course of a session, in fixed-width buckets.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionTimeline {
+    /// Width of a bucket: a minute, doubled each time the session outgrows
+    /// `MAX_TIMELINE_BUCKETS`.
+    pub bucket_secs: u64,
+    /// Bucket `i` covers `[i * bucket_secs, (i + 1) * bucket_secs)` from the session start.
+    #[serde_as(as = "Vec<HashMap<serde_with::DisplayFromStr, _>>")]
+    pub buckets: Vec<HashMap<LogicalKey, SessionKeyStats>>,
+    /// Session counters already put into a bucket. Only kept while the session runs.
+    #[serde_as(as = "HashMap<serde_with::DisplayFromStr, _>")]
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub counted: HashMap<LogicalKey, SessionKeyStats>,
+}
+
+impl Default for SessionTimeline {
+    fn default() -> Self {
+        Self {
+            bucket_secs: DEFAULT_TIMELINE_BUCKET_SECS,
+            buckets: Vec::new(),
+            counted: HashMap::new(),
+        }
+    }
+}
+
+impl SessionTimeline {
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty() && self.counted.is_empty()
+    }
+
+    /// Put what a key's session counters grew by since the last call into the bucket
+    /// containing `offset_secs`.
+    pub fn record(&mut self, key: &LogicalKey, session_stats: &SessionKeyStats, offset_secs: u64) {
+        let counted = self.counted.entry(key.clone()).or_default();
+        let presses = session_stats.presses.saturating_sub(counted.presses);
+        let chatters = session_stats.chatters.saturating_sub(counted.chatters);
+        // Follow the counters down as well, e.g. after the key's stats were reset
+        *counted = session_stats.clone();
+        if presses == 0 && chatters == 0 {
+            return;
+        }
+
+        let bucket = self.bucket_mut(offset_secs).entry(key.clone()).or_default();
+        bucket.presses += presses;
+        bucket.chatters += chatters;
+    }
+
+    fn bucket_mut(&mut self, offset_secs: u64) -> &mut HashMap<LogicalKey, SessionKeyStats> {
+        if self.bucket_secs == 0 {
+            self.bucket_secs = DEFAULT_TIMELINE_BUCKET_SECS;
+        }
+        while offset_secs / self.bucket_secs >= MAX_TIMELINE_BUCKETS as u64 {
+            self.coarsen();
+        }
+        let index = (offset_secs / self.bucket_secs) as usize;
+        if self.buckets.len() <= index {
+            self.buckets.resize_with(index + 1, HashMap::new);
+        }
+        &mut self.buckets[index]
+    }
+
+    /// Halve the resolution by merging each pair of neighbouring buckets.
+    fn coarsen(&mut self) {
+        self.bucket_secs *= 2;
+        let buckets = std::mem::take(&mut self.buckets);
+        for (index, bucket) in buckets.into_iter().enumerate() {
+            if index % 2 == 0 {
+                self.buckets.push(bucket);
+                continue;
+            }
+            let merged = self.buckets.last_mut().expect("even bucket pushed first");
+            for (key, stats) in bucket {
+                let entry = merged.entry(key).or_default();
+                entry.presses += stats.presses;
+                entry.chatters += stats.chatters;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(presses: u64, chatters: u64) -> SessionKeyStats {
+        SessionKeyStats { presses, chatters }
+    }
+
+    #[test]
+    fn test_timeline_buckets_counter_growth_by_minute() {
+        let mut timeline = SessionTimeline::default();
+        timeline.record(&LogicalKey::Key1, &stats(5, 0), 10);
+        timeline.record(&LogicalKey::Key1, &stats(8, 1), 59);
+        timeline.record(&LogicalKey::Key1, &stats(8, 1), 100);
+        timeline.record(&LogicalKey::Key1, &stats(10, 1), 185);
+
+        assert_eq!(timeline.buckets.len(), 4);
+        assert_eq!(timeline.buckets[0][&LogicalKey::Key1], stats(8, 1));
+        assert!(timeline.buckets[1].is_empty());
+        assert!(timeline.buckets[2].is_empty());
+        assert_eq!(timeline.buckets[3][&LogicalKey::Key1], stats(2, 0));
+    }
+
+    #[test]
+    fn test_long_timeline_stays_bounded() {
+        let mut timeline = SessionTimeline::default();
+        let minutes = MAX_TIMELINE_BUCKETS as u64 * 3;
+        for minute in 0..minutes {
+            timeline.record(&LogicalKey::Key2, &stats(minute + 1, 0), minute * 60);
+        }
+
+        assert!(timeline.buckets.len() <= MAX_TIMELINE_BUCKETS);
+        assert_eq!(timeline.bucket_secs, 240);
+        assert_eq!(timeline.buckets[0][&LogicalKey::Key2].presses, 4);
+        let total: u64 = timeline.buckets.iter().map(|b| b[&LogicalKey::Key2].presses).sum();
+        assert_eq!(total, minutes);
+    }
+}
@@ -1,7 +1,11 @@
 use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
 use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+use super::{LogicalKey, SessionKeyStats};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct ButtonStats {
     pub total_presses: u64,
     pub total_releases: u64,
@@ -22,7 +26,7 @@ impl ButtonStats {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SwitchData {
     pub switch_model_id: String,
     pub stats: ButtonStats,
@@ -30,6 +34,17 @@ pub struct SwitchData {
     pub last_replaced_at: Option<DateTime<Utc>>,
 }
 
+/// Lifetime totals of every key at one point in time. The growth between consecutive
+/// snapshots shows when presses happened, also outside sessions.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CounterSnapshot {
+    pub taken_at: DateTime<Utc>,
+    /// `total_presses` and `total_chatters` per key.
+    #[serde_as(as = "HashMap<serde_with::DisplayFromStr, _>")]
+    pub totals: HashMap<LogicalKey, SessionKeyStats>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SwitchModelInfo {
     pub id: String,
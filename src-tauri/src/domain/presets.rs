@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use crate::domain::models::{ButtonMap, LogicalKey};
+use crate::domain::models::{ButtonLayout, ButtonMap, LogicalKey};
 
 /// Generates a ButtonMap for the Official Controller.
 ///
@@ -30,6 +30,7 @@ pub fn get_official_controller_map() -> ButtonMap {
     ButtonMap {
         profile_name: "Official Controller".to_string(),
         bindings,
+        layout: ButtonLayout::RawIndex,
     }
 }
 
@@ -47,6 +48,19 @@ pub fn get_phoenix_wan_map() -> ButtonMap {
     map
 }
 
+/// Picks a preset for a newly seen controller based on its device name.
+/// Unknown devices get the default mapping.
+pub fn preset_for_device_name(name: &str) -> ButtonMap {
+    let name = name.to_lowercase();
+    if name.contains("phoenixwan") {
+        get_phoenix_wan_map()
+    } else if ["konami", "iidx", "beatmania"].iter().any(|n| name.contains(n)) {
+        get_official_controller_map()
+    } else {
+        ButtonMap::default()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -77,4 +91,11 @@ mod tests {
         // Inherits bindings
         assert_eq!(map.bindings.get(&LogicalKey::Key1), Some(&1));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_preset_for_device_name() {
+        assert_eq!(preset_for_device_name("PHOENIXWAN+").profile_name, "PhoenixWAN");
+        assert_eq!(preset_for_device_name("KONAMI IIDX Entry Model").profile_name, "Official Controller");
+        assert_eq!(preset_for_device_name("Generic USB Joystick").profile_name, "Default");
+    }
+}
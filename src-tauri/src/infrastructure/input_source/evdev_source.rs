@@ -0,0 +1,526 @@
+use log::warn;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Read};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::domain::errors::InputError;
+use crate::domain::interfaces::{unix_time_ms, InputSnapshot, InputSource};
+use crate::domain::models::ControllerInfo;
+
+use super::raw_buttons::{evdev_button_bits, ButtonEventQueue};
+
+const SYS_INPUT_DIR: &str = "/sys/class/input";
+const DEV_INPUT_DIR: &str = "/dev/input";
+/// How often the device list is refreshed while polling.
+const RESCAN_INTERVAL: Duration = Duration::from_secs(2);
+
+const EV_KEY: u16 = 0x01;
+/// Highest key code (`KEY_MAX`), used to size the `EVIOCGKEY` buffer.
+const KEY_MAX: usize = 0x2ff;
+/// Size of `struct input_event`: a `timeval` followed by type, code and value.
+const INPUT_EVENT_SIZE: usize = std::mem::size_of::<libc::input_event>();
+
+/// One `struct input_event` as read from an evdev node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawInputEvent {
+    /// Kernel timestamp (CLOCK_REALTIME) in Unix milliseconds.
+    pub timestamp_ms: u64,
+    pub kind: u16,
+    pub code: u16,
+    pub value: i32,
+}
+
+/// How `EV_KEY` codes become bits of the button bitmask.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvdevKeyMap {
+    /// Joystick/gamepad buttons by raw HID index, see `EvdevDeviceInfo::button_bits`.
+    Buttons,
+    /// Only the listed key codes (code to bit), e.g. keys of a keyboard-emulating controller.
+    Table(HashMap<u16, u32>),
+}
+
+impl EvdevKeyMap {
+    fn bit(&self, info: &EvdevDeviceInfo, code: u16) -> Option<u32> {
+        match self {
+            Self::Buttons => info.button_bits.get(&code).copied(),
+            Self::Table(table) => table.get(&code).copied(),
+        }
+    }
+
+    /// Prefix of device ids, so a device read as a keyboard gets its own profile.
+    fn id_prefix(&self) -> &'static str {
+        match self {
+            Self::Buttons => "",
+            Self::Table(_) => "kbd:",
+        }
+    }
+}
+
+/// An evdev node that exposes the keys of the active `EvdevKeyMap`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvdevDeviceInfo {
+    /// `vendor:product:phys` (prefixed with `kbd:` for key tables), stable across reboots
+    /// as long as the port does not change.
+    pub id: String,
+    pub name: String,
+    pub event_path: PathBuf,
+    /// Bit of each of the device's button codes, for `EvdevKeyMap::Buttons`.
+    pub button_bits: HashMap<u16, u32>,
+}
+
+/// Native Linux input source reading `/dev/input/event*` directly.
+///
+/// By default bits follow the raw HID button index (like `ButtonLayout::RawIndex`); with
+/// `EvdevKeyMap::Table` only the listed keys are read. Events carry the kernel timestamps.
+/// Devices are ordered by id, so `controller_index` does not depend on enumeration order.
+/// Reading the nodes requires membership in the `input` group.
+pub struct EvdevInputSource {
+    sys_dir: PathBuf,
+    dev_dir: PathBuf,
+    devices: Vec<EvdevDeviceInfo>,
+    last_scan: Option<Instant>,
+    key_map: EvdevKeyMap,
+    // Opened nodes by path, so several controllers can be polled side by side
+    open_devices: HashMap<PathBuf, EvdevDevice<File>>,
+    // Avoid logging the same permission error on every poll
+    last_open_error: Option<PathBuf>,
+}
+
+impl Default for EvdevInputSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EvdevInputSource {
+    pub fn new() -> Self {
+        Self::with_paths(SYS_INPUT_DIR, DEV_INPUT_DIR)
+    }
+
+    pub fn with_paths(sys_dir: impl Into<PathBuf>, dev_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            sys_dir: sys_dir.into(),
+            dev_dir: dev_dir.into(),
+            devices: Vec::new(),
+            last_scan: None,
+            key_map: EvdevKeyMap::Buttons,
+            open_devices: HashMap::new(),
+            last_open_error: None,
+        }
+    }
+
+    /// Change which key codes are read. Devices are rescanned, since which nodes
+    /// qualify depends on the map.
+    pub fn set_key_map(&mut self, key_map: EvdevKeyMap) {
+        if key_map != self.key_map {
+            self.key_map = key_map;
+            self.open_devices.clear();
+            self.last_scan = None;
+        }
+    }
+
+    fn rescan_if_due(&mut self) {
+        let due = self.last_scan.is_none_or(|t| t.elapsed() >= RESCAN_INTERVAL);
+        if due {
+            self.rescan();
+        }
+    }
+
+    fn rescan(&mut self) {
+        self.devices = scan_devices(&self.sys_dir, &self.dev_dir, &self.key_map);
+        self.last_scan = Some(Instant::now());
+        let devices = &self.devices;
+        self.open_devices
+            .retain(|_, open| devices.contains(&open.info));
+    }
+
+    fn open(&mut self, info: &EvdevDeviceInfo) -> Option<EvdevDevice<File>> {
+        let result = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(&info.event_path);
+        match result {
+            Ok(file) => {
+                self.last_open_error = None;
+                let initial = read_pressed_buttons(&file, info, &self.key_map).unwrap_or(0);
+                Some(EvdevDevice::new(info.clone(), file, initial))
+            }
+            Err(e) => {
+                if self.last_open_error.as_ref() != Some(&info.event_path) {
+                    warn!("Cannot open {:?} ({}): {}", info.event_path, info.name, e);
+                    self.last_open_error = Some(info.event_path.clone());
+                }
+                None
+            }
+        }
+    }
+}
+
+impl InputSource for EvdevInputSource {
+    fn get_state(&mut self, controller_index: u32) -> Result<u32, InputError> {
+        Ok(self
+            .poll_events(controller_index)?
+            .last()
+            .map(|s| s.buttons)
+            .unwrap_or(0))
+    }
+
+    fn poll_events(&mut self, controller_index: u32) -> Result<Vec<InputSnapshot>, InputError> {
+        self.rescan_if_due();
+
+        let Some(info) = self.devices.get(controller_index as usize).cloned() else {
+            return Err(InputError::Disconnected);
+        };
+
+        // The node may now belong to a different device after a replug
+        if self.open_devices.get(&info.event_path).is_some_and(|d| d.info != info) {
+            self.open_devices.remove(&info.event_path);
+        }
+        if !self.open_devices.contains_key(&info.event_path) {
+            let device = self.open(&info).ok_or(InputError::Disconnected)?;
+            self.open_devices.insert(info.event_path.clone(), device);
+        }
+        let device = self.open_devices.get_mut(&info.event_path).unwrap();
+
+        match device.poll(unix_time_ms(std::time::SystemTime::now()), &self.key_map) {
+            Ok(snapshots) => Ok(snapshots),
+            Err(e) => {
+                // ENODEV after unplugging; rescan on the next poll
+                warn!("Lost evdev device {:?}: {}", info.event_path, e);
+                self.open_devices.remove(&info.event_path);
+                self.last_scan = None;
+                Err(InputError::Disconnected)
+            }
+        }
+    }
+
+    fn enumerate_controllers(&mut self) -> Result<Vec<ControllerInfo>, InputError> {
+        self.rescan();
+        Ok(self
+            .devices
+            .iter()
+            .map(|d| ControllerInfo {
+                id: d.id.clone(),
+                name: d.name.clone(),
+            })
+            .collect())
+    }
+
+    fn find_controller_index(&mut self, controller_id: &str) -> Option<u32> {
+        // Uses the periodically refreshed list instead of rescanning sysfs on every poll
+        self.rescan_if_due();
+        self.devices.iter().position(|d| d.id == controller_id).map(|i| i as u32)
+    }
+}
+
+/// An opened evdev node. Generic over the reader so tests can feed a fake event stream.
+struct EvdevDevice<R> {
+    info: EvdevDeviceInfo,
+    reader: R,
+    queue: ButtonEventQueue,
+    // Bytes of an event that was only partially read
+    partial: Vec<u8>,
+}
+
+impl<R: Read> EvdevDevice<R> {
+    fn new(info: EvdevDeviceInfo, reader: R, initial_buttons: u32) -> Self {
+        let mut queue = ButtonEventQueue::default();
+        queue.drain(initial_buttons, 0);
+        Self {
+            info,
+            reader,
+            queue,
+            partial: Vec::new(),
+        }
+    }
+
+    /// Read everything available without blocking and return the resulting snapshots.
+    fn poll(&mut self, now_ms: u64, key_map: &EvdevKeyMap) -> std::io::Result<Vec<InputSnapshot>> {
+        let mut buf = [0u8; INPUT_EVENT_SIZE * 64];
+        loop {
+            match self.reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => self.partial.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        let complete = self.partial.len() - self.partial.len() % INPUT_EVENT_SIZE;
+        for event in parse_input_events(&self.partial[..complete]) {
+            // value 2 is autorepeat
+            if event.kind != EV_KEY || event.value > 1 {
+                continue;
+            }
+            if let Some(bit) = key_map.bit(&self.info, event.code) {
+                self.queue.apply(bit, event.value == 1, event.timestamp_ms);
+            }
+        }
+        self.partial.drain(..complete);
+
+        let current = self.queue.buttons();
+        Ok(self.queue.drain(current, now_ms))
+    }
+}
+
+/// Decode consecutive `struct input_event` records in native layout.
+/// Trailing bytes that do not form a whole event are ignored.
+pub fn parse_input_events(bytes: &[u8]) -> Vec<RawInputEvent> {
+    const LONG: usize = std::mem::size_of::<libc::c_long>();
+    let read_long = |b: &[u8]| -> i64 {
+        if LONG == 8 {
+            i64::from_ne_bytes(b[..8].try_into().unwrap())
+        } else {
+            i64::from(i32::from_ne_bytes(b[..4].try_into().unwrap()))
+        }
+    };
+
+    bytes
+        .chunks_exact(INPUT_EVENT_SIZE)
+        .map(|chunk| {
+            let sec = read_long(&chunk[0..]);
+            let usec = read_long(&chunk[LONG..]);
+            let rest = &chunk[INPUT_EVENT_SIZE - 8..];
+            RawInputEvent {
+                timestamp_ms: (sec.max(0) as u64) * 1000 + (usec.max(0) as u64) / 1000,
+                kind: u16::from_ne_bytes([rest[0], rest[1]]),
+                code: u16::from_ne_bytes([rest[2], rest[3]]),
+                value: i32::from_ne_bytes([rest[4], rest[5], rest[6], rest[7]]),
+            }
+        })
+        .collect()
+}
+
+/// List evdev nodes under `sys_dir` that report any key of `key_map`, sorted by id.
+pub fn scan_devices(sys_dir: &Path, dev_dir: &Path, key_map: &EvdevKeyMap) -> Vec<EvdevDeviceInfo> {
+    let Ok(entries) = fs::read_dir(sys_dir) else {
+        return Vec::new();
+    };
+
+    let mut devices: Vec<EvdevDeviceInfo> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let node = entry.file_name().to_string_lossy().to_string();
+            if !node.starts_with("event") {
+                return None;
+            }
+            let device_dir = entry.path().join("device");
+            let read = |name: &str| {
+                fs::read_to_string(device_dir.join(name))
+                    .map(|s| s.trim().to_string())
+                    .unwrap_or_default()
+            };
+
+            let has_key = key_capabilities(&read("capabilities/key"));
+            let button_bits = match key_map {
+                EvdevKeyMap::Buttons => evdev_button_bits(|code| has_key(code as usize)),
+                EvdevKeyMap::Table(_) => HashMap::new(),
+            };
+            let has_mapped_keys = match key_map {
+                EvdevKeyMap::Buttons => !button_bits.is_empty(),
+                EvdevKeyMap::Table(table) => table.keys().any(|&code| has_key(code as usize)),
+            };
+            if !has_mapped_keys {
+                return None;
+            }
+
+            let phys = read("phys");
+            let location = if phys.is_empty() { node.clone() } else { phys };
+            Some(EvdevDeviceInfo {
+                id: format!(
+                    "{}{}:{}:{}",
+                    key_map.id_prefix(),
+                    read("id/vendor"),
+                    read("id/product"),
+                    location
+                ),
+                name: read("name"),
+                event_path: dev_dir.join(&node),
+                button_bits,
+            })
+        })
+        .collect();
+
+    devices.sort_by(|a, b| a.id.cmp(&b.id).then_with(|| a.event_path.cmp(&b.event_path)));
+    devices
+}
+
+/// Whether a key code is set in `capabilities/key`, a list of hex words, most significant
+/// first, each the width of a `long`.
+fn key_capabilities(caps: &str) -> impl Fn(usize) -> bool {
+    let word_bits = 8 * std::mem::size_of::<libc::c_long>();
+    let words: Vec<u64> = caps
+        .split_whitespace()
+        .rev()
+        .map(|w| u64::from_str_radix(w, 16).unwrap_or(0))
+        .collect();
+
+    move |bit: usize| {
+        words
+            .get(bit / word_bits)
+            .is_some_and(|w| w & (1 << (bit % word_bits)) != 0)
+    }
+}
+
+/// Query keys that are already held down when the device is opened (`EVIOCGKEY`).
+fn read_pressed_buttons(file: &File, info: &EvdevDeviceInfo, key_map: &EvdevKeyMap) -> Option<u32> {
+    let mut keys = [0u8; KEY_MAX / 8 + 1];
+    // _IOC(_IOC_READ, 'E', 0x18, len)
+    let request = (2u64 << 30) | ((keys.len() as u64) << 16) | ((b'E' as u64) << 8) | 0x18;
+    let result = unsafe { libc::ioctl(file.as_raw_fd(), request as _, keys.as_mut_ptr()) };
+    if result < 0 {
+        return None;
+    }
+
+    let mut buttons = 0;
+    for code in 0..=KEY_MAX {
+        if keys[code / 8] & (1 << (code % 8)) != 0 {
+            if let Some(bit) = key_map.bit(info, code as u16) {
+                buttons |= 1 << bit;
+            }
+        }
+    }
+    Some(buttons)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encode(sec: i64, usec: i64, kind: u16, code: u16, value: i32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(sec as libc::c_long).to_ne_bytes());
+        bytes.extend_from_slice(&(usec as libc::c_long).to_ne_bytes());
+        bytes.extend_from_slice(&kind.to_ne_bytes());
+        bytes.extend_from_slice(&code.to_ne_bytes());
+        bytes.extend_from_slice(&value.to_ne_bytes());
+        bytes
+    }
+
+    /// A joystick with buttons 1-16 as BTN_TRIGGER.. and 17+ as BTN_TRIGGER_HAPPY.
+    fn device_with(stream: Vec<u8>) -> EvdevDevice<Cursor<Vec<u8>>> {
+        device_with_codes(stream, |c| (0x120..0x130).contains(&c) || (0x2c0..0x2c8).contains(&c))
+    }
+
+    fn device_with_codes(stream: Vec<u8>, has_code: impl Fn(u16) -> bool) -> EvdevDevice<Cursor<Vec<u8>>> {
+        let info = EvdevDeviceInfo {
+            id: "1ccf:8048:usb-0000:00:14.0-2/input0".to_string(),
+            name: "Fake IIDX".to_string(),
+            event_path: PathBuf::from("/dev/input/event7"),
+            button_bits: evdev_button_bits(has_code),
+        };
+        EvdevDevice::new(info, Cursor::new(stream), 0)
+    }
+
+    #[test]
+    fn test_parse_input_events() {
+        let bytes = encode(1_700_000_000, 250_000, EV_KEY, 0x120, 1);
+        let events = parse_input_events(&bytes);
+        assert_eq!(
+            events,
+            vec![RawInputEvent { timestamp_ms: 1_700_000_000_250, kind: EV_KEY, code: 0x120, value: 1 }]
+        );
+    }
+
+    #[test]
+    fn test_fake_stream_keeps_kernel_timestamps() {
+        let mut stream = Vec::new();
+        stream.extend(encode(10, 0, EV_KEY, 0x120, 1)); // button 1 down
+        stream.extend(encode(10, 0, 0, 0, 0)); // SYN_REPORT
+        stream.extend(encode(10, 40_000, EV_KEY, 0x120, 0));
+        stream.extend(encode(10, 42_500, EV_KEY, 0x120, 1)); // bounce 2.5ms later
+        stream.extend(encode(10, 43_000, EV_KEY, 0x2c1, 1)); // button 18
+        stream.extend(encode(10, 44_000, EV_KEY, 0x2c1, 2)); // autorepeat, ignored
+        stream.extend(encode(10, 44_000, EV_KEY, 0x1e, 1)); // KEY_A, ignored
+
+        let snapshots = device_with(stream).poll(20_000, &EvdevKeyMap::Buttons).unwrap();
+        let summary: Vec<(u32, u64)> = snapshots.iter().map(|s| (s.buttons, s.timestamp_ms)).collect();
+        assert_eq!(
+            summary,
+            vec![(0b1, 10_000), (0b0, 10_040), (0b1, 10_042), (0b1 | (1 << 17), 10_043)]
+        );
+    }
+
+    #[test]
+    fn test_partial_event_is_kept_for_next_poll() {
+        let event = encode(5, 0, EV_KEY, 0x120, 1);
+        let mut device = device_with(event[..10].to_vec());
+        assert_eq!(device.poll(100, &EvdevKeyMap::Buttons).unwrap(), vec![InputSnapshot { buttons: 0, timestamp_ms: 100 }]);
+
+        device.reader = Cursor::new(event[10..].to_vec());
+        assert_eq!(device.poll(200, &EvdevKeyMap::Buttons).unwrap(), vec![InputSnapshot { buttons: 1, timestamp_ms: 5_000 }]);
+    }
+
+    #[test]
+    fn test_buttons_from_several_blocks_keep_their_own_bits() {
+        let mut stream = Vec::new();
+        stream.extend(encode(1, 0, EV_KEY, 0x120, 1)); // BTN_TRIGGER
+        stream.extend(encode(1, 1_000, EV_KEY, 0x130, 1)); // BTN_SOUTH
+
+        let mut device = device_with_codes(stream, |c| c == 0x120 || c == 0x130);
+        let snapshots = device.poll(2_000, &EvdevKeyMap::Buttons).unwrap();
+        let buttons: Vec<u32> = snapshots.iter().map(|s| s.buttons).collect();
+        assert_eq!(buttons, vec![0b01, 0b11]);
+    }
+
+    #[test]
+    fn test_scan_devices_filters_and_orders() {
+        let sys = tempfile::tempdir().unwrap();
+        let add = |node: &str, vendor: &str, phys: &str, caps: &str| {
+            let dir = sys.path().join(node).join("device");
+            fs::create_dir_all(dir.join("id")).unwrap();
+            fs::create_dir_all(dir.join("capabilities")).unwrap();
+            fs::write(dir.join("name"), format!("Device {}\n", node)).unwrap();
+            fs::write(dir.join("phys"), format!("{}\n", phys)).unwrap();
+            fs::write(dir.join("id/vendor"), format!("{}\n", vendor)).unwrap();
+            fs::write(dir.join("id/product"), "8048\n").unwrap();
+            fs::write(dir.join("capabilities/key"), format!("{}\n", caps)).unwrap();
+        };
+        // Gamepad with BTN_TRIGGER..; buttons live in the 5th 64-bit word on 64-bit hosts
+        let joystick_caps = if std::mem::size_of::<libc::c_long>() == 8 {
+            "ffff00000000 0 0 0 0"
+        } else {
+            "ffff 0 0 0 0 0 0 0 0 0"
+        };
+        add("event9", "1ccf", "usb-2/input0", joystick_caps);
+        add("event3", "0a0a", "usb-1/input0", joystick_caps);
+        add("event1", "046d", "usb-3/input0", "1e"); // keyboard-only
+        fs::create_dir_all(sys.path().join("mouse0")).unwrap();
+
+        let devices = scan_devices(sys.path(), Path::new("/dev/input"), &EvdevKeyMap::Buttons);
+        let ids: Vec<&str> = devices.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, vec!["0a0a:8048:usb-1/input0", "1ccf:8048:usb-2/input0"]);
+        assert_eq!(devices[1].event_path, PathBuf::from("/dev/input/event9"));
+        assert_eq!(devices[1].name, "Device event9");
+        assert_eq!(devices[1].button_bits[&0x120], 0);
+        assert_eq!(devices[1].button_bits.len(), 16);
+
+        // KEY_1 (code 2) only exists on the keyboard
+        let keyboard = EvdevKeyMap::Table(HashMap::from([(2, 0)]));
+        let devices = scan_devices(sys.path(), Path::new("/dev/input"), &keyboard);
+        let ids: Vec<&str> = devices.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, vec!["kbd:046d:8048:usb-3/input0"]);
+    }
+
+    #[test]
+    fn test_key_table_maps_only_listed_keys() {
+        const KEY_Z: u16 = 44;
+        const KEY_S: u16 = 31;
+        let mut stream = Vec::new();
+        stream.extend(encode(1, 0, EV_KEY, KEY_Z, 1));
+        stream.extend(encode(1, 5_000, EV_KEY, KEY_S, 1));
+        stream.extend(encode(1, 6_000, EV_KEY, 0x120, 1)); // joystick button, not in the table
+        stream.extend(encode(1, 9_000, EV_KEY, KEY_Z, 0));
+
+        let key_map = EvdevKeyMap::Table(HashMap::from([(KEY_Z, 0), (KEY_S, 1)]));
+        let snapshots = device_with(stream).poll(2_000, &key_map).unwrap();
+        let buttons: Vec<u32> = snapshots.iter().map(|s| s.buttons).collect();
+        assert_eq!(buttons, vec![0b01, 0b11, 0b10]);
+    }
+}
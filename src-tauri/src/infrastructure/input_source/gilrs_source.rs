@@ -1,17 +1,89 @@
+use std::collections::HashMap;
+
 use crate::domain::errors::InputError;
-use crate::domain::interfaces::InputSource;
-use gilrs::{Gilrs, GamepadId};
+use crate::domain::interfaces::{unix_time_ms, InputSnapshot, InputSource};
+use crate::domain::models::{ButtonLayout, ControllerInfo};
+use gilrs::{Button, EventType, Gamepad, Gilrs, GamepadId};
+
+use super::raw_buttons::{evdev_key_bit, ButtonEventQueue};
 
 /// DirectInput/HID input source using the `gilrs` crate.
+///
+/// Button events are buffered per gamepad with their own timestamps, so edges that
+/// happen between two polls still reach the chatter detector in order.
+///
+/// With `ButtonLayout::RawIndex` bits come from the raw HID button codes, so bit N is
+/// physical button N + 1 regardless of how gilrs maps the device to a standard gamepad.
 pub struct GilrsInputSource {
     gilrs: Gilrs,
+    pending: HashMap<GamepadId, ButtonEventQueue>,
+    layout: ButtonLayout,
+    /// Controller id to index, rebuilt after gamepads connect or disconnect and on
+    /// enumeration. `None` until first needed or after a change.
+    controller_indices: Option<HashMap<String, u32>>,
 }
 
 impl GilrsInputSource {
     pub fn new() -> Self {
         Self {
             gilrs: Gilrs::new().unwrap(), // In a real app, handle error
+            pending: HashMap::new(),
+            layout: ButtonLayout::RawIndex,
+            controller_indices: None,
+        }
+    }
+
+    /// Move all queued gilrs events into the per-gamepad buffers.
+    fn pump_events(&mut self) {
+        while let Some(event) = self.gilrs.next_event() {
+            let (button, code, is_pressed) = match event.event {
+                EventType::ButtonPressed(button, code) => (button, code, true),
+                EventType::ButtonReleased(button, code) => (button, code, false),
+                EventType::Connected => {
+                    self.controller_indices = None;
+                    continue;
+                }
+                EventType::Disconnected => {
+                    self.pending.remove(&event.id);
+                    self.controller_indices = None;
+                    continue;
+                }
+                _ => continue,
+            };
+            let bit = match self.layout {
+                ButtonLayout::StandardButtons => standard_button_bit(button),
+                ButtonLayout::RawIndex => raw_button_bit(code.into_u32()),
+            };
+            if let Some(bit) = bit {
+                self.pending
+                    .entry(event.id)
+                    .or_default()
+                    .apply(bit, is_pressed, unix_time_ms(event.time));
+            }
+        }
+    }
+
+    /// Current button bitmask as tracked by gilrs.
+    fn sample_buttons(&self, gamepad_id: GamepadId) -> u32 {
+        let gamepad = self.gilrs.gamepad(gamepad_id);
+        let mut bitmap: u32 = 0;
+        match self.layout {
+            ButtonLayout::StandardButtons => {
+                for (btn, bit_pos) in BUTTON_BITS.iter() {
+                    if gamepad.is_pressed(*btn) {
+                        bitmap |= 1 << bit_pos;
+                    }
+                }
+            }
+            ButtonLayout::RawIndex => {
+                for (code, data) in gamepad.state().buttons() {
+                    if let Some(bit) = raw_button_bit(code.into_u32()).filter(|_| data.is_pressed()) {
+                        bitmap |= 1 << bit;
+                    }
+                }
+            }
         }
+        bitmap
     }
 
     /// Helper to map a stable index (user setting) to a runtime GamepadId
@@ -31,88 +103,181 @@ impl GilrsInputSource {
 
 impl InputSource for GilrsInputSource {
     fn get_state(&mut self, controller_index: u32) -> Result<u32, InputError> {
-        // 1. Pump events to update state
-        while let Some(_) = self.gilrs.next_event() {}
+        Ok(self
+            .poll_events(controller_index)?
+            .last()
+            .map(|s| s.buttons)
+            .unwrap_or(0))
+    }
+
+    fn poll_events(&mut self, controller_index: u32) -> Result<Vec<InputSnapshot>, InputError> {
+        self.pump_events();
 
-        // 2. Find target gamepad
         let gamepad_id = match self.get_gamepad_by_index(controller_index) {
             Some(id) => id,
             None => return Err(InputError::Disconnected),
         };
 
-        // 3. Get state
-        // Gilrs doesn't give a "bitmap" directly like XInput. We must construct it.
-        // Or we can map specific buttons.
-        // For IIDX controllers via generic HID, buttons are usually 0..N.
-        // We need to map these to our u16 bitmask.
-        //
-        // Assumption: The bitmask in this app is treated as "Bit N corresponds to Button N".
-        // XInput: wButtons (A=bit12, B=bit13 etc).
-        // HID: Generic buttons 1-16.
-        //
-        // To maintain compatibility, we should define a mapping.
-        // However, the architecture says "ButtonMap: Physical(Bitmask) -> LogicalKey".
-        // So as long as we produce a consistent Bitmask for the device, the user can remap it.
-        //
-        // Strategy: Map Gilrs buttons to bits.
-        // South -> Bit 0, East -> Bit 1... or just use raw button indices if available?
-        // Gilrs provides `is_pressed(Button)`.
-        // It also provides access to raw button code via `state().button_data(code)`.
-
-        let gamepad = self.gilrs.gamepad(gamepad_id);
-        let mut bitmap: u32 = 0;
+        // Gilrs doesn't give a "bitmap" directly like XInput, so events and the sampled
+        // state are both mapped to bits per `layout`. The user remaps bits via ButtonMap.
+        let sampled = self.sample_buttons(gamepad_id);
+        let now_ms = unix_time_ms(std::time::SystemTime::now());
+        Ok(self.pending.entry(gamepad_id).or_default().drain(sampled, now_ms))
+    }
 
-        // Try standard buttons first (common for HID mappings)
-        // This might need adjustment for specific controllers.
-        // DJ DAO usually maps to generic buttons.
-        // Let's iterate over a range of raw codes if possible, or standard buttons.
-        // Gilrs abstracts this.
-        //
-        // Critical: User says "DirectInput (HID Standard)".
-        // We should try to capture "all pressed buttons" into a bitmap.
-        // Since `gilrs` is high-level, let's check standard buttons.
-
-        use gilrs::Button;
-        let buttons = [
-            (Button::South, 0), (Button::East, 1), (Button::North, 2), (Button::West, 3),
-            (Button::C, 4), (Button::Z, 5), // 6-button pads
-            (Button::LeftTrigger, 6), (Button::LeftTrigger2, 7),
-            (Button::RightTrigger, 8), (Button::RightTrigger2, 9),
-            (Button::Select, 10), (Button::Start, 11),
-            (Button::Mode, 12), (Button::LeftThumb, 13), (Button::RightThumb, 14),
-            // dpad
-            (Button::DPadUp, 15), (Button::DPadDown, 16), (Button::DPadLeft, 17), (Button::DPadRight, 18),
-        ];
-
-        // Construct a u32 bitmap.
-        // Note: u16 is small for modern controllers (can have 32+ buttons).
-        // XInput wButtons is u16.
-        // If the user's controller has buttons mapping to indices > 15, we might lose them with u16.
-        // Architecture uses u16. For Rev 2, maybe we should have upgraded to u32/u64?
-        // For now, adhere to u16.
-
-        // Let's assume standard mapping for now.
-        // If the controller is generic, Gilrs might map Button 1 to South, Button 2 to East, etc.
-
-        for (btn, bit_pos) in buttons.iter() {
-            if *bit_pos < 32 && gamepad.is_pressed(*btn) {
-                bitmap |= 1 << bit_pos;
-            }
+    fn set_button_layout(&mut self, layout: ButtonLayout) {
+        if layout != self.layout {
+            // Buffered bits were numbered with the old layout
+            self.pending.clear();
+            self.layout = layout;
         }
+    }
 
-        Ok(bitmap)
+    fn enumerate_controllers(&mut self) -> Result<Vec<ControllerInfo>, InputError> {
+        let (uuids, names): (Vec<String>, Vec<String>) = self
+            .gilrs
+            .gamepads()
+            .map(|(_id, gamepad)| (controller_uuid(&gamepad), gamepad.name().to_string()))
+            .unzip();
+        let controllers: Vec<ControllerInfo> = unique_ids(uuids)
+            .into_iter()
+            .zip(names)
+            .map(|(id, name)| ControllerInfo { id, name })
+            .collect();
+        self.controller_indices = Some(index_by_id(&controllers));
+        Ok(controllers)
     }
 
-    fn enumerate_controllers(&mut self) -> Result<Vec<crate::domain::models::ControllerInfo>, InputError> {
-        let mut controllers = Vec::new();
-        for (_id, gamepad) in self.gilrs.gamepads() {
-            let uuid_bytes = gamepad.uuid();
-            let uuid_str = uuid_bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>();
-            controllers.push(crate::domain::models::ControllerInfo {
-                id: uuid_str,
-                name: gamepad.name().to_string(),
-            });
+    fn find_controller_index(&mut self, controller_id: &str) -> Option<u32> {
+        // Called on every poll, so only walk the gamepads again after they changed
+        if self.controller_indices.is_none() {
+            self.enumerate_controllers().ok()?;
         }
-        Ok(controllers)
+        self.controller_indices.as_ref()?.get(controller_id).copied()
+    }
+}
+
+/// SDL-style UUID of a gamepad in hex. Shared by every pad of the same model.
+pub(super) fn controller_uuid(gamepad: &Gamepad) -> String {
+    gamepad.uuid().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Controller ids for gamepad UUIDs in enumeration order.
+///
+/// The first pad with a UUID is identified by the UUID alone; further pads of the same
+/// model get their occurrence number appended (`<uuid>#2`, `<uuid>#3`, ...).
+pub(super) fn unique_ids(uuids: Vec<String>) -> Vec<String> {
+    let mut seen: HashMap<String, u32> = HashMap::new();
+    uuids
+        .into_iter()
+        .map(|uuid| {
+            let count = seen.entry(uuid.clone()).or_insert(0);
+            *count += 1;
+            if *count == 1 {
+                uuid
+            } else {
+                format!("{}#{}", uuid, count)
+            }
+        })
+        .collect()
+}
+
+/// Index of each controller id, in enumeration order.
+fn index_by_id(controllers: &[ControllerInfo]) -> HashMap<String, u32> {
+    controllers
+        .iter()
+        .enumerate()
+        .map(|(index, controller)| (controller.id.clone(), index as u32))
+        .collect()
+}
+
+/// Standard gilrs buttons and the bit each one occupies in the bitmask.
+const BUTTON_BITS: [(Button, u32); 19] = [
+    (Button::South, 0), (Button::East, 1), (Button::North, 2), (Button::West, 3),
+    (Button::C, 4), (Button::Z, 5), // 6-button pads
+    (Button::LeftTrigger, 6), (Button::LeftTrigger2, 7),
+    (Button::RightTrigger, 8), (Button::RightTrigger2, 9),
+    (Button::Select, 10), (Button::Start, 11),
+    (Button::Mode, 12), (Button::LeftThumb, 13), (Button::RightThumb, 14),
+    // dpad
+    (Button::DPadUp, 15), (Button::DPadDown, 16), (Button::DPadLeft, 17), (Button::DPadRight, 18),
+];
+
+fn standard_button_bit(button: Button) -> Option<u32> {
+    BUTTON_BITS.iter().find(|(b, _)| *b == button).map(|(_, bit)| *bit)
+}
+
+/// Maps a raw gilrs button code to its physical button index (0-based), per platform.
+fn raw_button_bit(raw_code: u32) -> Option<u32> {
+    #[cfg(target_os = "linux")]
+    let bit = evdev_button_bit(raw_code);
+    #[cfg(target_os = "windows")]
+    let bit = wgi_button_bit(raw_code);
+    #[cfg(target_os = "macos")]
+    let bit = hid_usage_button_bit(raw_code);
+    #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+    let bit = None;
+
+    bit.filter(|&b| b < 32)
+}
+
+/// Linux: `(EV_KEY << 16) | code`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn evdev_button_bit(raw_code: u32) -> Option<u32> {
+    const EV_KEY: u32 = 0x01;
+    if raw_code >> 16 != EV_KEY {
+        return None;
+    }
+    evdev_key_bit((raw_code & 0xffff) as u16)
+}
+
+/// Windows (Windows.Gaming.Input raw controller): `(kind << 16) | index`, buttons are kind 0.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn wgi_button_bit(raw_code: u32) -> Option<u32> {
+    (raw_code >> 16 == 0).then_some(raw_code & 0xffff)
+}
+
+/// macOS: `(usage_page << 16) | usage`, HID button usages are 1-based on page 0x09.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn hid_usage_button_bit(raw_code: u32) -> Option<u32> {
+    const PAGE_BUTTON: u32 = 0x09;
+    if raw_code >> 16 != PAGE_BUTTON {
+        return None;
+    }
+    (raw_code & 0xffff).checked_sub(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pads_sharing_a_uuid_get_distinct_ids() {
+        let ids = unique_ids(vec!["a".to_string(), "b".to_string(), "a".to_string(), "a".to_string()]);
+        assert_eq!(ids, ["a", "b", "a#2", "a#3"]);
+
+        let controllers: Vec<ControllerInfo> =
+            ids.into_iter().map(|id| ControllerInfo { id, name: String::new() }).collect();
+        let indices = index_by_id(&controllers);
+        assert_eq!(indices["a"], 0);
+        assert_eq!(indices["a#2"], 2);
+        assert_eq!(indices["a#3"], 3);
+    }
+
+    #[test]
+    fn test_evdev_raw_code_requires_ev_key() {
+        let key = |code: u32| (1 << 16) | code;
+        assert_eq!(evdev_button_bit(key(0x130)), Some(0)); // BTN_SOUTH
+        assert_eq!(evdev_button_bit(key(0x2c0)), Some(16)); // BTN_TRIGGER_HAPPY1
+        assert_eq!(evdev_button_bit((3 << 16) | 0x120), None); // not EV_KEY
+    }
+
+    #[test]
+    fn test_wgi_and_hid_usage_codes() {
+        assert_eq!(wgi_button_bit(5), Some(5));
+        assert_eq!(wgi_button_bit((1 << 16) | 5), None); // axis
+        assert_eq!(hid_usage_button_bit((0x09 << 16) | 1), Some(0));
+        assert_eq!(hid_usage_button_bit((0x09 << 16) | 32), Some(31));
+        assert_eq!(hid_usage_button_bit((0x01 << 16) | 0x30), None);
     }
 }
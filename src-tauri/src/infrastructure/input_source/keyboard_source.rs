@@ -0,0 +1,184 @@
+use log::warn;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::domain::errors::InputError;
+use crate::domain::interfaces::{InputSnapshot, InputSource};
+use crate::domain::models::ControllerInfo;
+
+use super::evdev_source::{EvdevInputSource, EvdevKeyMap};
+
+/// Input source for keyboard-emulating controllers and hitbox-style boards.
+///
+/// Reads keyboards through evdev and sets the bits configured in
+/// `AppConfig::keyboard_bindings`; all other keys are ignored. Every keyboard that has
+/// one of the configured keys is listed, with a `kbd:` prefixed id.
+pub struct KeyboardInputSource {
+    inner: EvdevInputSource,
+}
+
+impl Default for KeyboardInputSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KeyboardInputSource {
+    /// Starts with an empty table; bindings arrive through `set_keyboard_bindings`.
+    pub fn new() -> Self {
+        let mut inner = EvdevInputSource::new();
+        inner.set_key_map(EvdevKeyMap::Table(HashMap::new()));
+        Self { inner }
+    }
+}
+
+impl InputSource for KeyboardInputSource {
+    fn get_state(&mut self, controller_index: u32) -> Result<u32, InputError> {
+        self.inner.get_state(controller_index)
+    }
+
+    fn poll_events(&mut self, controller_index: u32) -> Result<Vec<InputSnapshot>, InputError> {
+        self.inner.poll_events(controller_index)
+    }
+
+    fn set_keyboard_bindings(&mut self, bindings: &BTreeMap<String, u32>) {
+        self.inner.set_key_map(EvdevKeyMap::Table(resolve_key_bindings(bindings)));
+    }
+
+    fn enumerate_controllers(&mut self) -> Result<Vec<ControllerInfo>, InputError> {
+        self.inner.enumerate_controllers()
+    }
+
+    fn find_controller_index(&mut self, controller_id: &str) -> Option<u32> {
+        self.inner.find_controller_index(controller_id)
+    }
+}
+
+/// Turn key names into evdev codes. Unknown names and bits above 31 are skipped with a warning.
+pub fn resolve_key_bindings(bindings: &BTreeMap<String, u32>) -> HashMap<u16, u32> {
+    let mut table = HashMap::new();
+    for (name, &bit) in bindings {
+        match key_code(name) {
+            Some(code) if bit < 32 => {
+                table.insert(code, bit);
+            }
+            Some(_) => warn!("Keyboard binding {} uses bit {}, only 0-31 exist", name, bit),
+            None => warn!("Unknown keyboard key name: {}", name),
+        }
+    }
+    table
+}
+
+/// Linux key code for a key name as in `input-event-codes.h`, with or without the `KEY_`
+/// prefix and in any case (`Z`, `key_leftshift`). A decimal number is taken as the code itself.
+pub fn key_code(name: &str) -> Option<u16> {
+    let upper = name.trim().to_ascii_uppercase();
+    let name = upper.strip_prefix("KEY_").unwrap_or(&upper);
+    if let Ok(code) = name.parse::<u16>() {
+        // A lone digit is the digit key, not code 0-9
+        if name.len() > 1 {
+            return Some(code);
+        }
+    }
+
+    const LETTERS: [(char, u16); 26] = [
+        ('Q', 16), ('W', 17), ('E', 18), ('R', 19), ('T', 20), ('Y', 21), ('U', 22), ('I', 23),
+        ('O', 24), ('P', 25), ('A', 30), ('S', 31), ('D', 32), ('F', 33), ('G', 34), ('H', 35),
+        ('J', 36), ('K', 37), ('L', 38), ('Z', 44), ('X', 45), ('C', 46), ('V', 47), ('B', 48),
+        ('N', 49), ('M', 50),
+    ];
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if let Some(&(_, code)) = LETTERS.iter().find(|(letter, _)| *letter == c) {
+            return Some(code);
+        }
+        return match c {
+            '1'..='9' => Some(c as u16 - '1' as u16 + 2),
+            '0' => Some(11),
+            _ => None,
+        };
+    }
+
+    if let Some(n) = name.strip_prefix('F').and_then(|n| n.parse::<u16>().ok()) {
+        return match n {
+            1..=10 => Some(58 + n),
+            11 | 12 => Some(76 + n),
+            _ => None,
+        };
+    }
+    if let Some(n) = name.strip_prefix("KP").and_then(|n| n.parse::<u16>().ok()) {
+        const KEYPAD: [u16; 10] = [82, 79, 80, 81, 75, 76, 77, 71, 72, 73];
+        return KEYPAD.get(n as usize).copied();
+    }
+
+    let code = match name {
+        "ESC" => 1,
+        "MINUS" => 12,
+        "EQUAL" => 13,
+        "BACKSPACE" => 14,
+        "TAB" => 15,
+        "LEFTBRACE" => 26,
+        "RIGHTBRACE" => 27,
+        "ENTER" => 28,
+        "LEFTCTRL" => 29,
+        "SEMICOLON" => 39,
+        "APOSTROPHE" => 40,
+        "GRAVE" => 41,
+        "LEFTSHIFT" => 42,
+        "BACKSLASH" => 43,
+        "COMMA" => 51,
+        "DOT" => 52,
+        "SLASH" => 53,
+        "RIGHTSHIFT" => 54,
+        "KPASTERISK" => 55,
+        "LEFTALT" => 56,
+        "SPACE" => 57,
+        "CAPSLOCK" => 58,
+        "KPMINUS" => 74,
+        "KPPLUS" => 78,
+        "KPDOT" => 83,
+        "KPENTER" => 96,
+        "RIGHTCTRL" => 97,
+        "KPSLASH" => 98,
+        "RIGHTALT" => 100,
+        "HOME" => 102,
+        "UP" => 103,
+        "PAGEUP" => 104,
+        "LEFT" => 105,
+        "RIGHT" => 106,
+        "END" => 107,
+        "DOWN" => 108,
+        "PAGEDOWN" => 109,
+        "INSERT" => 110,
+        "DELETE" => 111,
+        _ => return None,
+    };
+    Some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_code_names() {
+        assert_eq!(key_code("Z"), Some(44));
+        assert_eq!(key_code("key_leftshift"), Some(42));
+        assert_eq!(key_code("1"), Some(2));
+        assert_eq!(key_code("0"), Some(11));
+        assert_eq!(key_code("F12"), Some(88));
+        assert_eq!(key_code("KP7"), Some(71));
+        assert_eq!(key_code("183"), Some(183));
+        assert_eq!(key_code("NOPE"), None);
+    }
+
+    #[test]
+    fn test_resolve_key_bindings_skips_invalid_entries() {
+        let bindings = BTreeMap::from([
+            ("Z".to_string(), 0),
+            ("S".to_string(), 1),
+            ("D".to_string(), 32),
+            ("WHAT".to_string(), 2),
+        ]);
+        assert_eq!(resolve_key_bindings(&bindings), HashMap::from([(44, 0), (31, 1)]));
+    }
+}
@@ -0,0 +1,357 @@
+use log::{info, warn};
+use midir::{MidiInput, MidiInputConnection};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::domain::errors::InputError;
+use crate::domain::interfaces::{unix_time_ms, InputSnapshot, InputSource};
+use crate::domain::models::{ControllerInfo, MidiConfig};
+
+use super::raw_buttons::ButtonEventQueue;
+
+const CLIENT_NAME: &str = "Switch Life Manager";
+const VIRTUAL_PORT_NAME: &str = "Switch Life Manager Input";
+const VIRTUAL_PORT_ID: &str = "midi:virtual";
+/// How often the port list is refreshed while polling.
+const RESCAN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Turns the MIDI messages of one port into button state changes.
+struct MidiButtonMapper {
+    config: MidiConfig,
+    // Turntable direction bit currently held, and when it is released
+    turntable_hold: Option<(u32, u64)>,
+    queue: ButtonEventQueue,
+}
+
+impl MidiButtonMapper {
+    fn new(config: MidiConfig) -> Self {
+        Self {
+            config,
+            turntable_hold: None,
+            queue: ButtonEventQueue::default(),
+        }
+    }
+
+    fn handle_message(&mut self, message: &[u8], timestamp_ms: u64) {
+        let [status, data1, data2, ..] = *message else {
+            return;
+        };
+        match status & 0xF0 {
+            0x90 if data2 > 0 => self.set_note(data1, true, timestamp_ms),
+            0x80 | 0x90 => self.set_note(data1, false, timestamp_ms),
+            0xB0 if Some(data1) == self.config.turntable_cc => self.turn(data2, timestamp_ms),
+            _ => {}
+        }
+    }
+
+    fn set_note(&mut self, note: u8, is_pressed: bool, timestamp_ms: u64) {
+        if let Some(&bit) = self.config.note_bits.get(&note) {
+            self.apply(bit, is_pressed, timestamp_ms);
+        }
+    }
+
+    /// Relative encoder: 1-63 turns one way, 65-127 the other.
+    fn turn(&mut self, value: u8, timestamp_ms: u64) {
+        let bit = match value {
+            1..=63 => self.config.turntable_up_bit,
+            65..=127 => self.config.turntable_down_bit,
+            _ => return,
+        };
+        if let Some((held, _)) = self.turntable_hold {
+            if held != bit {
+                self.apply(held, false, timestamp_ms);
+            }
+        }
+        self.apply(bit, true, timestamp_ms);
+        self.turntable_hold = Some((bit, timestamp_ms + self.config.turntable_hold_ms));
+    }
+
+    fn apply(&mut self, bit: u32, is_pressed: bool, timestamp_ms: u64) {
+        if bit < 32 {
+            self.queue.apply(bit, is_pressed, timestamp_ms);
+        }
+    }
+
+    /// Release the turntable once it stopped moving, then take the pending snapshots.
+    fn drain(&mut self, now_ms: u64) -> Vec<InputSnapshot> {
+        if let Some((bit, until)) = self.turntable_hold {
+            if now_ms >= until {
+                self.apply(bit, false, until);
+                self.turntable_hold = None;
+            }
+        }
+        let current = self.queue.buttons();
+        self.queue.drain(current, now_ms)
+    }
+}
+
+struct MidiPort {
+    id: String,
+    name: String,
+}
+
+/// A connected port. Closing happens when the connection is dropped.
+struct OpenPort {
+    mapper: Arc<Mutex<MidiButtonMapper>>,
+    _connection: MidiInputConnection<()>,
+}
+
+/// Input source for MIDI DJ controllers via `midir`.
+///
+/// Ports are listed with `midi:<port name>` ids. With `MidiConfig::virtual_port`, a virtual
+/// port named "Switch Life Manager Input" is listed as well (`midi:virtual`), so any software
+/// that sends MIDI can stand in for a controller.
+pub struct MidiInputSource {
+    config: MidiConfig,
+    ports: Vec<MidiPort>,
+    last_scan: Option<Instant>,
+    open_ports: HashMap<String, OpenPort>,
+    virtual_port: Option<OpenPort>,
+    // Avoid logging the same error on every poll
+    last_open_error: Option<String>,
+}
+
+impl Default for MidiInputSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MidiInputSource {
+    pub fn new() -> Self {
+        Self {
+            config: MidiConfig::default(),
+            ports: Vec::new(),
+            last_scan: None,
+            open_ports: HashMap::new(),
+            virtual_port: None,
+            last_open_error: None,
+        }
+    }
+
+    fn rescan_if_due(&mut self) {
+        if self.last_scan.is_none_or(|t| t.elapsed() >= RESCAN_INTERVAL) {
+            self.rescan();
+        }
+    }
+
+    fn rescan(&mut self) {
+        let mut ports = Vec::new();
+        match MidiInput::new(CLIENT_NAME) {
+            Ok(input) => {
+                for port in input.ports() {
+                    if let Ok(name) = input.port_name(&port) {
+                        ports.push(MidiPort { id: format!("midi:{}", name), name });
+                    }
+                }
+            }
+            Err(e) => warn!("Cannot list MIDI ports: {}", e),
+        }
+        ports.sort_by(|a, b| a.id.cmp(&b.id));
+        if self.virtual_port.is_some() {
+            ports.push(MidiPort {
+                id: VIRTUAL_PORT_ID.to_string(),
+                name: VIRTUAL_PORT_NAME.to_string(),
+            });
+        }
+
+        self.open_ports.retain(|id, _| ports.iter().any(|p| p.id == *id));
+        self.ports = ports;
+        self.last_scan = Some(Instant::now());
+    }
+
+    fn new_mapper(&self) -> Arc<Mutex<MidiButtonMapper>> {
+        Arc::new(Mutex::new(MidiButtonMapper::new(self.config.clone())))
+    }
+
+    fn open(&mut self, port_name: &str) -> Option<OpenPort> {
+        let mapper = self.new_mapper();
+        let result = MidiInput::new(CLIENT_NAME)
+            .map_err(|e| e.to_string())
+            .and_then(|input| {
+                let port = input
+                    .ports()
+                    .into_iter()
+                    .find(|p| input.port_name(p).is_ok_and(|name| name == port_name))
+                    .ok_or_else(|| "port vanished".to_string())?;
+                let callback_mapper = mapper.clone();
+                input
+                    .connect(&port, "switch-life-manager-in", move |_, message, _| {
+                        let now = unix_time_ms(SystemTime::now());
+                        if let Ok(mut mapper) = callback_mapper.lock() {
+                            mapper.handle_message(message, now);
+                        }
+                    }, ())
+                    .map_err(|e| e.to_string())
+            });
+
+        match result {
+            Ok(connection) => {
+                self.last_open_error = None;
+                Some(OpenPort { mapper, _connection: connection })
+            }
+            Err(e) => {
+                if self.last_open_error.as_deref() != Some(port_name) {
+                    warn!("Cannot open MIDI port {}: {}", port_name, e);
+                    self.last_open_error = Some(port_name.to_string());
+                }
+                None
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    fn open_virtual(&mut self) -> Option<OpenPort> {
+        use midir::os::unix::VirtualInput;
+
+        let mapper = self.new_mapper();
+        let callback_mapper = mapper.clone();
+        let result = MidiInput::new(CLIENT_NAME).map_err(|e| e.to_string()).and_then(|input| {
+            input
+                .create_virtual(VIRTUAL_PORT_NAME, move |_, message, _| {
+                    let now = unix_time_ms(SystemTime::now());
+                    if let Ok(mut mapper) = callback_mapper.lock() {
+                        mapper.handle_message(message, now);
+                    }
+                }, ())
+                .map_err(|e| e.to_string())
+        });
+        match result {
+            Ok(connection) => {
+                info!("Virtual MIDI port {} opened", VIRTUAL_PORT_NAME);
+                Some(OpenPort { mapper, _connection: connection })
+            }
+            Err(e) => {
+                warn!("Cannot create virtual MIDI port: {}", e);
+                None
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn open_virtual(&mut self) -> Option<OpenPort> {
+        warn!("Virtual MIDI ports are not supported on this platform");
+        None
+    }
+}
+
+impl InputSource for MidiInputSource {
+    fn get_state(&mut self, controller_index: u32) -> Result<u32, InputError> {
+        Ok(self
+            .poll_events(controller_index)?
+            .last()
+            .map(|s| s.buttons)
+            .unwrap_or(0))
+    }
+
+    fn poll_events(&mut self, controller_index: u32) -> Result<Vec<InputSnapshot>, InputError> {
+        self.rescan_if_due();
+
+        let Some(port) = self.ports.get(controller_index as usize) else {
+            return Err(InputError::Disconnected);
+        };
+        let (id, name) = (port.id.clone(), port.name.clone());
+
+        let open_port = if id == VIRTUAL_PORT_ID {
+            self.virtual_port.as_ref()
+        } else {
+            if !self.open_ports.contains_key(&id) {
+                let open_port = self.open(&name).ok_or(InputError::Disconnected)?;
+                self.open_ports.insert(id.clone(), open_port);
+            }
+            self.open_ports.get(&id)
+        };
+        let open_port = open_port.ok_or(InputError::Disconnected)?;
+
+        let now = unix_time_ms(SystemTime::now());
+        let mut mapper = open_port
+            .mapper
+            .lock()
+            .map_err(|_| InputError::Other(anyhow::anyhow!("MIDI mapper lock poisoned")))?;
+        Ok(mapper.drain(now))
+    }
+
+    fn set_midi_config(&mut self, config: &MidiConfig) {
+        if *config == self.config && self.last_scan.is_some() {
+            return;
+        }
+        self.config = config.clone();
+        // Reconnect so every port maps with the new table
+        self.open_ports.clear();
+        self.virtual_port = None;
+        if self.config.virtual_port {
+            self.virtual_port = self.open_virtual();
+        }
+        self.rescan();
+    }
+
+    fn enumerate_controllers(&mut self) -> Result<Vec<ControllerInfo>, InputError> {
+        self.rescan();
+        Ok(self
+            .ports
+            .iter()
+            .map(|p| ControllerInfo {
+                id: p.id.clone(),
+                name: p.name.clone(),
+            })
+            .collect())
+    }
+
+    fn find_controller_index(&mut self, controller_id: &str) -> Option<u32> {
+        self.rescan_if_due();
+        self.ports.iter().position(|p| p.id == controller_id).map(|i| i as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapper() -> MidiButtonMapper {
+        MidiButtonMapper::new(MidiConfig::default())
+    }
+
+    fn buttons(snapshots: &[InputSnapshot]) -> Vec<(u32, u64)> {
+        snapshots.iter().map(|s| (s.buttons, s.timestamp_ms)).collect()
+    }
+
+    #[test]
+    fn test_notes_map_to_bits_on_any_channel() {
+        let mut mapper = mapper();
+        mapper.handle_message(&[0x90, 48, 100], 10); // note 48 on, channel 1 -> Key1
+        mapper.handle_message(&[0x95, 49, 64], 12); // note 49 on, channel 6 -> Key2
+        mapper.handle_message(&[0x90, 48, 0], 20); // velocity 0 is a release
+        mapper.handle_message(&[0x85, 49, 0], 25); // note off
+        mapper.handle_message(&[0x90, 100, 100], 26); // unmapped note
+        mapper.handle_message(&[0xF8], 27); // clock
+
+        assert_eq!(
+            buttons(&mapper.drain(30)),
+            vec![(0b01, 10), (0b11, 12), (0b10, 20), (0b00, 25)]
+        );
+    }
+
+    #[test]
+    fn test_turntable_holds_direction_until_idle() {
+        let mut mapper = mapper();
+        let (up, down) = (1 << 12, 1 << 13);
+        mapper.handle_message(&[0xB0, 16, 1], 100);
+        mapper.handle_message(&[0xB0, 16, 2], 150); // keeps turning, hold extended
+        assert_eq!(buttons(&mapper.drain(200)), vec![(up, 100)]);
+
+        mapper.handle_message(&[0xB0, 16, 127], 220); // reversed
+        assert_eq!(buttons(&mapper.drain(250)), vec![(0, 220), (down, 220)]);
+
+        // Released at the end of the hold time, not at the poll time
+        assert_eq!(buttons(&mapper.drain(400)), vec![(0, 320)]);
+    }
+
+    #[test]
+    fn test_other_controllers_are_ignored() {
+        let mut mapper = mapper();
+        mapper.handle_message(&[0xB0, 17, 1], 100);
+        mapper.handle_message(&[0xB0, 16, 64], 100);
+        assert_eq!(buttons(&mapper.drain(200)), vec![(0, 200)]);
+    }
+}
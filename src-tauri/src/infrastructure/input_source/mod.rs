@@ -2,30 +2,51 @@
 //!
 //! - `GilrsInputSource` — DirectInput/HID via the `gilrs` crate (default)
 //! - `XInputSource` — Windows XInput (Windows only)
+//! - `EvdevInputSource` — native evdev `/dev/input/event*` reader (Linux only)
+//! - `KeyboardInputSource` — configured keyboard keys as buttons, via evdev (Linux only)
+//! - `MidiInputSource` — MIDI notes and turntable CC via `midir`
 //! - `MockInputSource` — Test mock (`#[cfg(test)]` only)
 //! - `DynamicInputSource` — Runtime-switchable enum wrapping the above
 
 mod gilrs_source;
+mod midi_source;
+mod raw_buttons;
 #[cfg(target_os = "windows")]
 mod xinput_source;
+#[cfg(target_os = "linux")]
+mod evdev_source;
+#[cfg(target_os = "linux")]
+mod keyboard_source;
 #[cfg(test)]
 mod mock_source;
 
 pub use gilrs_source::GilrsInputSource;
+pub use midi_source::MidiInputSource;
 #[cfg(target_os = "windows")]
 pub use xinput_source::XInputSource;
+#[cfg(target_os = "linux")]
+pub use evdev_source::EvdevInputSource;
+#[cfg(target_os = "linux")]
+pub use keyboard_source::KeyboardInputSource;
 #[cfg(test)]
 pub use mock_source::MockInputSource;
 
 use crate::domain::errors::InputError;
-use crate::domain::interfaces::InputSource;
-use crate::domain::models::InputMethod;
+use crate::domain::interfaces::{InputSnapshot, InputSource};
+use std::collections::BTreeMap;
+
+use crate::domain::models::{ButtonLayout, InputMethod, MidiConfig};
 
 /// Runtime-switchable input source that delegates to the appropriate backend.
 pub enum DynamicInputSource {
     #[cfg(target_os = "windows")]
     XInput(XInputSource),
     Gilrs(GilrsInputSource),
+    #[cfg(target_os = "linux")]
+    Evdev(EvdevInputSource),
+    #[cfg(target_os = "linux")]
+    Keyboard(KeyboardInputSource),
+    Midi(MidiInputSource),
     #[cfg(test)]
     Mock(MockInputSource),
 }
@@ -41,6 +62,15 @@ impl DynamicInputSource {
                  Self::Gilrs(GilrsInputSource::new())
             },
             InputMethod::DirectInput => Self::Gilrs(GilrsInputSource::new()),
+            #[cfg(target_os = "linux")]
+            InputMethod::Evdev => Self::Evdev(EvdevInputSource::new()),
+            #[cfg(not(target_os = "linux"))]
+            InputMethod::Evdev => Self::Gilrs(GilrsInputSource::new()),
+            #[cfg(target_os = "linux")]
+            InputMethod::Keyboard => Self::Keyboard(KeyboardInputSource::new()),
+            #[cfg(not(target_os = "linux"))]
+            InputMethod::Keyboard => Self::Gilrs(GilrsInputSource::new()),
+            InputMethod::Midi => Self::Midi(MidiInputSource::new()),
         }
     }
 
@@ -56,34 +86,104 @@ impl InputSource for DynamicInputSource {
             #[cfg(target_os = "windows")]
             Self::XInput(s) => s.get_state(controller_index),
             Self::Gilrs(s) => s.get_state(controller_index),
+            #[cfg(target_os = "linux")]
+            Self::Evdev(s) => s.get_state(controller_index),
+            #[cfg(target_os = "linux")]
+            Self::Keyboard(s) => s.get_state(controller_index),
+            Self::Midi(s) => s.get_state(controller_index),
             #[cfg(test)]
             Self::Mock(s) => s.get_state(controller_index),
         }
     }
 
+    fn poll_events(&mut self, controller_index: u32) -> Result<Vec<InputSnapshot>, InputError> {
+        match self {
+            #[cfg(target_os = "windows")]
+            Self::XInput(s) => s.poll_events(controller_index),
+            Self::Gilrs(s) => s.poll_events(controller_index),
+            #[cfg(target_os = "linux")]
+            Self::Evdev(s) => s.poll_events(controller_index),
+            #[cfg(target_os = "linux")]
+            Self::Keyboard(s) => s.poll_events(controller_index),
+            Self::Midi(s) => s.poll_events(controller_index),
+            #[cfg(test)]
+            Self::Mock(s) => s.poll_events(controller_index),
+        }
+    }
+
     fn set_input_method(&mut self, method: InputMethod) {
         self.switch_to(method);
     }
 
+    fn set_button_layout(&mut self, layout: ButtonLayout) {
+        match self {
+            #[cfg(target_os = "windows")]
+            Self::XInput(s) => s.set_button_layout(layout),
+            Self::Gilrs(s) => s.set_button_layout(layout),
+            // evdev always uses raw button indices
+            #[cfg(target_os = "linux")]
+            Self::Evdev(_) | Self::Keyboard(_) => {}
+            // MIDI bits come from MidiConfig
+            Self::Midi(_) => {}
+            #[cfg(test)]
+            Self::Mock(s) => s.set_button_layout(layout),
+        }
+    }
+
+    fn set_keyboard_bindings(&mut self, bindings: &BTreeMap<String, u32>) {
+        #[cfg(target_os = "linux")]
+        if let Self::Keyboard(s) = self {
+            s.set_keyboard_bindings(bindings);
+        }
+        #[cfg(not(target_os = "linux"))]
+        let _ = bindings;
+    }
+
+    fn set_midi_config(&mut self, config: &MidiConfig) {
+        if let Self::Midi(s) = self {
+            s.set_midi_config(config);
+        }
+    }
+
+    fn find_controller_index(&mut self, controller_id: &str) -> Option<u32> {
+        match self {
+            #[cfg(target_os = "windows")]
+            Self::XInput(_) => None, // XInput slots have no stable id
+            Self::Gilrs(s) => s.find_controller_index(controller_id),
+            #[cfg(target_os = "linux")]
+            Self::Evdev(s) => s.find_controller_index(controller_id),
+            #[cfg(target_os = "linux")]
+            Self::Keyboard(s) => s.find_controller_index(controller_id),
+            Self::Midi(s) => s.find_controller_index(controller_id),
+            #[cfg(test)]
+            Self::Mock(s) => s.find_controller_index(controller_id),
+        }
+    }
+
     fn enumerate_controllers(&mut self) -> Result<Vec<crate::domain::models::ControllerInfo>, InputError> {
         match self {
             #[cfg(target_os = "windows")]
             Self::XInput(_) => {
                 // For XInput, we'll instantiate a temporary Gilrs to enumerate all connected gamepads
                 // because XInput doesn't provide hardware UUIDs easily.
+                // Ids are built the same way as by the gilrs source, so a pad keeps its profile.
                 let temp_gilrs = gilrs::Gilrs::new().map_err(|_| InputError::Disconnected)?;
-                let mut controllers = Vec::new();
-                for (_id, gamepad) in temp_gilrs.gamepads() {
-                    let uuid_bytes = gamepad.uuid();
-                    let uuid_str = uuid_bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>();
-                    controllers.push(crate::domain::models::ControllerInfo {
-                        id: uuid_str,
-                        name: gamepad.name().to_string(),
-                    });
-                }
-                Ok(controllers)
+                let (uuids, names): (Vec<String>, Vec<String>) = temp_gilrs
+                    .gamepads()
+                    .map(|(_id, gamepad)| (gilrs_source::controller_uuid(&gamepad), gamepad.name().to_string()))
+                    .unzip();
+                Ok(gilrs_source::unique_ids(uuids)
+                    .into_iter()
+                    .zip(names)
+                    .map(|(id, name)| crate::domain::models::ControllerInfo { id, name })
+                    .collect())
             },
             Self::Gilrs(s) => s.enumerate_controllers(),
+            #[cfg(target_os = "linux")]
+            Self::Evdev(s) => s.enumerate_controllers(),
+            #[cfg(target_os = "linux")]
+            Self::Keyboard(s) => s.enumerate_controllers(),
+            Self::Midi(s) => s.enumerate_controllers(),
             #[cfg(test)]
             Self::Mock(s) => s.enumerate_controllers(),
         }
@@ -0,0 +1,194 @@
+//! Helpers shared by sources that build the button bitmask from raw button events.
+
+use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
+
+use crate::domain::interfaces::InputSnapshot;
+
+/// Upper bound of buffered snapshots per device, so devices that are never read
+/// (e.g. a second controller) cannot grow the buffer without limit.
+pub(super) const MAX_PENDING_SNAPSHOTS: usize = 1024;
+
+/// Button state changes of one device that have not been consumed yet.
+#[derive(Debug, Default)]
+pub(super) struct ButtonEventQueue {
+    buttons: u32,
+    snapshots: VecDeque<InputSnapshot>,
+}
+
+impl ButtonEventQueue {
+    pub(super) fn apply(&mut self, bit: u32, is_pressed: bool, timestamp_ms: u64) {
+        let buttons = if is_pressed {
+            self.buttons | (1 << bit)
+        } else {
+            self.buttons & !(1 << bit)
+        };
+        if buttons == self.buttons {
+            return;
+        }
+        self.buttons = buttons;
+        if self.snapshots.len() == MAX_PENDING_SNAPSHOTS {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(InputSnapshot { buttons, timestamp_ms });
+    }
+
+    /// Bitmask after all events applied so far.
+    pub(super) fn buttons(&self) -> u32 {
+        self.buttons
+    }
+
+    /// Take all buffered snapshots. `sampled` is the state reported by the device now;
+    /// if events were missed (e.g. before the first read) it is appended to resync.
+    pub(super) fn drain(&mut self, sampled: u32, now_ms: u64) -> Vec<InputSnapshot> {
+        let mut snapshots: Vec<InputSnapshot> = self.snapshots.drain(..).collect();
+        if snapshots.last().map(|s| s.buttons) != Some(sampled) {
+            snapshots.push(InputSnapshot { buttons: sampled, timestamp_ms: now_ms });
+        }
+        self.buttons = sampled;
+        snapshots
+    }
+}
+
+/// Maps a Linux `EV_KEY` code to its physical button index (0-based).
+///
+/// hid-input assigns HID buttons 1-16 to one of the BTN_MISC / BTN_JOYSTICK / BTN_GAMEPAD
+/// blocks (depending on the device type) and buttons 17+ to BTN_TRIGGER_HAPPY. The three
+/// blocks share bits 0-15, so this is only for gilrs, which does not tell which codes a
+/// device has; the evdev source uses `evdev_button_bits`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(super) fn evdev_key_bit(code: u16) -> Option<u32> {
+    const BTN_MISC: u16 = 0x100;
+    const BTN_JOYSTICK: u16 = 0x120;
+    const BTN_GAMEPAD: u16 = 0x130;
+    const BTN_TRIGGER_HAPPY: u16 = 0x2c0;
+
+    let bit = match code {
+        c if (BTN_MISC..BTN_MISC + 16).contains(&c) => c - BTN_MISC,
+        c if (BTN_JOYSTICK..BTN_JOYSTICK + 16).contains(&c) => c - BTN_JOYSTICK,
+        c if (BTN_GAMEPAD..BTN_GAMEPAD + 16).contains(&c) => c - BTN_GAMEPAD,
+        c if (BTN_TRIGGER_HAPPY..BTN_TRIGGER_HAPPY + 16).contains(&c) => 16 + c - BTN_TRIGGER_HAPPY,
+        _ => return None,
+    };
+    Some(bit as u32)
+}
+
+/// `EV_KEY` codes of joystick and gamepad buttons: the BTN_MISC, BTN_JOYSTICK and
+/// BTN_GAMEPAD blocks, then BTN_TRIGGER_HAPPY.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(super) const EVDEV_BUTTON_CODES: [Range<u16>; 4] = [0x100..0x110, 0x120..0x130, 0x130..0x140, 0x2c0..0x2e8];
+
+/// Bit of each button code a device has, numbered in code order.
+///
+/// For a device whose buttons all come from hid-input, bit N is HID button N + 1, as with
+/// `evdev_key_bit`. Codes from different blocks never share a bit. Buttons past the 32nd
+/// are not read.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(super) fn evdev_button_bits(has_code: impl Fn(u16) -> bool) -> HashMap<u16, u32> {
+    EVDEV_BUTTON_CODES
+        .into_iter()
+        .flatten()
+        .filter(|&code| has_code(code))
+        .take(32)
+        .zip(0..)
+        .collect()
+}
+
+/// XInput `wButtons` flags in raw button order, the way DirectInput numbers an Xbox pad:
+/// A, B, X, Y, LB, RB, Back, Start, left and right stick, then the d-pad.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub(super) const XINPUT_BUTTON_ORDER: [u16; 14] = [
+    0x1000, 0x2000, 0x4000, 0x8000, 0x0100, 0x0200, 0x0020, 0x0010, 0x0040, 0x0080, 0x0001,
+    0x0002, 0x0004, 0x0008,
+];
+
+/// Renumbers XInput `wButtons` so bit N is the Nth button of `XINPUT_BUTTON_ORDER`,
+/// for `ButtonLayout::RawIndex`.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub(super) fn xinput_raw_index_bits(w_buttons: u16) -> u32 {
+    XINPUT_BUTTON_ORDER
+        .iter()
+        .enumerate()
+        .filter(|(_, &flag)| w_buttons & flag != 0)
+        .fold(0, |bits, (index, _)| bits | (1 << index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::{ButtonLayout, ButtonMap};
+
+    #[test]
+    fn test_queue_keeps_edges_within_one_poll() {
+        let mut queue = ButtonEventQueue::default();
+        queue.apply(0, true, 1_000);
+        queue.apply(0, false, 1_000);
+        queue.apply(0, true, 1_001);
+
+        let snapshots = queue.drain(1, 1_005);
+        let buttons: Vec<u32> = snapshots.iter().map(|s| s.buttons).collect();
+        assert_eq!(buttons, vec![1, 0, 1]);
+        assert_eq!(snapshots[2].timestamp_ms, 1_001);
+    }
+
+    #[test]
+    fn test_queue_resyncs_with_sampled_state() {
+        let mut queue = ButtonEventQueue::default();
+        assert_eq!(queue.drain(0b100, 50), vec![InputSnapshot { buttons: 0b100, timestamp_ms: 50 }]);
+
+        queue.apply(2, true, 60); // already pressed, no change
+        assert_eq!(queue.drain(0b100, 70).len(), 1);
+    }
+
+    #[test]
+    fn test_queue_is_bounded() {
+        let mut queue = ButtonEventQueue::default();
+        for i in 0..(MAX_PENDING_SNAPSHOTS as u64 + 10) {
+            queue.apply(1, i % 2 == 0, i);
+        }
+        assert_eq!(queue.snapshots.len(), MAX_PENDING_SNAPSHOTS);
+        assert_eq!(queue.snapshots.front().unwrap().timestamp_ms, 10);
+    }
+
+    #[test]
+    fn test_evdev_button_bits_follow_the_device_codes() {
+        // hid-input gamepad with 18 buttons
+        let gamepad = evdev_button_bits(|c| (0x130..0x140).contains(&c) || (0x2c0..0x2c2).contains(&c));
+        assert_eq!(gamepad[&0x130], 0);
+        assert_eq!(gamepad[&0x2c1], 17);
+
+        // Codes from two blocks get their own bits
+        let mixed = evdev_button_bits(|c| [0x120, 0x121, 0x130, 0x131].contains(&c));
+        let bits: Vec<u32> = [0x120, 0x121, 0x130, 0x131].iter().map(|c| mixed[c]).collect();
+        assert_eq!(bits, vec![0, 1, 2, 3]);
+
+        let many = evdev_button_bits(|_| true);
+        assert_eq!(many.len(), 32);
+        assert_eq!(many[&0x12f], 31);
+        assert!(!many.contains_key(&0x130));
+    }
+
+    #[test]
+    fn test_evdev_codes_map_to_physical_index() {
+        assert_eq!(evdev_key_bit(0x120), Some(0)); // BTN_TRIGGER
+        assert_eq!(evdev_key_bit(0x13f), Some(15));
+        assert_eq!(evdev_key_bit(0x130), Some(0)); // BTN_SOUTH
+        assert_eq!(evdev_key_bit(0x2c0), Some(16)); // BTN_TRIGGER_HAPPY1
+        assert_eq!(evdev_key_bit(0x2cf), Some(31));
+        assert_eq!(evdev_key_bit(0x2d0), None);
+        assert_eq!(evdev_key_bit(0x1e), None); // KEY_A
+    }
+
+    #[test]
+    fn test_default_map_uses_buttons_xinput_reports() {
+        let map = ButtonMap::default();
+        assert_eq!(map.layout, ButtonLayout::RawIndex);
+
+        let reported = XINPUT_BUTTON_ORDER.iter().fold(0, |bits, &flag| bits | xinput_raw_index_bits(flag));
+        for (key, bits) in &map.bindings {
+            assert_eq!(bits.count_ones(), 1, "{}", key);
+            assert_eq!(bits & !reported, 0, "{} is bound to a button XInput never reports", key);
+        }
+        assert_eq!(xinput_raw_index_bits(0x1000 | 0x0008), 1 | (1 << 13)); // A and d-pad right
+    }
+}
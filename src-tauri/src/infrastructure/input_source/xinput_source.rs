@@ -1,14 +1,19 @@
 use crate::domain::errors::InputError;
 use crate::domain::interfaces::InputSource;
+use crate::domain::models::ButtonLayout;
+
+use super::raw_buttons::xinput_raw_index_bits;
 
 /// Windows XInput input source for Xbox-compatible controllers.
 #[cfg(target_os = "windows")]
-pub struct XInputSource;
+pub struct XInputSource {
+    layout: ButtonLayout,
+}
 
 #[cfg(target_os = "windows")]
 impl XInputSource {
     pub fn new() -> Self {
-        Self
+        Self { layout: ButtonLayout::default() }
     }
 }
 
@@ -21,11 +26,19 @@ impl InputSource for XInputSource {
         let result = unsafe { XInputGetState(controller_index, &mut state) };
 
         if result == 0 { // ERROR_SUCCESS
-            Ok(state.Gamepad.wButtons.0 as u32)
+            let w_buttons = state.Gamepad.wButtons.0;
+            Ok(match self.layout {
+                ButtonLayout::StandardButtons => w_buttons as u32,
+                ButtonLayout::RawIndex => xinput_raw_index_bits(w_buttons),
+            })
         } else if result == 1167 { // ERROR_DEVICE_NOT_CONNECTED
              Err(InputError::Disconnected)
         } else {
              Err(InputError::Other(anyhow::anyhow!("XInput Error: {}", result)))
         }
     }
+
+    fn set_button_layout(&mut self, layout: ButtonLayout) {
+        self.layout = layout;
+    }
 }
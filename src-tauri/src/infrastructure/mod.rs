@@ -1,4 +1,5 @@
 pub mod input_source;
 pub mod persistence;
 pub mod process_monitor;
+pub mod suspend_clock;
 pub mod timer;
@@ -1,12 +1,15 @@
 use anyhow::{Context, Result, anyhow};
 use std::path::{Path, PathBuf};
 use std::fs;
+use std::io::Write;
+use std::sync::Mutex;
 #[cfg(target_os = "windows")]
 use std::thread;
 #[cfg(target_os = "windows")]
 use std::time::Duration;
+use serde::Serialize;
 use tempfile::NamedTempFile;
-use crate::domain::models::UserProfile;
+use crate::domain::models::{default_alert_rules, AlertRule, ProfileBundle, UserProfile};
 
 const CURRENT_SCHEMA_VERSION: u32 = 2;
 
@@ -17,12 +20,15 @@ pub trait ConfigRepository {
 
 pub struct FileConfigRepository {
     path: PathBuf,
+    /// JSON last written, so saving an unchanged profile does not rewrite the file.
+    last_saved: Mutex<Option<Vec<u8>>>,
 }
 
 impl FileConfigRepository {
     pub fn new<P: AsRef<Path>>(path: P) -> Self {
         Self {
             path: path.as_ref().to_path_buf(),
+            last_saved: Mutex::new(None),
         }
     }
 
@@ -42,57 +48,6 @@ impl FileConfigRepository {
              Ok(path)
         }
     }
-
-    /// Ensures the directory for the config file exists.
-    fn ensure_directory(&self) -> Result<()> {
-        if let Some(parent) = self.path.parent() {
-            if !parent.exists() {
-                fs::create_dir_all(parent).context(format!("Failed to create directory: {:?}", parent))?;
-            }
-        }
-        Ok(())
-    }
-
-    // Platform specific rename
-    #[cfg(target_os = "windows")]
-    fn atomic_rename_with_retry(src: &Path, dst: &Path) -> Result<()> {
-        use windows::Win32::Storage::FileSystem::{MoveFileExW, MOVEFILE_REPLACE_EXISTING, MOVEFILE_WRITE_THROUGH};
-        use std::os::windows::ffi::OsStrExt;
-
-        let src_wide: Vec<u16> = src.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
-        let dst_wide: Vec<u16> = dst.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
-
-        let mut attempts = 0;
-        const MAX_ATTEMPTS: i32 = 3;
-
-        loop {
-            let result = unsafe {
-                MoveFileExW(
-                    windows::core::PCWSTR(src_wide.as_ptr()),
-                    windows::core::PCWSTR(dst_wide.as_ptr()),
-                    MOVEFILE_REPLACE_EXISTING | MOVEFILE_WRITE_THROUGH
-                )
-            };
-
-            match result {
-                Ok(_) => return Ok(()),
-                Err(e) => {
-                    attempts += 1;
-                    if attempts >= MAX_ATTEMPTS {
-                        return Err(anyhow::anyhow!("MoveFileExW failed after {} attempts: {}", attempts, e));
-                    }
-                    // Wait 50-100ms
-                    thread::sleep(Duration::from_millis(50));
-                }
-            }
-        }
-    }
-
-    #[cfg(not(target_os = "windows"))]
-    fn atomic_rename_with_retry(src: &Path, dst: &Path) -> Result<()> {
-        // Simple rename for non-Windows (or implement retry if desired, but requirement specified Windows logic)
-        fs::rename(src, dst).context("Failed to rename temp file")
-    }
 }
 
 impl ConfigRepository for FileConfigRepository {
@@ -126,38 +81,176 @@ impl ConfigRepository for FileConfigRepository {
         } else if version != CURRENT_SCHEMA_VERSION {
              return Err(anyhow!("Schema version mismatch: expected {}, found {}", CURRENT_SCHEMA_VERSION, version));
         }
+        if let Some(controllers) = value.get_mut("controllers").and_then(|c| c.as_object_mut()) {
+            controllers.values_mut().for_each(migrate_recent_sessions);
+        }
+        if let Some(config) = value.get_mut("config") {
+            migrate_chatter_alert_percent(config);
+        }
 
         let profile: UserProfile = serde_json::from_value(value).context("Failed to deserialize migrated config")?;
         Ok(profile)
     }
 
     fn save(&self, profile: &UserProfile) -> Result<()> {
-        // Ensure parent directory exists
-        self.ensure_directory()?;
+        let json = serde_json::to_vec_pretty(profile).context("Failed to serialize JSON")?;
+        let mut last_saved = self.last_saved.lock().unwrap();
+        if last_saved.as_ref() == Some(&json) {
+            return Ok(());
+        }
+        write_file_atomic(&self.path, &json)?;
+        *last_saved = Some(json);
+        Ok(())
+    }
+}
+
+/// Configs saved before alert rules existed kept the report's chatter alert level in a
+/// `chatter_alert_percent` field. Carry it over to the default `ChatterRate` rule; configs that
+/// already have rules keep them, since those are what raised alerts.
+fn migrate_chatter_alert_percent(config: &mut serde_json::Value) {
+    let Some(config) = config.as_object_mut() else {
+        return;
+    };
+    let Some(percent) = config.remove("chatter_alert_percent").and_then(|p| p.as_f64()) else {
+        return;
+    };
+    if config.contains_key("alert_rules") {
+        return;
+    }
+    let mut rules = default_alert_rules();
+    for rule in &mut rules {
+        if let AlertRule::ChatterRate { percent: rule_percent, .. } = rule {
+            *rule_percent = percent;
+        }
+    }
+    config.insert("alert_rules".to_string(), serde_json::json!(rules));
+}
 
-        // Create temp file in the SAME DIRECTORY to ensure atomic rename works
-        let parent = self.path.parent().unwrap_or_else(|| Path::new("."));
-        let file = NamedTempFile::new_in(parent).context("Failed to create temp file")?;
+/// Profiles saved before `recent_sessions` became a view over `session_history` stored the
+/// newest sessions, with their timelines, in a `recent_sessions` array of each controller.
+/// Move the ones missing from the history into it.
+fn migrate_recent_sessions(controller: &mut serde_json::Value) {
+    let Some(controller) = controller.as_object_mut() else {
+        return;
+    };
+    let Some(serde_json::Value::Array(recent)) = controller.remove("recent_sessions") else {
+        return;
+    };
+    let history = controller.entry("session_history").or_insert_with(|| serde_json::json!([]));
+    let Some(history) = history.as_array_mut() else {
+        return;
+    };
+    for session in recent {
+        match history.iter_mut().find(|s| s.get("start_time") == session.get("start_time")) {
+            // The history copy was stored without its timeline
+            Some(stored) => *stored = session,
+            None => history.push(session),
+        }
+    }
+}
 
-        // Write JSON
-        serde_json::to_writer_pretty(&file, profile).context("Failed to serialize config")?;
+/// Serializes `value` as pretty JSON and writes it to `path` atomically.
+pub fn write_json_atomic<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    let json = serde_json::to_vec_pretty(value).context("Failed to serialize JSON")?;
+    write_file_atomic(path, &json)
+}
 
-        // Flush and Sync
-        file.as_file().sync_all().context("Failed to sync temp file")?;
+/// Writes `contents` to `path` atomically.
+///
+/// The parent directory is created if missing, and the data is written to a
+/// temp file in the same directory before being renamed over the target.
+pub fn write_file_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    // Ensure parent directory exists
+    ensure_parent_directory(path)?;
 
-        // Persist (Atomic Rename)
-        // keep() detaches the file from the TempFile wrapper so it doesn't get deleted on drop
-        let (temp_file, temp_path) = file.keep().context("Failed to keep temp file")?;
+    // Create temp file in the SAME DIRECTORY to ensure atomic rename works
+    let parent = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut file = NamedTempFile::new_in(parent).context("Failed to create temp file")?;
 
-        // Explicitly drop the file handle to ensure it's closed before renaming
-        drop(temp_file);
+    file.write_all(contents).context("Failed to write temp file")?;
 
-        Self::atomic_rename_with_retry(&temp_path, &self.path).map_err(|e| {
-            // Attempt cleanup if rename fails
-            let _ = fs::remove_file(&temp_path);
-            e
-        })
+    // Flush and Sync
+    file.as_file().sync_all().context("Failed to sync temp file")?;
+
+    // Persist (Atomic Rename)
+    // keep() detaches the file from the TempFile wrapper so it doesn't get deleted on drop
+    let (temp_file, temp_path) = file.keep().context("Failed to keep temp file")?;
+
+    // Explicitly drop the file handle to ensure it's closed before renaming
+    drop(temp_file);
+
+    atomic_rename_with_retry(&temp_path, path).map_err(|e| {
+        // Attempt cleanup if rename fails
+        let _ = fs::remove_file(&temp_path);
+        e
+    })
+}
+
+/// Writes an exported controller bundle to `path`.
+pub fn save_bundle(path: &Path, bundle: &ProfileBundle) -> Result<()> {
+    write_json_atomic(path, bundle)
+}
+
+/// Reads a controller bundle previously written by `save_bundle`.
+pub fn load_bundle(path: &Path) -> Result<ProfileBundle> {
+    let file = fs::File::open(path).context(format!("Failed to open bundle file: {:?}", path))?;
+    let reader = std::io::BufReader::new(file);
+    let mut value: serde_json::Value = serde_json::from_reader(reader).context("Failed to parse bundle file")?;
+    if let Some(profile) = value.get_mut("profile") {
+        migrate_recent_sessions(profile);
     }
+    serde_json::from_value(value).context("Failed to parse bundle file")
+}
+
+/// Ensures the directory for the given file exists.
+fn ensure_parent_directory(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            fs::create_dir_all(parent).context(format!("Failed to create directory: {:?}", parent))?;
+        }
+    }
+    Ok(())
+}
+
+// Platform specific rename
+#[cfg(target_os = "windows")]
+fn atomic_rename_with_retry(src: &Path, dst: &Path) -> Result<()> {
+    use windows::Win32::Storage::FileSystem::{MoveFileExW, MOVEFILE_REPLACE_EXISTING, MOVEFILE_WRITE_THROUGH};
+    use std::os::windows::ffi::OsStrExt;
+
+    let src_wide: Vec<u16> = src.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    let dst_wide: Vec<u16> = dst.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+
+    let mut attempts = 0;
+    const MAX_ATTEMPTS: i32 = 3;
+
+    loop {
+        let result = unsafe {
+            MoveFileExW(
+                windows::core::PCWSTR(src_wide.as_ptr()),
+                windows::core::PCWSTR(dst_wide.as_ptr()),
+                MOVEFILE_REPLACE_EXISTING | MOVEFILE_WRITE_THROUGH
+            )
+        };
+
+        match result {
+            Ok(_) => return Ok(()),
+            Err(e) => {
+                attempts += 1;
+                if attempts >= MAX_ATTEMPTS {
+                    return Err(anyhow::anyhow!("MoveFileExW failed after {} attempts: {}", attempts, e));
+                }
+                // Wait 50-100ms
+                thread::sleep(Duration::from_millis(50));
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn atomic_rename_with_retry(src: &Path, dst: &Path) -> Result<()> {
+    // Simple rename for non-Windows (or implement retry if desired, but requirement specified Windows logic)
+    fs::rename(src, dst).context("Failed to rename temp file")
 }
 
 #[cfg(test)]
@@ -183,6 +276,102 @@ mod tests {
         assert_eq!(loaded.config.target_controller_index, 99);
     }
 
+    #[test]
+    fn test_unchanged_profile_is_not_rewritten() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("config.json");
+        let repo = FileConfigRepository::new(&file_path);
+
+        let mut profile = UserProfile::default();
+        repo.save(&profile).unwrap();
+        fs::remove_file(&file_path).unwrap();
+        repo.save(&profile).unwrap();
+        assert!(!file_path.exists());
+
+        profile.config.target_controller_index = 3;
+        repo.save(&profile).unwrap();
+        assert!(file_path.exists());
+    }
+
+    #[test]
+    fn test_chatter_alert_percent_moves_into_alert_rule() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("config.json");
+        let mut value = serde_json::to_value(UserProfile::default()).unwrap();
+        let config = value["config"].as_object_mut().unwrap();
+        config.remove("alert_rules");
+        config.insert("chatter_alert_percent".to_string(), serde_json::json!(7.5));
+        fs::write(&file_path, serde_json::to_vec(&value).unwrap()).unwrap();
+
+        let loaded = FileConfigRepository::new(&file_path).load().unwrap();
+        assert_eq!(loaded.config.chatter_alert_percent(), Some(7.5));
+        assert_eq!(loaded.config.alert_rules.len(), default_alert_rules().len());
+
+        // Saved rules win over the old field
+        value["config"]["alert_rules"] = serde_json::json!(default_alert_rules());
+        fs::write(&file_path, serde_json::to_vec(&value).unwrap()).unwrap();
+        let loaded = FileConfigRepository::new(&file_path).load().unwrap();
+        assert_eq!(loaded.config.chatter_alert_percent(), UserProfile::default().config.chatter_alert_percent());
+    }
+
+    /// A session as stored in the legacy `recent_sessions` array.
+    fn legacy_session(start: &str, bucket_secs: u64) -> serde_json::Value {
+        serde_json::json!({
+            "start_time": start,
+            "end_time": start,
+            "duration_secs": 0,
+            "stats": {},
+            "game_id": null,
+            "started_by": "Manual",
+            "timeline": { "bucket_secs": bucket_secs, "buckets": [] },
+        })
+    }
+
+    #[test]
+    fn test_recent_sessions_move_into_history() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("config.json");
+        let mut value = serde_json::to_value(UserProfile::default()).unwrap();
+        value["controllers"]["default"]["session_history"] =
+            serde_json::json!([legacy_session("2025-01-01T00:00:00Z", 60)]);
+        value["controllers"]["default"]["recent_sessions"] = serde_json::json!([
+            legacy_session("2025-01-01T00:00:00Z", 120),
+            legacy_session("2025-01-02T00:00:00Z", 60),
+        ]);
+        fs::write(&file_path, serde_json::to_vec(&value).unwrap()).unwrap();
+
+        let loaded = FileConfigRepository::new(&file_path).load().unwrap();
+        let history = &loaded.controllers["default"].session_history;
+        assert_eq!(history.len(), 2);
+        // The recent copy, with its timeline, replaces the one in the history
+        assert_eq!(history[0].timeline.bucket_secs, 120);
+        assert_eq!(history[1].start_time.to_rfc3339(), "2025-01-02T00:00:00+00:00");
+    }
+
+    #[test]
+    fn test_recent_sessions_migrate_without_history() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("config.json");
+        let repo = FileConfigRepository::new(&file_path);
+        let mut value = serde_json::to_value(UserProfile::default()).unwrap();
+        let controller = value["controllers"]["default"].as_object_mut().unwrap();
+        controller.remove("session_history");
+        controller.insert(
+            "recent_sessions".to_string(),
+            serde_json::json!([legacy_session("2025-01-01T00:00:00Z", 60)]),
+        );
+        fs::write(&file_path, serde_json::to_vec(&value).unwrap()).unwrap();
+
+        let loaded = repo.load().unwrap();
+        assert_eq!(loaded.controllers["default"].session_history.len(), 1);
+
+        // Saving the migrated profile drops the legacy array for good
+        repo.save(&loaded).unwrap();
+        let saved: serde_json::Value = serde_json::from_slice(&fs::read(&file_path).unwrap()).unwrap();
+        assert!(saved["controllers"]["default"].get("recent_sessions").is_none());
+        assert_eq!(repo.load().unwrap().controllers["default"].session_history.len(), 1);
+    }
+
     #[test]
     fn test_schema_version_check() {
         let dir = tempdir().unwrap();
@@ -202,6 +391,39 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("Schema version mismatch"));
     }
 
+    #[test]
+    fn test_bundle_roundtrip() {
+        use crate::usecase::profile_transfer::ProfileTransfer;
+
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("exports").join("default.slmbundle.json");
+
+        let profile = UserProfile::default();
+        let bundle = ProfileTransfer::export_bundle(&profile, "default", Some("Pad".to_string())).unwrap();
+        save_bundle(&file_path, &bundle).unwrap();
+
+        let loaded = load_bundle(&file_path).unwrap();
+        assert_eq!(loaded.controller_id, "default");
+        assert_eq!(loaded.controller_name.as_deref(), Some("Pad"));
+        assert_eq!(loaded.format_version, bundle.format_version);
+    }
+
+    #[test]
+    fn test_bundle_recent_sessions_move_into_history() {
+        use crate::usecase::profile_transfer::ProfileTransfer;
+
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("default.slmbundle.json");
+        let bundle = ProfileTransfer::export_bundle(&UserProfile::default(), "default", None).unwrap();
+        let mut value = serde_json::to_value(&bundle).unwrap();
+        value["profile"]["recent_sessions"] = serde_json::json!([legacy_session("2025-01-01T00:00:00Z", 60)]);
+        fs::write(&file_path, serde_json::to_vec(&value).unwrap()).unwrap();
+
+        let loaded = load_bundle(&file_path).unwrap();
+        assert_eq!(loaded.profile.session_history.len(), 1);
+        assert_eq!(loaded.profile.session_history[0].timeline.bucket_secs, 60);
+    }
+
     #[test]
     fn test_get_default_config_path() {
         // Just check it returns something reasonable and doesn't crash
@@ -1,22 +1,198 @@
-use sysinfo::{ProcessesToUpdate, System};
+use log::debug;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+use std::collections::HashMap;
 use std::ffi::OsStr;
+use std::time::{Duration, Instant};
+
+use crate::domain::models::{find_game_match, GameDefinition, GameMatch, ProcessInfo};
 
 /// Abstraction for monitoring the target game process.
 pub trait ProcessMonitor: Send + Sync {
     /// Checks if the target process is currently running.
     fn is_process_running(&mut self, process_name: &str) -> bool;
+
+    /// Returns the first game in `games` that has a running process, with the rule that matched.
+    ///
+    /// The default only checks name-only rules without wildcards, via `is_process_running`.
+    fn match_game(&mut self, games: &[GameDefinition]) -> Option<GameMatch> {
+        games.iter().find_map(|game| {
+            game.process_rules()
+                .into_iter()
+                .filter(|rule| rule.is_name_only() && !rule.process_name.contains(['*', '?']))
+                .find(|rule| self.is_process_running(&rule.process_name))
+                .map(|rule| GameMatch {
+                    game_id: game.id.clone(),
+                    rule,
+                    pid: None,
+                })
+        })
+    }
+
+    /// Returns the id of the first game in `games` that has a running process.
+    fn find_running_game(&mut self, games: &[GameDefinition]) -> Option<String> {
+        self.match_game(games).map(|found| found.game_id)
+    }
 }
 
 /// Implementation using sysinfo.
+///
+/// Reading the whole process table is expensive with hundreds of processes, so once a game
+/// is found only its PID is checked until it exits, and while no game runs the full scan
+/// happens at most every `full_scan_interval`, or right away when `games` changed. A game that
+/// starts while another one is tracked is not noticed until the tracked one exits.
 pub struct SysinfoProcessMonitor {
     system: System,
+    tracked: Option<TrackedGame>,
+    last_full_scan: Option<Instant>,
+    /// The definitions the last full scan looked for.
+    scanned_games: Vec<GameDefinition>,
+    full_scan_interval: Duration,
+    track_pid: bool,
+    use_exit_notification: bool,
+}
+
+/// Default pause between full scans while no game is running.
+pub const DEFAULT_FULL_SCAN_INTERVAL: Duration = Duration::from_secs(6);
+
+/// The game found by the last full scan.
+struct TrackedGame {
+    found: GameMatch,
+    pid: Pid,
+    // Guards against the PID being reused by another process
+    name: String,
+    exit_watch: Option<ExitWatch>,
 }
 
 impl SysinfoProcessMonitor {
     pub fn new() -> Self {
         Self {
             system: System::new(),
+            tracked: None,
+            last_full_scan: None,
+            scanned_games: Vec::new(),
+            full_scan_interval: DEFAULT_FULL_SCAN_INTERVAL,
+            track_pid: true,
+            use_exit_notification: true,
+        }
+    }
+
+    /// Pause between full scans while no game is running. Zero scans on every check.
+    pub fn with_full_scan_interval(mut self, interval: Duration) -> Self {
+        self.full_scan_interval = interval;
+        self
+    }
+
+    /// Whether a found game is followed by its PID. Without it every check is a full scan.
+    pub fn with_pid_tracking(mut self, enabled: bool) -> Self {
+        self.track_pid = enabled;
+        self
+    }
+
+    /// Whether to wait for the tracked process to exit through a pidfd (Linux only) instead
+    /// of looking its PID up on every check.
+    pub fn with_exit_notification(mut self, enabled: bool) -> Self {
+        self.use_exit_notification = enabled;
+        self
+    }
+
+    /// The tracked game, if it is still running and still defined in `games`.
+    fn check_tracked(&mut self, games: &[GameDefinition]) -> Option<GameMatch> {
+        let tracked = self.tracked.as_ref()?;
+
+        let still_defined = games.iter().any(|game| {
+            game.id == tracked.found.game_id && game.process_rules().contains(&tracked.found.rule)
+        });
+        if !still_defined {
+            // The definitions changed; `match_game` looks again right away
+            self.tracked = None;
+            return None;
+        }
+
+        let alive = match &tracked.exit_watch {
+            Some(watch) => !watch.has_exited(),
+            None => {
+                self.system.refresh_processes_specifics(
+                    ProcessesToUpdate::Some(&[tracked.pid]),
+                    true,
+                    ProcessRefreshKind::nothing(),
+                );
+                self.system
+                    .process(tracked.pid)
+                    .is_some_and(|p| p.name().to_string_lossy() == tracked.name)
+            }
+        };
+        if alive {
+            Some(tracked.found.clone())
+        } else {
+            debug!("Game process {} exited", tracked.pid);
+            self.tracked = None;
+            None
+        }
+    }
+
+    fn full_scan(&mut self, games: &[GameDefinition]) -> Option<GameMatch> {
+        let with_window_titles = games
+            .iter()
+            .flat_map(|game| game.rules.iter())
+            .any(|rule| rule.needs_window_title());
+        let processes = self.list_processes(with_window_titles);
+        self.last_full_scan = Some(Instant::now());
+        if self.scanned_games != games {
+            self.scanned_games = games.to_vec();
+        }
+
+        let found = find_game_match(games, &processes)?;
+        if self.track_pid {
+            if let Some(process) = processes.iter().find(|p| Some(p.pid) == found.pid) {
+                let exit_watch = if self.use_exit_notification {
+                    ExitWatch::open(process.pid)
+                } else {
+                    None
+                };
+                self.tracked = Some(TrackedGame {
+                    found: found.clone(),
+                    pid: Pid::from_u32(process.pid),
+                    name: process.name.clone(),
+                    exit_watch,
+                });
+            }
         }
+        Some(found)
+    }
+
+    /// Name, executable and command line of every process; window titles only when asked for.
+    fn list_processes(&mut self, with_window_titles: bool) -> Vec<ProcessInfo> {
+        // The default refresh kind reads CPU and memory but not the command line
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing()
+                .with_exe(UpdateKind::OnlyIfNotSet)
+                .with_cmd(UpdateKind::OnlyIfNotSet),
+        );
+
+        let mut window_titles = if with_window_titles { window_titles() } else { HashMap::new() };
+        self.system
+            .processes()
+            .iter()
+            .map(|(pid, process)| {
+                let pid = pid.as_u32();
+                let command_line = process
+                    .cmd()
+                    .iter()
+                    .map(|arg| arg.to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                ProcessInfo {
+                    pid,
+                    name: process.name().to_string_lossy().to_string(),
+                    executable_path: process.exe().map(|path| path.to_string_lossy().to_string()),
+                    // Empty for processes we may not inspect
+                    command_line: (!command_line.is_empty()).then_some(command_line),
+                    window_titles: window_titles.remove(&pid).unwrap_or_default(),
+                }
+            })
+            .collect()
     }
 }
 
@@ -43,6 +219,102 @@ impl ProcessMonitor for SysinfoProcessMonitor {
 
         found
     }
+
+    fn match_game(&mut self, games: &[GameDefinition]) -> Option<GameMatch> {
+        if let Some(found) = self.check_tracked(games) {
+            return Some(found);
+        }
+        let due = self.scanned_games != games
+            || self
+                .last_full_scan
+                .is_none_or(|t| t.elapsed() >= self.full_scan_interval);
+        if !due {
+            return None;
+        }
+        self.full_scan(games)
+    }
+}
+
+/// Notification of a process exit through a pidfd, which becomes readable once the process
+/// has exited. Needs Linux 5.3; on older kernels `open` fails and PID lookups are used.
+#[cfg(target_os = "linux")]
+struct ExitWatch(std::os::fd::OwnedFd);
+
+#[cfg(target_os = "linux")]
+impl ExitWatch {
+    fn open(pid: u32) -> Option<Self> {
+        use std::os::fd::FromRawFd;
+
+        let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0) };
+        if fd < 0 {
+            debug!("pidfd_open({}) failed: {}", pid, std::io::Error::last_os_error());
+            return None;
+        }
+        Some(Self(unsafe { std::os::fd::OwnedFd::from_raw_fd(fd as i32) }))
+    }
+
+    fn has_exited(&self) -> bool {
+        use std::os::fd::AsRawFd;
+
+        let mut pollfd = libc::pollfd {
+            fd: self.0.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let ready = unsafe { libc::poll(&mut pollfd, 1, 0) };
+        ready > 0 && pollfd.revents & libc::POLLIN != 0
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+enum ExitWatch {}
+
+#[cfg(not(target_os = "linux"))]
+impl ExitWatch {
+    fn open(_pid: u32) -> Option<Self> {
+        None
+    }
+
+    fn has_exited(&self) -> bool {
+        match *self {}
+    }
+}
+
+/// Titles of visible top-level windows, by owning process id.
+#[cfg(windows)]
+fn window_titles() -> HashMap<u32, Vec<String>> {
+    use windows::core::BOOL;
+    use windows::Win32::Foundation::{HWND, LPARAM};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        EnumWindows, GetWindowTextW, GetWindowThreadProcessId, IsWindowVisible,
+    };
+
+    unsafe extern "system" fn collect(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let titles = &mut *(lparam.0 as *mut HashMap<u32, Vec<String>>);
+        if IsWindowVisible(hwnd).as_bool() {
+            let mut buffer = [0u16; 512];
+            let len = GetWindowTextW(hwnd, &mut buffer);
+            if len > 0 {
+                let mut pid = 0u32;
+                GetWindowThreadProcessId(hwnd, Some(&mut pid));
+                titles
+                    .entry(pid)
+                    .or_default()
+                    .push(String::from_utf16_lossy(&buffer[..len as usize]));
+            }
+        }
+        BOOL::from(true)
+    }
+
+    let mut titles: HashMap<u32, Vec<String>> = HashMap::new();
+    // The callback only runs during EnumWindows, while `titles` is borrowed
+    let _ = unsafe { EnumWindows(Some(collect), LPARAM(&mut titles as *mut _ as isize)) };
+    titles
+}
+
+#[cfg(not(windows))]
+fn window_titles() -> HashMap<u32, Vec<String>> {
+    HashMap::new()
 }
 
 /// Mock implementation for testing.
@@ -65,3 +337,87 @@ impl ProcessMonitor for MockProcessMonitor {
         self.is_running
     }
 }
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::domain::models::ProcessRule;
+    use std::process::{Child, Command};
+
+    fn sleeper_game() -> Vec<GameDefinition> {
+        vec![GameDefinition {
+            rules: vec![ProcessRule {
+                command_line: Some("sleep 3217".to_string()),
+                ..ProcessRule::new("sleep")
+            }],
+            ..GameDefinition::new("sleeper", "Sleeper", &[])
+        }]
+    }
+
+    fn start_sleeper() -> Child {
+        let child = Command::new("sleep").arg("3217").spawn().unwrap();
+        // Wait for the exec, until then the process still looks like the test binary
+        let cmdline_path = format!("/proc/{}/cmdline", child.id());
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !std::fs::read(&cmdline_path).is_ok_and(|cmdline| cmdline == b"sleep\03217\0") {
+            assert!(Instant::now() < deadline, "sleep did not start in time");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        child
+    }
+
+    fn assert_tracks_until_exit(mut monitor: SysinfoProcessMonitor) {
+        let games = sleeper_game();
+        let mut child = start_sleeper();
+
+        let found = monitor.match_game(&games).expect("sleeper should be found");
+        assert_eq!(found.pid, Some(child.id()));
+        assert!(monitor.tracked.is_some());
+        assert_eq!(monitor.match_game(&games).map(|m| m.game_id).as_deref(), Some("sleeper"));
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert_eq!(monitor.match_game(&games), None);
+        assert!(monitor.tracked.is_none());
+    }
+
+    #[test]
+    fn test_tracked_pid_detects_exit() {
+        assert_tracks_until_exit(
+            SysinfoProcessMonitor::new()
+                .with_full_scan_interval(Duration::from_secs(3600))
+                .with_exit_notification(false),
+        );
+    }
+
+    #[test]
+    fn test_pidfd_detects_exit() {
+        assert_tracks_until_exit(
+            SysinfoProcessMonitor::new().with_full_scan_interval(Duration::from_secs(3600)),
+        );
+    }
+
+    #[test]
+    fn test_changed_definitions_drop_tracking() {
+        let mut monitor = SysinfoProcessMonitor::new().with_full_scan_interval(Duration::from_secs(3600));
+        let mut child = start_sleeper();
+
+        assert!(monitor.match_game(&sleeper_game()).is_some());
+        let first_scan = monitor.last_full_scan;
+
+        // Without the tracked game, tracking is dropped and a full scan runs at once
+        assert_eq!(monitor.match_game(&[]), None);
+        assert!(monitor.tracked.is_none());
+        assert_ne!(monitor.last_full_scan, first_scan);
+        // Unchanged definitions wait for the interval
+        let second_scan = monitor.last_full_scan;
+        assert_eq!(monitor.match_game(&[]), None);
+        assert_eq!(monitor.last_full_scan, second_scan);
+        // Changed definitions are scanned for at once, well within the interval
+        let found = monitor.match_game(&sleeper_game()).expect("sleeper should be found");
+        assert_eq!(found.pid, Some(child.id()));
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
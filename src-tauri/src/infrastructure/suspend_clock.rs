@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+#[cfg(target_os = "windows")]
+use windows::Win32::System::WindowsProgramming::{QueryInterruptTime, QueryUnbiasedInterruptTime};
+
+/// Total time the computer has been suspended since it booted.
+///
+/// Read as the difference between a monotonic clock that keeps counting while suspended and
+/// one that does not, so changes to the wall clock do not affect it. `None` where the
+/// platform has no such pair of clocks.
+pub fn suspended_since_boot() -> Option<Duration> {
+    #[cfg(target_os = "linux")]
+    {
+        Some(read_clock(libc::CLOCK_BOOTTIME)?.saturating_sub(read_clock(libc::CLOCK_MONOTONIC)?))
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        // Both count 100ns intervals; the unbiased one leaves out time spent suspended
+        let mut unbiased = 0u64;
+        let interrupt = unsafe {
+            if !QueryUnbiasedInterruptTime(&mut unbiased).as_bool() {
+                return None;
+            }
+            QueryInterruptTime()
+        };
+        Some(Duration::from_nanos(interrupt.saturating_sub(unbiased) * 100))
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    {
+        None
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_clock(clock: libc::clockid_t) -> Option<Duration> {
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    if unsafe { libc::clock_gettime(clock, &mut ts) } != 0 {
+        return None;
+    }
+    Some(Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32))
+}
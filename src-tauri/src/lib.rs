@@ -4,6 +4,8 @@ pub mod domain;
 pub mod infrastructure;
 pub mod usecase;
 mod logging;
+mod metrics_server;
+mod notifications;
 mod tray;
 
 use std::sync::Arc;
@@ -25,6 +27,7 @@ use crate::usecase::state_publisher::{MonitorSharedState, StatePublisher};
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
         .invoke_handler(tauri::generate_handler![
             commands::get_snapshot,
             commands::force_save,
@@ -34,8 +37,22 @@ pub fn run() {
             commands::update_config,
             commands::set_target_controller,
             commands::reset_to_default_mapping,
+            commands::set_button_layout,
             commands::set_last_replaced_date,
-            commands::set_active_controller
+            commands::set_active_controller,
+            commands::export_profile,
+            commands::import_profile,
+            commands::export_data,
+            commands::get_session_report,
+            commands::render_session_report,
+            commands::export_session_report,
+            commands::get_play_stats,
+            commands::export_play_stats,
+            commands::start_session,
+            commands::stop_session,
+            commands::pause_session,
+            commands::resume_session,
+            commands::acknowledge_alert
         ])
         .setup(|app| {
             logging::init_logger();
@@ -47,6 +64,12 @@ pub fn run() {
             setup_monitor(command_rx, shared_state.clone());
             app.manage(AppState::new(shared_state.clone(), command_tx));
 
+            // Start optional OpenMetrics endpoint
+            metrics_server::spawn_metrics_server(shared_state.clone());
+
+            // Show desktop notifications for new alerts
+            notifications::spawn_notifier(app.handle().clone(), shared_state.clone());
+
             // Start frontend state emit loop
             start_emit_loop(app.handle().clone(), shared_state);
 
@@ -0,0 +1,112 @@
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use log::{error, info, warn};
+
+use crate::usecase::metrics::{render_openmetrics, OPENMETRICS_CONTENT_TYPE};
+use crate::usecase::state_publisher::MonitorSharedState;
+
+/// How often the server re-checks `metrics_listen_addr` while idle.
+const CONFIG_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Spawn the optional OpenMetrics endpoint on a background thread.
+///
+/// The listener follows `config.metrics_listen_addr` in the shared state, so the endpoint
+/// can be enabled, moved or disabled at runtime through `update_config`.
+pub fn spawn_metrics_server(shared_state: Arc<ArcSwap<MonitorSharedState>>) {
+    thread::spawn(move || {
+        let mut listener: Option<(String, TcpListener)> = None;
+
+        loop {
+            let wanted = shared_state.load().config.metrics_listen_addr.clone();
+
+            let current = listener.as_ref().map(|(addr, _)| addr.clone());
+            if wanted != current {
+                if let Some((addr, _)) = listener.take() {
+                    info!("Metrics endpoint on {} stopped", addr);
+                }
+                if let Some(addr) = wanted {
+                    match bind(&addr) {
+                        Ok(l) => {
+                            info!("Metrics endpoint listening on http://{}/metrics", addr);
+                            listener = Some((addr, l));
+                        }
+                        Err(e) => {
+                            error!("Failed to bind metrics endpoint on {}: {}", addr, e);
+                            // Avoid retrying every tick; wait for a config change
+                            wait_for_config_change(&shared_state, &addr);
+                        }
+                    }
+                }
+            }
+
+            match &listener {
+                Some((_, l)) => match l.accept() {
+                    Ok((stream, _)) => {
+                        // A slow or idle client only blocks its own thread, not other scrapes
+                        let shared_state = shared_state.clone();
+                        thread::spawn(move || {
+                            if let Err(e) = handle_connection(stream, &shared_state) {
+                                warn!("Metrics request failed: {}", e);
+                            }
+                        });
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(Duration::from_millis(50)),
+                    Err(e) => {
+                        warn!("Metrics accept failed: {}", e);
+                        thread::sleep(CONFIG_CHECK_INTERVAL);
+                    }
+                },
+                None => thread::sleep(CONFIG_CHECK_INTERVAL),
+            }
+        }
+    });
+}
+
+fn bind(addr: &str) -> std::io::Result<TcpListener> {
+    let listener = TcpListener::bind(addr)?;
+    listener.set_nonblocking(true)?;
+    Ok(listener)
+}
+
+fn wait_for_config_change(shared_state: &ArcSwap<MonitorSharedState>, failed_addr: &str) {
+    while shared_state.load().config.metrics_listen_addr.as_deref() == Some(failed_addr) {
+        thread::sleep(CONFIG_CHECK_INTERVAL);
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, shared_state: &ArcSwap<MonitorSharedState>) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(2)))?;
+
+    let mut request_line = String::new();
+    let mut reader = BufReader::new(stream.try_clone()?);
+    reader.read_line(&mut request_line)?;
+    // Drain headers
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or("");
+    let (status, content_type, body) = if path == "/metrics" || path.starts_with("/metrics?") {
+        let state = shared_state.load();
+        ("200 OK", OPENMETRICS_CONTENT_TYPE, render_openmetrics(&state))
+    } else {
+        ("404 Not Found", "text/plain; charset=utf-8", "Not Found\n".to_string())
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
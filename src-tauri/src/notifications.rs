@@ -0,0 +1,40 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use arc_swap::ArcSwap;
+use chrono::Utc;
+use log::{info, warn};
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+use crate::usecase::alert_notifications::NotificationThrottle;
+use crate::usecase::state_publisher::MonitorSharedState;
+
+/// How often the published alerts are checked for new ones.
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Spawn a background thread that shows desktop notifications for new alerts, as allowed
+/// by `config.notifications`.
+pub fn spawn_notifier(app_handle: AppHandle, shared_state: Arc<ArcSwap<MonitorSharedState>>) {
+    thread::spawn(move || {
+        let mut throttle = NotificationThrottle::new(Utc::now());
+
+        loop {
+            thread::sleep(CHECK_INTERVAL);
+            let state = shared_state.load();
+            for notification in throttle.take(&state.alerts, &state.config.notifications, Utc::now()) {
+                let result = app_handle
+                    .notification()
+                    .builder()
+                    .title("Switch Life Manager")
+                    .body(&notification.body)
+                    .show();
+                match result {
+                    Ok(()) => info!("Notified alerts {:?}: {}", notification.alert_ids, notification.body),
+                    Err(e) => warn!("Failed to show notification for alerts {:?}: {}", notification.alert_ids, e),
+                }
+            }
+        }
+    });
+}
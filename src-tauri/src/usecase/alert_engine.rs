@@ -0,0 +1,279 @@
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, VecDeque};
+
+use crate::domain::models::{
+    Alert, AlertKind, AlertLog, AlertRule, ControllerProfile, LogicalKey, SessionKeyStats, SwitchModelInfo,
+};
+
+/// Lifetime totals of one key each time they changed, reaching back far enough to cover
+/// the last `window_presses` presses.
+#[derive(Debug, Default)]
+struct PressWindow {
+    checkpoints: VecDeque<SessionKeyStats>,
+}
+
+impl PressWindow {
+    /// Add the current totals. Returns the chatter rate in percent over at least the last
+    /// `window_presses` presses, or `None` until that many presses were seen.
+    fn update(&mut self, totals: SessionKeyStats, window_presses: u64) -> Option<f64> {
+        if let Some(last) = self.checkpoints.back() {
+            // The switch was replaced or its stats reset
+            if totals.presses < last.presses || totals.chatters < last.chatters {
+                self.checkpoints.clear();
+            }
+        }
+        if self.checkpoints.back() != Some(&totals) {
+            self.checkpoints.push_back(totals.clone());
+        }
+        // Keep only the newest checkpoint that is still a full window back
+        while self.checkpoints.len() >= 2 && totals.presses - self.checkpoints[1].presses >= window_presses {
+            self.checkpoints.pop_front();
+        }
+
+        let oldest = self.checkpoints.front()?;
+        let presses = totals.presses - oldest.presses;
+        if presses == 0 || presses < window_presses {
+            return None;
+        }
+        Some((totals.chatters - oldest.chatters) as f64 / presses as f64 * 100.0)
+    }
+}
+
+/// Evaluates `AlertRule`s against controller stats and keeps `AlertLog` up to date.
+///
+/// Press windows only live in memory, so chatter rates start over when the app starts.
+#[derive(Debug)]
+pub struct AlertEngine {
+    models: Vec<SwitchModelInfo>,
+    /// By controller id, then key and window size.
+    windows: HashMap<String, HashMap<(LogicalKey, u64), PressWindow>>,
+}
+
+impl AlertEngine {
+    pub fn new(models: Vec<SwitchModelInfo>) -> Self {
+        Self { models, windows: HashMap::new() }
+    }
+
+    /// Raise alerts for the per-key rules a controller breaks and resolve the ones it no
+    /// longer does. `in_session` is whether a session is counting this controller's presses.
+    /// Returns the newly raised alerts.
+    ///
+    /// Press windows of keys and window sizes no longer in use are dropped.
+    pub fn evaluate(
+        &mut self,
+        rules: &[AlertRule],
+        controller_id: &str,
+        profile: &ControllerProfile,
+        in_session: bool,
+        log: &mut AlertLog,
+        now: DateTime<Utc>,
+    ) -> Vec<Alert> {
+        let mut raised = Vec::new();
+        let mut keys: Vec<&LogicalKey> = profile.switches.keys().collect();
+        keys.sort();
+        if !self.windows.contains_key(controller_id) {
+            self.windows.insert(controller_id.to_string(), HashMap::new());
+        }
+        let windows = self.windows.get_mut(controller_id).unwrap();
+
+        for rule in rules {
+            let kind = rule.kind();
+            for &key in &keys {
+                let stats = &profile.switches[key].stats;
+                let breach = match *rule {
+                    AlertRule::ChatterRate { percent, window_presses } => {
+                        let totals = SessionKeyStats { presses: stats.total_presses, chatters: stats.total_chatters };
+                        let window = windows.entry((key.clone(), window_presses)).or_default();
+                        match window.update(totals, window_presses) {
+                            Some(rate) if rate > percent => Some(Some(format!(
+                                "{} chattered on {:.1}% of its last {} presses",
+                                key, rate, window_presses
+                            ))),
+                            Some(_) => Some(None),
+                            // Not enough presses yet to tell either way
+                            None => None,
+                        }
+                    }
+                    AlertRule::Lifespan { percent } => {
+                        let switch_model_id = &profile.switches[key].switch_model_id;
+                        let used = self
+                            .models
+                            .iter()
+                            .find(|m| &m.id == switch_model_id)
+                            .filter(|m| m.rated_lifespan_presses > 0)
+                            .map(|m| stats.total_presses as f64 / m.rated_lifespan_presses as f64 * 100.0);
+                        match used {
+                            Some(used) if used >= percent => Some(Some(format!(
+                                "{} has used {:.0}% of its rated lifespan",
+                                key, used
+                            ))),
+                            _ => Some(None),
+                        }
+                    }
+                    AlertRule::SessionChatters { count } => {
+                        if in_session && count > 0 && stats.last_session_chatters >= count {
+                            Some(Some(format!(
+                                "{} chattered {} times this session",
+                                key, stats.last_session_chatters
+                            )))
+                        } else {
+                            Some(None)
+                        }
+                    }
+                    AlertRule::DisconnectedInSession => None,
+                };
+
+                match breach {
+                    Some(Some(message)) => {
+                        if let Some(alert) = log.raise(kind, controller_id, Some(key), message, now) {
+                            raised.push(alert.clone());
+                        }
+                    }
+                    Some(None) => {
+                        log.resolve(kind, controller_id, Some(key), now);
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        windows.retain(|(key, size), _| {
+            profile.switches.contains_key(key)
+                && rules.iter().any(|rule| {
+                    matches!(rule, AlertRule::ChatterRate { window_presses, .. } if window_presses == size)
+                })
+        });
+
+        if !in_session {
+            log.resolve(AlertKind::DisconnectedInSession, controller_id, None, now);
+        }
+        raised
+    }
+
+    /// Drop the press windows of controllers for which `keep` returns false.
+    pub fn retain_controllers(&mut self, keep: impl Fn(&str) -> bool) {
+        self.windows.retain(|id, _| keep(id));
+    }
+
+    /// Raise or resolve the disconnect alert of the active controller when its connection
+    /// changes. Returns the alert if one was raised.
+    pub fn connection_changed(
+        rules: &[AlertRule],
+        controller_id: &str,
+        connected: bool,
+        in_session: bool,
+        log: &mut AlertLog,
+        now: DateTime<Utc>,
+    ) -> Option<Alert> {
+        let kind = AlertKind::DisconnectedInSession;
+        if connected {
+            log.resolve(kind, controller_id, None, now);
+            return None;
+        }
+        if !in_session || !rules.contains(&AlertRule::DisconnectedInSession) {
+            return None;
+        }
+        let message = "Controller disconnected during the session".to_string();
+        log.raise(kind, controller_id, None, message, now).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::{default_alert_rules, get_default_switch_models, ButtonStats, SwitchData};
+
+    fn profile_with(stats: ButtonStats) -> ControllerProfile {
+        let mut profile = ControllerProfile::default();
+        profile.switches.insert(LogicalKey::Key1, SwitchData {
+            switch_model_id: get_default_switch_models()[0].id.clone(),
+            stats,
+            last_replaced_at: None,
+        });
+        profile
+    }
+
+    #[test]
+    fn test_press_window_covers_last_presses() {
+        let mut window = PressWindow::default();
+        let totals = |presses, chatters| SessionKeyStats { presses, chatters };
+
+        assert_eq!(window.update(totals(1000, 50), 100), None);
+        assert_eq!(window.update(totals(1050, 50), 100), None);
+        // 100 presses since the first checkpoint, 5 of them chattered
+        assert_eq!(window.update(totals(1100, 55), 100), Some(5.0));
+        // The first checkpoint dropped out, the window now starts at 1100
+        assert_eq!(window.update(totals(1200, 55), 100), Some(0.0));
+        // A reset starts over
+        assert_eq!(window.update(totals(10, 0), 100), None);
+    }
+
+    #[test]
+    fn test_rules_raise_and_resolve_alerts() {
+        let rules = vec![
+            AlertRule::ChatterRate { percent: 2.0, window_presses: 100 },
+            AlertRule::SessionChatters { count: 5 },
+        ];
+        let mut engine = AlertEngine::new(get_default_switch_models());
+        let mut log = AlertLog::default();
+        let now = Utc::now();
+
+        let stats = |total_presses, total_chatters, last_session_chatters| ButtonStats {
+            total_presses,
+            total_chatters,
+            last_session_chatters,
+            ..Default::default()
+        };
+
+        assert!(engine.evaluate(&rules, "c1", &profile_with(stats(0, 0, 0)), true, &mut log, now).is_empty());
+        let raised = engine.evaluate(&rules, "c1", &profile_with(stats(100, 5, 5)), true, &mut log, now);
+        let kinds: Vec<AlertKind> = raised.iter().map(|a| a.kind).collect();
+        assert_eq!(kinds, [AlertKind::ChatterRate, AlertKind::SessionChatters]);
+
+        // Still breached: nothing new
+        assert!(engine.evaluate(&rules, "c1", &profile_with(stats(150, 6, 6)), true, &mut log, now).is_empty());
+        // Session over and the last 100 presses were clean
+        engine.evaluate(&rules, "c1", &profile_with(stats(250, 6, 6)), false, &mut log, now);
+        assert_eq!(log.active().count(), 0);
+    }
+
+    #[test]
+    fn test_unused_press_windows_are_dropped() {
+        let mut engine = AlertEngine::new(get_default_switch_models());
+        let mut log = AlertLog::default();
+        let now = Utc::now();
+        let profile = profile_with(ButtonStats { total_presses: 10, ..Default::default() });
+        let chatter_rate = |window_presses| vec![AlertRule::ChatterRate { percent: 2.0, window_presses }];
+
+        engine.evaluate(&chatter_rate(100), "c1", &profile, false, &mut log, now);
+        engine.evaluate(&chatter_rate(100), "c2", &profile, false, &mut log, now);
+        engine.evaluate(&chatter_rate(200), "c1", &profile, false, &mut log, now);
+        let sizes: Vec<u64> = engine.windows["c1"].keys().map(|(_, size)| *size).collect();
+        assert_eq!(sizes, [200]);
+
+        engine.evaluate(&chatter_rate(200), "c1", &ControllerProfile::default(), false, &mut log, now);
+        assert!(engine.windows["c1"].is_empty());
+
+        engine.retain_controllers(|id| id == "c1");
+        assert!(!engine.windows.contains_key("c2"));
+    }
+
+    #[test]
+    fn test_lifespan_and_disconnect_alerts() {
+        let rules = default_alert_rules();
+        let mut engine = AlertEngine::new(get_default_switch_models());
+        let mut log = AlertLog::default();
+        let now = Utc::now();
+        let rated = get_default_switch_models()[0].rated_lifespan_presses;
+
+        let worn = profile_with(ButtonStats { total_presses: rated * 95 / 100, ..Default::default() });
+        let raised = engine.evaluate(&rules, "c1", &worn, false, &mut log, now);
+        assert_eq!(raised[0].kind, AlertKind::Lifespan);
+
+        assert!(AlertEngine::connection_changed(&rules, "c1", false, false, &mut log, now).is_none());
+        assert!(AlertEngine::connection_changed(&rules, "c1", false, true, &mut log, now).is_some());
+        AlertEngine::connection_changed(&rules, "c1", true, true, &mut log, now);
+        let active: Vec<AlertKind> = log.active().map(|a| a.kind).collect();
+        assert_eq!(active, [AlertKind::Lifespan]);
+    }
+}
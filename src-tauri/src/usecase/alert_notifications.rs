@@ -0,0 +1,138 @@
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+use crate::domain::models::{Alert, AlertKind, NotificationConfig};
+
+/// One desktop notification, covering one or more alerts of the same kind.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertNotification {
+    pub kind: AlertKind,
+    /// Oldest first.
+    pub alert_ids: Vec<u64>,
+    /// The alert messages, one per line.
+    pub body: String,
+}
+
+/// Picks the newly raised alerts worth a desktop notification.
+///
+/// Alerts raised before the notifier started, such as ones loaded with the profile, never
+/// notify, nor do muted ones. An alert raised within `min_interval_secs` of the last
+/// notification of its kind waits, and the waiting alerts of a kind that are still active
+/// notify together once the interval has passed.
+#[derive(Debug)]
+pub struct NotificationThrottle {
+    started_at: DateTime<Utc>,
+    last_seen_id: u64,
+    last_sent: HashMap<AlertKind, DateTime<Utc>>,
+    /// Rate-limited alert ids by kind, oldest first.
+    pending: HashMap<AlertKind, Vec<u64>>,
+}
+
+impl NotificationThrottle {
+    pub fn new(started_at: DateTime<Utc>) -> Self {
+        Self { started_at, last_seen_id: 0, last_sent: HashMap::new(), pending: HashMap::new() }
+    }
+
+    /// Notifications due now for the alerts in `alerts`, oldest first.
+    pub fn take(&mut self, alerts: &[Alert], config: &NotificationConfig, now: DateTime<Utc>) -> Vec<AlertNotification> {
+        let last_seen_id = self.last_seen_id;
+        for alert in alerts.iter().filter(|a| a.id > last_seen_id) {
+            self.last_seen_id = alert.id;
+            if alert.is_active() && alert.raised_at >= self.started_at {
+                self.pending.entry(alert.kind).or_default().push(alert.id);
+            }
+        }
+
+        let min_interval = Duration::seconds(config.min_interval_secs as i64);
+        let mut due = Vec::new();
+        for (kind, ids) in self.pending.iter_mut() {
+            if !config.enabled || config.muted_kinds.contains(kind) {
+                ids.clear();
+                continue;
+            }
+            if self.last_sent.get(kind).is_some_and(|&sent| now - sent < min_interval) {
+                continue;
+            }
+            // Alerts resolved while they waited are no longer worth a notification
+            let active: Vec<&Alert> = std::mem::take(ids)
+                .into_iter()
+                .filter_map(|id| alerts.iter().find(|a| a.id == id && a.is_active()))
+                .collect();
+            if active.is_empty() {
+                continue;
+            }
+            self.last_sent.insert(*kind, now);
+            due.push(AlertNotification {
+                kind: *kind,
+                alert_ids: active.iter().map(|a| a.id).collect(),
+                body: active.iter().map(|a| a.message.as_str()).collect::<Vec<_>>().join("\n"),
+            });
+        }
+        self.pending.retain(|_, ids| !ids.is_empty());
+        due.sort_by_key(|n| n.alert_ids[0]);
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(id: u64, kind: AlertKind, raised_at: DateTime<Utc>) -> Alert {
+        Alert {
+            id,
+            kind,
+            controller_id: "c1".to_string(),
+            key: None,
+            message: format!("alert {}", id),
+            raised_at,
+            acknowledged_at: None,
+            resolved_at: None,
+        }
+    }
+
+    fn ids(notifications: &[AlertNotification]) -> Vec<Vec<u64>> {
+        notifications.iter().map(|n| n.alert_ids.clone()).collect()
+    }
+
+    #[test]
+    fn test_rate_limited_alerts_notify_together_after_interval() {
+        let start = Utc::now();
+        let mut throttle = NotificationThrottle::new(start);
+        let config = NotificationConfig { min_interval_secs: 60, ..Default::default() };
+        let later = start + Duration::seconds(1);
+
+        let mut alerts = vec![
+            alert(1, AlertKind::Lifespan, start - Duration::hours(1)),
+            alert(2, AlertKind::ChatterRate, later),
+            alert(3, AlertKind::ChatterRate, later),
+            alert(4, AlertKind::SaveFailed, later),
+        ];
+        let sent = throttle.take(&alerts, &config, later);
+        assert_eq!(ids(&sent), [vec![2, 3], vec![4]]);
+        assert_eq!(sent[0].body, "alert 2\nalert 3");
+        assert!(throttle.take(&alerts, &config, later).is_empty());
+
+        // Within the interval the new alerts wait
+        alerts.push(alert(5, AlertKind::ChatterRate, later));
+        alerts.push(alert(6, AlertKind::ChatterRate, later));
+        alerts.push(alert(7, AlertKind::ChatterRate, later));
+        assert!(throttle.take(&alerts, &config, later + Duration::seconds(30)).is_empty());
+        alerts[5].resolved_at = Some(later + Duration::seconds(40));
+
+        let sent = throttle.take(&alerts, &config, later + Duration::seconds(61));
+        assert_eq!(ids(&sent), [vec![5, 7]]);
+        assert!(throttle.take(&alerts, &config, later + Duration::seconds(200)).is_empty());
+    }
+
+    #[test]
+    fn test_muted_kinds_do_not_notify() {
+        let start = Utc::now();
+        let mut throttle = NotificationThrottle::new(start);
+        let config = NotificationConfig { muted_kinds: vec![AlertKind::SaveFailed], ..Default::default() };
+
+        let alerts = [alert(1, AlertKind::SaveFailed, start), alert(2, AlertKind::Lifespan, start)];
+        let kinds: Vec<AlertKind> = throttle.take(&alerts, &config, start).iter().map(|n| n.kind).collect();
+        assert_eq!(kinds, [AlertKind::Lifespan]);
+    }
+}
@@ -0,0 +1,181 @@
+use chrono::Utc;
+
+use crate::domain::models::{
+    ControllerInfo, ControllerProfile, ControllerSwitchEntry, ControllerSwitchReason, UserProfile,
+};
+use crate::domain::presets::preset_for_device_name;
+
+/// Id of the profile created before any controller was identified.
+pub const DEFAULT_CONTROLLER_ID: &str = "default";
+
+/// Upper bound of `UserProfile::controller_switch_history` entries.
+pub const MAX_CONTROLLER_SWITCH_HISTORY: usize = 100;
+
+/// How a profile for a connected controller was obtained.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfileProvision {
+    Existing,
+    /// The controller-less `default` profile was moved to this controller.
+    AdoptedDefault,
+    /// A new profile was created from the named preset.
+    Created { preset_name: String },
+}
+
+/// Chooses and prepares controller profiles when controllers connect.
+pub struct ControllerSelector;
+
+impl ControllerSelector {
+    /// Picks the controller to activate after the set of connected controllers changed.
+    ///
+    /// The first connected id in `priority` wins; otherwise the active controller is kept
+    /// while connected, else the first connected one that already has a profile is used,
+    /// else the first connected one. Returns `None` to keep `active_id`.
+    pub fn choose(
+        connected: &[ControllerInfo],
+        priority: &[String],
+        active_id: &str,
+        has_profile: impl Fn(&str) -> bool,
+    ) -> Option<String> {
+        let is_connected = |id: &str| connected.iter().any(|c| c.id == id);
+
+        let preferred = priority
+            .iter()
+            .find(|id| is_connected(id))
+            .cloned()
+            .or_else(|| is_connected(active_id).then(|| active_id.to_string()))
+            .or_else(|| connected.iter().find(|c| has_profile(&c.id)).map(|c| c.id.clone()))
+            .or_else(|| connected.first().map(|c| c.id.clone()))?;
+
+        (preferred != active_id).then_some(preferred)
+    }
+
+    /// Make sure `controller` has a profile.
+    ///
+    /// With `adopt_default` the controller takes over the `default` profile while that is the
+    /// only one, so stats recorded before profiles were per-device are kept. Otherwise it gets
+    /// a preset chosen by name.
+    pub fn ensure_profile(
+        profile: &mut UserProfile,
+        controller: &ControllerInfo,
+        adopt_default: bool,
+    ) -> ProfileProvision {
+        if profile.controllers.contains_key(&controller.id) {
+            return ProfileProvision::Existing;
+        }
+
+        let only_default = adopt_default
+            && profile.controllers.len() == 1
+            && profile.controllers.contains_key(DEFAULT_CONTROLLER_ID);
+        if only_default {
+            let default = profile.controllers.remove(DEFAULT_CONTROLLER_ID).unwrap();
+            profile.controllers.insert(controller.id.clone(), default);
+            if profile.active_controller_id == DEFAULT_CONTROLLER_ID {
+                profile.active_controller_id = controller.id.clone();
+            }
+            return ProfileProvision::AdoptedDefault;
+        }
+
+        let mapping = preset_for_device_name(&controller.name);
+        let preset_name = mapping.profile_name.clone();
+        profile.controllers.insert(
+            controller.id.clone(),
+            ControllerProfile {
+                mapping,
+                ..Default::default()
+            },
+        );
+        ProfileProvision::Created { preset_name }
+    }
+
+    /// Make `controller_id` the active profile and record the change. Returns false if it
+    /// already was active.
+    ///
+    /// A controller without a profile gets one; a manual choice may adopt `default`.
+    pub fn activate(profile: &mut UserProfile, controller_id: &str, reason: ControllerSwitchReason) -> bool {
+        if profile.active_controller_id == controller_id {
+            return false;
+        }
+
+        let from_controller_id = profile.active_controller_id.clone();
+        if !profile.controllers.contains_key(controller_id) {
+            let controller = ControllerInfo { id: controller_id.to_string(), name: String::new() };
+            Self::ensure_profile(profile, &controller, reason == ControllerSwitchReason::Manual);
+        }
+        profile.controller_switch_history.push(ControllerSwitchEntry {
+            date: Utc::now(),
+            from_controller_id,
+            to_controller_id: controller_id.to_string(),
+            reason,
+        });
+        if profile.controller_switch_history.len() > MAX_CONTROLLER_SWITCH_HISTORY {
+            let excess = profile.controller_switch_history.len() - MAX_CONTROLLER_SWITCH_HISTORY;
+            profile.controller_switch_history.drain(..excess);
+        }
+        profile.active_controller_id = controller_id.to_string();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(id: &str, name: &str) -> ControllerInfo {
+        ControllerInfo {
+            id: id.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_choose_respects_priority() {
+        let connected = vec![info("a", "Pad A"), info("b", "Pad B")];
+        let priority = vec!["x".to_string(), "b".to_string()];
+
+        assert_eq!(ControllerSelector::choose(&connected, &priority, "a", |_| false), Some("b".to_string()));
+        assert_eq!(ControllerSelector::choose(&connected, &priority, "b", |_| false), None);
+    }
+
+    #[test]
+    fn test_choose_keeps_connected_active_controller() {
+        let connected = vec![info("a", "Pad A"), info("b", "Pad B")];
+        assert_eq!(ControllerSelector::choose(&connected, &[], "b", |_| true), None);
+        assert_eq!(ControllerSelector::choose(&connected, &[], "gone", |id| id == "b"), Some("b".to_string()));
+        // Without any known device the first connected one gets a profile
+        assert_eq!(ControllerSelector::choose(&connected, &[], "gone", |_| false), Some("a".to_string()));
+        assert_eq!(ControllerSelector::choose(&[], &[], "gone", |_| true), None);
+    }
+
+    #[test]
+    fn test_only_chosen_controller_adopts_default_profile() {
+        let mut profile = UserProfile::default();
+        let provision = ControllerSelector::ensure_profile(&mut profile, &info("pad-0", "Generic"), false);
+        assert_eq!(provision, ProfileProvision::Created { preset_name: "Default".to_string() });
+        assert!(profile.controllers.contains_key(DEFAULT_CONTROLLER_ID));
+        profile.controllers.remove("pad-0");
+
+        let provision = ControllerSelector::ensure_profile(&mut profile, &info("pad-1", "Generic"), true);
+        assert_eq!(provision, ProfileProvision::AdoptedDefault);
+        assert_eq!(profile.active_controller_id, "pad-1");
+        assert!(!profile.controllers.contains_key(DEFAULT_CONTROLLER_ID));
+
+        let provision = ControllerSelector::ensure_profile(&mut profile, &info("pad-2", "PHOENIXWAN"), true);
+        assert_eq!(provision, ProfileProvision::Created { preset_name: "PhoenixWAN".to_string() });
+        assert_eq!(profile.controllers["pad-2"].mapping.profile_name, "PhoenixWAN");
+    }
+
+    #[test]
+    fn test_activate_records_history() {
+        let mut profile = UserProfile::default();
+        assert!(ControllerSelector::activate(&mut profile, "pad-1", ControllerSwitchReason::Manual));
+        // Choosing the controller by hand adopts the default profile
+        assert!(!profile.controllers.contains_key(DEFAULT_CONTROLLER_ID));
+        assert!(!ControllerSelector::activate(&mut profile, "pad-1", ControllerSwitchReason::Manual));
+
+        assert_eq!(profile.controller_switch_history.len(), 1);
+        let entry = &profile.controller_switch_history[0];
+        assert_eq!(entry.from_controller_id, DEFAULT_CONTROLLER_ID);
+        assert_eq!(entry.to_controller_id, "pad-1");
+        assert_eq!(entry.reason, ControllerSwitchReason::Manual);
+    }
+}
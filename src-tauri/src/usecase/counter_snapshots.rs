@@ -0,0 +1,125 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+use crate::domain::models::{ControllerProfile, CounterSnapshot, LogicalKey, SessionKeyStats};
+
+/// Maximum number of snapshots kept in `ControllerProfile.counter_snapshots`. Snapshots are
+/// only taken when something was pressed, so this covers years of hourly snapshots.
+pub const MAX_COUNTER_SNAPSHOTS: usize = 10_000;
+
+/// Usage between two consecutive snapshots.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CounterUsage {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub keys: HashMap<LogicalKey, SessionKeyStats>,
+}
+
+/// Takes per-key total snapshots and turns them back into usage over time.
+pub struct CounterSnapshots;
+
+impl CounterSnapshots {
+    /// Lifetime presses and chatters of every key.
+    pub fn totals(profile: &ControllerProfile) -> HashMap<LogicalKey, SessionKeyStats> {
+        profile
+            .switches
+            .iter()
+            .map(|(key, switch)| {
+                let totals = SessionKeyStats {
+                    presses: switch.stats.total_presses,
+                    chatters: switch.stats.total_chatters,
+                };
+                (key.clone(), totals)
+            })
+            .collect()
+    }
+
+    /// Record a snapshot if any total changed since the last one.
+    /// Returns true if a snapshot was taken.
+    pub fn take_if_changed(profile: &mut ControllerProfile, now: DateTime<Utc>) -> bool {
+        let totals = Self::totals(profile);
+        if profile.counter_snapshots.last().is_some_and(|s| s.totals == totals) {
+            return false;
+        }
+        profile.counter_snapshots.push(CounterSnapshot { taken_at: now, totals });
+        if profile.counter_snapshots.len() > MAX_COUNTER_SNAPSHOTS {
+            profile.counter_snapshots.remove(0);
+        }
+        true
+    }
+
+    /// Growth of each key between consecutive snapshots. A total that went down, because the
+    /// switch was replaced or its stats reset, counts from zero.
+    pub fn usage(snapshots: &[CounterSnapshot]) -> Vec<CounterUsage> {
+        snapshots
+            .windows(2)
+            .map(|pair| {
+                let (before, after) = (&pair[0], &pair[1]);
+                let keys = after
+                    .totals
+                    .iter()
+                    .filter_map(|(key, now)| {
+                        let was = before.totals.get(key).cloned().unwrap_or_default();
+                        let grown = |was: u64, now: u64| if now >= was { now - was } else { now };
+                        let usage = SessionKeyStats {
+                            presses: grown(was.presses, now.presses),
+                            chatters: grown(was.chatters, now.chatters),
+                        };
+                        (usage != SessionKeyStats::default()).then(|| (key.clone(), usage))
+                    })
+                    .collect();
+                CounterUsage { from: before.taken_at, to: after.taken_at, keys }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use crate::domain::models::{ButtonStats, SwitchData};
+
+    fn set_presses(profile: &mut ControllerProfile, key: LogicalKey, presses: u64) {
+        profile.switches.insert(key, SwitchData {
+            switch_model_id: "omron_d2mv_01_1c3".to_string(),
+            stats: ButtonStats { total_presses: presses, ..Default::default() },
+            last_replaced_at: None,
+        });
+    }
+
+    #[test]
+    fn test_snapshot_only_when_changed() {
+        let mut profile = ControllerProfile::default();
+        let t0 = Utc::now();
+        set_presses(&mut profile, LogicalKey::Key1, 100);
+
+        assert!(CounterSnapshots::take_if_changed(&mut profile, t0));
+        assert!(!CounterSnapshots::take_if_changed(&mut profile, t0 + Duration::hours(1)));
+        set_presses(&mut profile, LogicalKey::Key1, 150);
+        assert!(CounterSnapshots::take_if_changed(&mut profile, t0 + Duration::hours(2)));
+        assert_eq!(profile.counter_snapshots.len(), 2);
+    }
+
+    #[test]
+    fn test_usage_between_snapshots() {
+        let mut profile = ControllerProfile::default();
+        let t0 = Utc::now();
+        set_presses(&mut profile, LogicalKey::Key1, 100);
+        CounterSnapshots::take_if_changed(&mut profile, t0);
+        set_presses(&mut profile, LogicalKey::Key1, 150);
+        set_presses(&mut profile, LogicalKey::Key2, 20);
+        CounterSnapshots::take_if_changed(&mut profile, t0 + Duration::hours(1));
+        // Key1 replaced and pressed 5 times on the new switch
+        set_presses(&mut profile, LogicalKey::Key1, 5);
+        CounterSnapshots::take_if_changed(&mut profile, t0 + Duration::hours(2));
+
+        let usage = CounterSnapshots::usage(&profile.counter_snapshots);
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].to, t0 + Duration::hours(1));
+        assert_eq!(usage[0].keys[&LogicalKey::Key1].presses, 50);
+        assert_eq!(usage[0].keys[&LogicalKey::Key2].presses, 20);
+        assert_eq!(usage[1].keys[&LogicalKey::Key1].presses, 5);
+        assert!(!usage[1].keys.contains_key(&LogicalKey::Key2));
+    }
+}
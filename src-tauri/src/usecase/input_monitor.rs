@@ -62,7 +62,8 @@ impl ChatterDetector {
             }
 
             let is_chatter = if let Some(last_release) = state.last_release_at {
-                now_ms - last_release < self.chatter_threshold_ms
+                // Device timestamps are not guaranteed to be monotonic
+                now_ms.saturating_sub(last_release) < self.chatter_threshold_ms
             } else {
                 false
             };
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::domain::models::{ButtonStats, LogicalKey, SwitchData};
+use crate::usecase::state_publisher::MonitorSharedState;
+
+/// Content type of the text produced by `render_openmetrics`.
+pub const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Metric family name, help text and the `ButtonStats` field it reads.
+type KeyCounter = (&'static str, &'static str, fn(&ButtonStats) -> u64);
+
+/// Controller id, connection state and its per-key stats in key order.
+type ControllerSeries<'a> = (String, bool, Vec<(&'a LogicalKey, &'a ButtonStats)>);
+
+/// Renders the monitor state in the OpenMetrics text format.
+///
+/// Per-key counters are labelled with the controller id and the `LogicalKey`, for the
+/// active controller and every other monitored one, and emitted in `LogicalKey` order
+/// so scrapes are stable.
+pub fn render_openmetrics(state: &MonitorSharedState) -> String {
+    let mut out = String::new();
+
+    // The active controller comes from the top-level fields, which are always published
+    let mut controllers: Vec<ControllerSeries> =
+        vec![(state.active_controller_id.clone(), state.is_connected, sorted_stats(&state.switches))];
+    for monitored in state.monitored_controllers.iter().filter(|c| !c.is_active) {
+        controllers.push((monitored.controller_id.clone(), monitored.is_connected, sorted_stats(&monitored.switches)));
+    }
+
+    let key_counters: [KeyCounter; 4] = [
+        ("slm_button_presses", "Intended presses per key.", |s| s.total_presses),
+        ("slm_button_releases", "Releases per key.", |s| s.total_releases),
+        ("slm_button_chatters", "Presses detected as chatter per key.", |s| s.total_chatters),
+        ("slm_button_chatter_releases", "Releases followed by chatter per key.", |s| s.total_chatter_releases),
+    ];
+
+    for (name, help, value) in key_counters {
+        let _ = writeln!(out, "# TYPE {} counter", name);
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        for (controller, _, stats) in &controllers {
+            for (key, stats) in stats {
+                let _ = writeln!(
+                    out,
+                    "{}_total{{controller=\"{}\",key=\"{}\"}} {}",
+                    name,
+                    escape_label(controller),
+                    escape_label(&key.to_string()),
+                    value(stats)
+                );
+            }
+        }
+    }
+
+    let _ = writeln!(out, "# TYPE slm_controller_connected gauge");
+    let _ = writeln!(out, "# HELP slm_controller_connected Whether each monitored controller is connected.");
+    for (controller, is_connected, _) in &controllers {
+        let _ = writeln!(
+            out,
+            "slm_controller_connected{{controller=\"{}\"}} {}",
+            escape_label(controller),
+            *is_connected as u8
+        );
+    }
+
+    let _ = writeln!(out, "# TYPE slm_game_running gauge");
+    let _ = writeln!(out, "# HELP slm_game_running Whether the target game process is running.");
+    let _ = writeln!(out, "slm_game_running {}", state.is_game_running as u8);
+
+    let _ = writeln!(out, "# TYPE slm_session_active gauge");
+    let _ = writeln!(out, "# HELP slm_session_active Whether a session is running, from the game or started manually.");
+    let _ = writeln!(out, "slm_session_active {}", state.current_session.is_some() as u8);
+
+    let _ = writeln!(out, "# TYPE slm_monitor_polls counter");
+    let _ = writeln!(out, "# HELP slm_monitor_polls Completed input polling iterations.");
+    let _ = writeln!(out, "slm_monitor_polls_total {}", state.counters.polls_total);
+
+    let _ = writeln!(out, "# TYPE slm_monitor_configured_poll_interval_seconds gauge");
+    let _ = writeln!(
+        out,
+        "# HELP slm_monitor_configured_poll_interval_seconds Configured polling interval while connected."
+    );
+    let _ = writeln!(
+        out,
+        "slm_monitor_configured_poll_interval_seconds {}",
+        state.config.polling_rate_ms_connected as f64 / 1000.0
+    );
+
+    let timing = &state.poll_timing;
+    let _ = writeln!(out, "# TYPE slm_monitor_poll_interval_seconds gauge");
+    let _ = writeln!(
+        out,
+        "# HELP slm_monitor_poll_interval_seconds Measured polling interval over the recent window."
+    );
+    for (stat, value_us) in [
+        ("min", timing.min_us),
+        ("avg", timing.avg_us),
+        ("p99", timing.p99_us),
+        ("max", timing.max_us),
+    ] {
+        let _ = writeln!(
+            out,
+            "slm_monitor_poll_interval_seconds{{stat=\"{}\"}} {}",
+            stat,
+            value_us as f64 / 1_000_000.0
+        );
+    }
+
+    let _ = writeln!(out, "# TYPE slm_monitor_poll_overruns counter");
+    let _ = writeln!(out, "# HELP slm_monitor_poll_overruns Polls that came later than twice the configured interval.");
+    let _ = writeln!(out, "slm_monitor_poll_overruns_total {}", timing.overruns);
+
+    let _ = writeln!(out, "# TYPE slm_profile_saves counter");
+    let _ = writeln!(out, "# HELP slm_profile_saves Profile save attempts by result.");
+    let _ = writeln!(out, "slm_profile_saves_total{{result=\"success\"}} {}", state.counters.saves_succeeded);
+    let _ = writeln!(out, "slm_profile_saves_total{{result=\"failure\"}} {}", state.counters.saves_failed);
+
+    out.push_str("# EOF\n");
+    out
+}
+
+fn sorted_stats(switches: &HashMap<LogicalKey, SwitchData>) -> Vec<(&LogicalKey, &ButtonStats)> {
+    let mut stats: Vec<_> = switches.iter().map(|(key, data)| (key, &data.stats)).collect();
+    stats.sort_by_key(|(key, _)| *key);
+    stats
+}
+
+/// Escapes a label value per the OpenMetrics text format.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::usecase::state_publisher::MonitoredControllerState;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_render_per_key_counters() {
+        let mut state = MonitorSharedState {
+            active_controller_id: "pad\"1".to_string(),
+            is_connected: true,
+            ..Default::default()
+        };
+        for (key, presses) in [(LogicalKey::Key2, 20), (LogicalKey::Key1, 10)] {
+            state.switches.insert(key, SwitchData {
+                switch_model_id: "generic_unknown".to_string(),
+                stats: ButtonStats {
+                    total_presses: presses,
+                    total_chatters: 1,
+                    ..Default::default()
+                },
+                last_replaced_at: None,
+            });
+        }
+        state.counters.saves_failed = 2;
+        state.poll_timing.p99_us = 1_500;
+        state.monitored_controllers.push(MonitoredControllerState {
+            controller_id: "pad2".to_string(),
+            switches: Arc::new(state.switches.clone()),
+            ..Default::default()
+        });
+
+        let text = render_openmetrics(&state);
+
+        let key1 = text.find("slm_button_presses_total{controller=\"pad\\\"1\",key=\"Key1\"} 10").unwrap();
+        let key2 = text.find("slm_button_presses_total{controller=\"pad\\\"1\",key=\"Key2\"} 20").unwrap();
+        assert!(key1 < key2);
+        assert!(text.contains("slm_button_chatters_total{controller=\"pad\\\"1\",key=\"Key1\"} 1"));
+        assert!(text.contains("slm_controller_connected{controller=\"pad\\\"1\"} 1"));
+        assert!(text.contains("slm_button_presses_total{controller=\"pad2\",key=\"Key2\"} 20"));
+        assert!(text.contains("slm_controller_connected{controller=\"pad2\"} 0"));
+        assert!(text.contains("slm_profile_saves_total{result=\"failure\"} 2"));
+        assert!(text.contains("slm_monitor_poll_interval_seconds{stat=\"p99\"} 0.0015"));
+        assert!(text.ends_with("# EOF\n"));
+    }
+}
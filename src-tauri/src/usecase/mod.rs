@@ -3,6 +3,16 @@ pub mod state_publisher;
 pub mod input_monitor;
 pub mod switch_operations;
 pub mod session_manager;
+pub mod profile_transfer;
+pub mod profile_merge;
+pub mod stats_export;
+pub mod session_report;
+pub mod play_stats;
+pub mod counter_snapshots;
+pub mod alert_engine;
+pub mod alert_notifications;
+pub mod metrics;
+pub mod controller_selection;
 #[cfg(test)]
 pub mod test_helpers;
 #[cfg(test)]
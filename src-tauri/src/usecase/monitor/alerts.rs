@@ -0,0 +1,126 @@
+use chrono::{DateTime, Utc};
+use log::warn;
+
+use crate::domain::interfaces::InputSource;
+use crate::domain::models::{Alert, AlertKind};
+use crate::infrastructure::persistence::ConfigRepository;
+use crate::infrastructure::process_monitor::ProcessMonitor;
+use crate::usecase::alert_engine::AlertEngine;
+
+use super::MonitorService;
+
+impl<I: InputSource, P: ProcessMonitor, R: ConfigRepository> MonitorService<I, P, R> {
+    /// Evaluate the alert rules against every controller with a profile.
+    ///
+    /// The active controller is in the session while one runs; other controllers only once
+    /// their session stats were reset for it.
+    pub(super) fn evaluate_alerts(&mut self, now: DateTime<Utc>, force_publish: &mut bool) {
+        let session_running = self.profile.active_session.is_some();
+        self.alert_engine.retain_controllers(|id| self.profile.controllers.contains_key(id));
+        let mut raised = Vec::new();
+        for (id, profile) in &self.profile.controllers {
+            let in_session = session_running
+                && (*id == self.profile.active_controller_id
+                    || self.other_controllers.get(id).is_some_and(|d| d.in_session));
+            raised.extend(self.alert_engine.evaluate(
+                &self.profile.config.alert_rules,
+                id,
+                profile,
+                in_session,
+                &mut self.profile.alerts,
+                now,
+            ));
+        }
+
+        for alert in &raised {
+            self.announce_alert(alert);
+        }
+        if !raised.is_empty() {
+            *force_publish = true;
+        }
+    }
+
+    /// Raise or resolve the disconnect alert of the active controller.
+    pub(super) fn alert_connection_changed(&mut self, is_connected: bool, now: DateTime<Utc>) {
+        let raised = AlertEngine::connection_changed(
+            &self.profile.config.alert_rules,
+            &self.profile.active_controller_id,
+            is_connected,
+            self.profile.active_session.is_some(),
+            &mut self.profile.alerts,
+            now,
+        );
+        if let Some(alert) = raised {
+            self.announce_alert(&alert);
+        }
+    }
+
+    /// Raise an alert when an auto save fails and resolve it once one succeeds.
+    ///
+    /// A failed save cannot store its own alert; the next successful save stores it resolved.
+    pub(super) fn alert_save_result(&mut self, error: Option<String>, now: DateTime<Utc>) {
+        let kind = AlertKind::SaveFailed;
+        let Some(error) = error else {
+            self.profile.alerts.resolve(kind, "", None, now);
+            return;
+        };
+        let message = format!("Saving the profile failed: {}", error);
+        if let Some(alert) = self.profile.alerts.raise(kind, "", None, message, now).cloned() {
+            self.announce_alert(&alert);
+        }
+    }
+
+    /// Returns false if there is no unacknowledged alert with that id.
+    pub(crate) fn acknowledge_alert(&mut self, id: u64) -> bool {
+        self.profile.alerts.acknowledge(id, Utc::now())
+    }
+
+    fn announce_alert(&self, alert: &Alert) {
+        warn!("Alert: {}", alert.message);
+        self.publisher.update_status(alert.message.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::errors::InputError;
+    use crate::domain::models::{ActiveSession, AlertKind, SessionOrigin, UserProfile};
+    use crate::usecase::test_helpers::create_test_service;
+
+    #[test]
+    fn test_disconnect_mid_session_raises_alert_until_reconnect() {
+        let mut harness = create_test_service(UserProfile::default());
+        let service = &mut harness.service;
+        let (mut was_connected, mut force_publish) = (true, false);
+
+        // Disconnecting without a session is not an alert
+        service.handle_connection_state::<()>(&Err(InputError::Disconnected), &mut was_connected, &mut force_publish);
+        service.handle_connection_state(&Ok(()), &mut was_connected, &mut force_publish);
+        assert_eq!(service.profile.alerts.active().count(), 0);
+
+        service.profile.active_session = Some(ActiveSession::new(Utc::now(), None, SessionOrigin::Manual));
+        service.handle_connection_state::<()>(&Err(InputError::Disconnected), &mut was_connected, &mut force_publish);
+        let active: Vec<AlertKind> = service.profile.alerts.active().map(|a| a.kind).collect();
+        assert_eq!(active, [AlertKind::DisconnectedInSession]);
+
+        let id = service.profile.alerts.alerts[0].id;
+        assert!(service.acknowledge_alert(id));
+        service.handle_connection_state(&Ok(()), &mut was_connected, &mut force_publish);
+        assert_eq!(service.profile.alerts.active().count(), 0);
+    }
+
+    #[test]
+    fn test_save_failure_alert_resolves_on_next_save() {
+        let mut harness = create_test_service(UserProfile::default());
+        let service = &mut harness.service;
+
+        service.alert_save_result(Some("disk full".to_string()), Utc::now());
+        service.alert_save_result(Some("disk full".to_string()), Utc::now());
+        let active: Vec<&str> = service.profile.alerts.active().map(|a| a.message.as_str()).collect();
+        assert_eq!(active, ["Saving the profile failed: disk full"]);
+
+        service.alert_save_result(None, Utc::now());
+        assert_eq!(service.profile.alerts.active().count(), 0);
+    }
+}
@@ -1,11 +1,18 @@
-use log::{error, info};
+use log::{error, info, warn};
 use std::sync::Arc;
 use std::time::Duration;
 
 use crate::domain::interfaces::InputSource;
-use crate::infrastructure::persistence::ConfigRepository;
+use crate::domain::models::{
+    get_default_switch_models, ControllerProfile, ControllerSwitchReason, ImportMode, SessionOrigin,
+};
+use crate::infrastructure::persistence::{self, ConfigRepository};
 use crate::infrastructure::process_monitor::ProcessMonitor;
 use crate::usecase::input_monitor::ChatterDetector;
+use crate::usecase::play_stats::PlayStatsAggregator;
+use crate::usecase::profile_transfer::ProfileTransfer;
+use crate::usecase::session_report::SessionReporter;
+use crate::usecase::stats_export::StatsExporter;
 use crate::usecase::switch_operations::SwitchOperations;
 
 use super::commands::MonitorCommand;
@@ -30,16 +37,50 @@ impl<I: InputSource, P: ProcessMonitor, R: ConfigRepository> MonitorService<I, P
                     self.publisher.update_save_result(true, msg);
                 }
             }
+            MonitorCommand::StartSession => {
+                let msg = if self.start_manual_session(SessionOrigin::Manual) {
+                    "Session started"
+                } else {
+                    "A session is already running"
+                };
+                self.publisher.update_status(msg.to_string());
+            }
+            MonitorCommand::StopSession => {
+                let msg = if self.stop_session() { "Session stopped" } else { "No session is running" };
+                self.publisher.update_status(msg.to_string());
+            }
+            MonitorCommand::PauseSession => {
+                if self.set_session_paused(true) {
+                    self.publisher.update_status("Session paused".to_string());
+                }
+            }
+            MonitorCommand::ResumeSession => {
+                if self.set_session_paused(false) {
+                    self.publisher.update_status("Session resumed".to_string());
+                }
+            }
             MonitorCommand::UpdateConfig(cfg) => {
                 // Update input method if changed
                 if cfg.input_method != self.profile.config.input_method {
                     self.input_source.set_input_method(cfg.input_method.clone());
+                    self.apply_button_layout();
                     info!("Input method switched to {:?}", cfg.input_method);
                 }
+                if cfg.input_method != self.profile.config.input_method
+                    || cfg.keyboard_bindings != self.profile.config.keyboard_bindings
+                {
+                    self.input_source.set_keyboard_bindings(&cfg.keyboard_bindings);
+                }
+                if cfg.input_method != self.profile.config.input_method || cfg.midi != self.profile.config.midi {
+                    self.input_source.set_midi_config(&cfg.midi);
+                }
 
-                self.profile.config = cfg;
+                self.profile.config = *cfg;
                 self.chatter_detector =
                     ChatterDetector::new(self.profile.config.chatter_threshold_ms);
+                for device in self.other_controllers.values_mut() {
+                    device.chatter_detector = ChatterDetector::new(self.profile.config.chatter_threshold_ms);
+                }
                 info!("Config updated");
             }
             MonitorCommand::UpdateMapping(name, bindings) => {
@@ -74,6 +115,13 @@ impl<I: InputSource, P: ProcessMonitor, R: ConfigRepository> MonitorService<I, P
                     info!("Set binding for key: {} -> button {}", key, button);
                 }
             }
+            MonitorCommand::SetButtonLayout(layout) => {
+                if let Some(active_profile) = self.profile.controllers.get_mut(&self.profile.active_controller_id) {
+                    active_profile.mapping.layout = layout;
+                    self.apply_button_layout();
+                    info!("Button layout set to {:?}", layout);
+                }
+            }
             MonitorCommand::ReplaceSwitch { key, new_model_id } => {
                 if let Some(active_profile) = self.profile.controllers.get_mut(&self.profile.active_controller_id) {
                     SwitchOperations::replace_switch(active_profile, key.clone(), new_model_id.clone());
@@ -92,16 +140,142 @@ impl<I: InputSource, P: ProcessMonitor, R: ConfigRepository> MonitorService<I, P
                     info!("Set last replaced date for {} to {}", key, date);
                 }
             }
+            MonitorCommand::AcknowledgeAlert(id) => {
+                if self.acknowledge_alert(id) {
+                    info!("Acknowledged alert {}", id);
+                }
+            }
             MonitorCommand::SetActiveController(id) => {
                 info!("Setting active controller to: {}", id);
-                self.profile.active_controller_id = id.clone();
-                // Ensure profile exists
-                if !self.profile.controllers.contains_key(&id) {
-                    self.profile.controllers.insert(id.clone(), crate::domain::models::ControllerProfile::default());
+                self.activate_controller(&id, ControllerSwitchReason::Manual);
+            }
+            MonitorCommand::ExportProfile { controller_id, controller_name, path } => {
+                let result = ProfileTransfer::export_bundle(&self.profile, &controller_id, controller_name)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|bundle| persistence::save_bundle(&path, &bundle));
+                match result {
+                    Ok(()) => {
+                        info!("Exported controller profile {} to {:?}", controller_id, path);
+                        self.publisher.update_status(format!("Exported profile {}", controller_id));
+                    }
+                    Err(e) => {
+                        error!("Profile export failed: {}", e);
+                        self.publisher.update_status(format!("Export failed: {}", e));
+                    }
                 }
-                let active_profile = self.profile.controllers.get(&id).unwrap();
-                self.cached_bindings = Arc::new(active_profile.mapping.bindings.clone());
             }
+            MonitorCommand::ImportProfile { path, mode } => {
+                let result = Self::load_import_baseline(&mode).and_then(|baseline| {
+                    let bundle = persistence::load_bundle(&path)?;
+                    ProfileTransfer::import_bundle(&mut self.profile, bundle, mode, baseline.as_ref())
+                        .map_err(anyhow::Error::from)
+                });
+                match result {
+                    Ok(outcome) => {
+                        info!("Imported controller profile {} from {:?}", outcome.controller_id, path);
+                        for conflict in &outcome.conflicts {
+                            warn!("Merge conflict: {:?}", conflict);
+                        }
+                        let msg = if outcome.conflicts.is_empty() {
+                            format!("Imported profile {}", outcome.controller_id)
+                        } else {
+                            format!(
+                                "Imported profile {} with {} conflict(s)",
+                                outcome.controller_id,
+                                outcome.conflicts.len()
+                            )
+                        };
+                        self.publisher.update_status(msg);
+                    }
+                    Err(e) => {
+                        error!("Profile import failed: {}", e);
+                        self.publisher.update_status(format!("Import failed: {}", e));
+                    }
+                }
+            }
+            MonitorCommand::ExportData { kind, format, path } => {
+                let Some(active_profile) = self.profile.controllers.get(&self.profile.active_controller_id) else {
+                    return;
+                };
+                let result = StatsExporter::render(
+                    active_profile,
+                    &get_default_switch_models(),
+                    &self.profile.config.game_definitions(),
+                    kind,
+                    format,
+                )
+                .map_err(anyhow::Error::from)
+                .and_then(|contents| persistence::write_file_atomic(&path, contents.as_bytes()));
+                match result {
+                    Ok(()) => {
+                        info!("Exported {:?} as {:?} to {:?}", kind, format, path);
+                        self.publisher.update_status(format!("Exported {:?}", kind));
+                    }
+                    Err(e) => {
+                        error!("Export failed: {}", e);
+                        self.publisher.update_status(format!("Export failed: {}", e));
+                    }
+                }
+            }
+            MonitorCommand::ExportPlayStats { period, utc_offset_minutes, format, path } => {
+                let Some(active_profile) = self.profile.controllers.get(&self.profile.active_controller_id) else {
+                    return;
+                };
+                let sessions = PlayStatsAggregator::sessions(active_profile);
+                let rollups = PlayStatsAggregator::rollup_at_offset(
+                    &sessions,
+                    &active_profile.counter_snapshots,
+                    period,
+                    utc_offset_minutes,
+                );
+                let result = PlayStatsAggregator::render(&rollups, format)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|contents| persistence::write_file_atomic(&path, contents.as_bytes()));
+                match result {
+                    Ok(()) => {
+                        info!("Exported {:?} play stats as {:?} to {:?}", period, format, path);
+                        self.publisher.update_status(format!("Exported {:?} play stats", period));
+                    }
+                    Err(e) => {
+                        error!("Play stats export failed: {}", e);
+                        self.publisher.update_status(format!("Export failed: {}", e));
+                    }
+                }
+            }
+            MonitorCommand::ExportSessionReport { start_time, format, path } => {
+                let Some(active_profile) = self.profile.controllers.get(&self.profile.active_controller_id) else {
+                    return;
+                };
+                let Some(report) = SessionReporter::build_for(
+                    &active_profile.session_history,
+                    start_time,
+                    &active_profile.switches,
+                    &self.profile.config,
+                ) else {
+                    self.publisher.update_status("No session to report on".to_string());
+                    return;
+                };
+                let contents = SessionReporter::render(&report, format);
+                match persistence::write_file_atomic(&path, contents.as_bytes()) {
+                    Ok(()) => {
+                        info!("Exported session report as {:?} to {:?}", format, path);
+                        self.publisher.update_status("Exported session report".to_string());
+                    }
+                    Err(e) => {
+                        error!("Session report export failed: {}", e);
+                        self.publisher.update_status(format!("Export failed: {}", e));
+                    }
+                }
+            }
+        }
+    }
+
+    fn load_import_baseline(mode: &ImportMode) -> anyhow::Result<Option<ControllerProfile>> {
+        match mode {
+            ImportMode::MergeInto { baseline_path: Some(path), .. } => {
+                Ok(Some(persistence::load_bundle(path)?.profile))
+            }
+            _ => Ok(None),
         }
     }
 
@@ -1,16 +1,22 @@
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use std::collections::HashMap;
+use std::path::PathBuf;
 
-use crate::domain::models::{AppConfig, LogicalKey};
+use crate::domain::models::{AppConfig, ButtonLayout, ImportMode, LogicalKey};
+use crate::usecase::play_stats::RollupPeriod;
+use crate::usecase::session_report::ReportFormat;
+use crate::usecase::stats_export::{ExportFormat, ExportKind};
 
 /// Commands that can be sent to the monitor service thread.
 pub enum MonitorCommand {
-    UpdateConfig(AppConfig),
+    UpdateConfig(Box<AppConfig>),
     UpdateMapping(String, HashMap<LogicalKey, u32>), // Preserved for bulk updates (presets)
     SetKeyBinding {
         key: LogicalKey,
         button: u32,
     }, // Added for single key update with conflict resolution
+    /// Switch how the active controller's buttons are numbered. Bindings are kept as-is.
+    SetButtonLayout(ButtonLayout),
     ReplaceSwitch {
         key: LogicalKey,
         new_model_id: String,
@@ -23,6 +29,42 @@ pub enum MonitorCommand {
         date: chrono::DateTime<Utc>,
     },
     SetActiveController(String),
+    ExportProfile {
+        controller_id: String,
+        controller_name: Option<String>,
+        path: PathBuf,
+    },
+    ImportProfile {
+        path: PathBuf,
+        mode: ImportMode,
+    },
+    ExportData {
+        kind: ExportKind,
+        format: ExportFormat,
+        path: PathBuf,
+    },
+    /// Write the report of the session that started at `start_time`, or of the latest one.
+    ExportSessionReport {
+        start_time: Option<DateTime<Utc>>,
+        format: ReportFormat,
+        path: PathBuf,
+    },
+    /// Write calendar rollups of the active controller's sessions. Without an offset the
+    /// system timezone is used.
+    ExportPlayStats {
+        period: RollupPeriod,
+        utc_offset_minutes: Option<i32>,
+        format: ExportFormat,
+        path: PathBuf,
+    },
+    /// Start a session without a game process. Ignored while a session is running.
+    StartSession,
+    /// End the running session, however it was started.
+    StopSession,
+    PauseSession,
+    ResumeSession,
+    /// Mark an alert as seen. It stays active until its condition clears.
+    AcknowledgeAlert(u64),
     Shutdown,
     ForceSave,
 }
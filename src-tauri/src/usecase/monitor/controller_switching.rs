@@ -0,0 +1,150 @@
+use log::info;
+use std::sync::Arc;
+
+use crate::domain::interfaces::InputSource;
+use crate::domain::models::{ControllerInfo, ControllerSwitchReason, InputMethod};
+use crate::infrastructure::persistence::ConfigRepository;
+use crate::infrastructure::process_monitor::ProcessMonitor;
+use crate::usecase::controller_selection::{ControllerSelector, ProfileProvision};
+use crate::usecase::input_monitor::ChatterDetector;
+use crate::usecase::session_manager::SessionManager;
+
+use super::multi_controller::DeviceMonitorState;
+use super::MonitorService;
+
+impl<I: InputSource, P: ProcessMonitor, R: ConfigRepository> MonitorService<I, P, R> {
+    /// React to a changed set of connected controllers: activate the preferred one, creating
+    /// its profile if it has none. Only a controller chosen by `controller_priority` adopts
+    /// the `default` profile. Deferred while a game session is running, so a session is
+    /// never split across two profiles. Off with XInput, which cannot target a controller id.
+    pub(super) fn handle_controller_changes(
+        &mut self,
+        controllers: &[ControllerInfo],
+        seen_controller_ids: &mut Vec<String>,
+        force_publish: &mut bool,
+    ) {
+        if !self.profile.config.auto_switch_controller || self.profile.config.input_method == InputMethod::XInput {
+            return;
+        }
+
+        let mut ids: Vec<String> = controllers.iter().map(|c| c.id.clone()).collect();
+        ids.sort();
+        if ids == *seen_controller_ids || self.profile.active_session.is_some() {
+            return;
+        }
+        *seen_controller_ids = ids;
+
+        let chosen = ControllerSelector::choose(
+            controllers,
+            &self.profile.config.controller_priority,
+            &self.profile.active_controller_id,
+            |id| self.profile.controllers.contains_key(id),
+        );
+        let Some(id) = chosen else {
+            return;
+        };
+        let controller = controllers.iter().find(|c| c.id == id).unwrap();
+        let adopt_default = self.profile.config.controller_priority.contains(&id);
+        match ControllerSelector::ensure_profile(&mut self.profile, controller, adopt_default) {
+            ProfileProvision::Existing => {}
+            ProfileProvision::AdoptedDefault => {
+                info!("Default profile assigned to controller {} ({})", controller.id, controller.name);
+            }
+            ProfileProvision::Created { preset_name } => {
+                info!(
+                    "Created profile for new controller {} ({}) with preset {}",
+                    controller.id, controller.name, preset_name
+                );
+            }
+        }
+
+        self.activate_controller(&id, ControllerSwitchReason::AutoConnect);
+        self.profile.config.target_controller_id = Some(id.clone());
+        self.publisher.update_status(format!("Switched to controller {}", controller.name));
+        *force_publish = true;
+    }
+
+    /// Switch the active controller profile and record it in the history.
+    ///
+    /// The previous controller keeps being monitored as one of the other controllers,
+    /// and both keep their own edge state.
+    pub(super) fn activate_controller(&mut self, controller_id: &str, reason: ControllerSwitchReason) {
+        let previous_id = self.profile.active_controller_id.clone();
+        if ControllerSelector::activate(&mut self.profile, controller_id, reason) {
+            info!("Active controller switched to {} ({:?})", controller_id, reason);
+            if !self.profile.controllers.contains_key(&previous_id) {
+                // The previous profile was adopted by this controller; its state carries over
+                self.refresh_active_controller();
+                return;
+            }
+
+            let threshold = self.profile.config.chatter_threshold_ms;
+            let in_session = self.profile.active_session.is_some();
+            let next = self.other_controllers.remove(controller_id);
+            if in_session && !next.as_ref().is_some_and(|d| d.in_session) {
+                let active_profile = self.profile.controllers.get_mut(controller_id).unwrap();
+                SessionManager::start_session(active_profile);
+            }
+
+            let mut previous = DeviceMonitorState::new("", threshold);
+            previous.in_session = in_session;
+            previous.chatter_detector = std::mem::replace(
+                &mut self.chatter_detector,
+                next.map_or_else(|| ChatterDetector::new(threshold), |d| d.chatter_detector),
+            );
+            self.other_controllers.insert(previous_id, previous);
+        }
+        self.refresh_active_controller();
+    }
+
+    /// Reload per-controller runtime state after the active profile changed.
+    fn refresh_active_controller(&mut self) {
+        let active_profile = self.profile.controllers.get(&self.profile.active_controller_id).unwrap();
+        self.cached_bindings = Arc::new(active_profile.mapping.bindings.clone());
+        self.apply_button_layout();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::UserProfile;
+    use crate::usecase::controller_selection::DEFAULT_CONTROLLER_ID;
+    use crate::usecase::test_helpers::create_test_service;
+
+    fn pad(id: &str) -> ControllerInfo {
+        ControllerInfo { id: id.to_string(), name: "Pad".to_string() }
+    }
+
+    #[test]
+    fn test_first_connected_pad_gets_a_preset_profile() {
+        let mut profile = UserProfile::default();
+        profile.config.input_method = InputMethod::DirectInput;
+        profile.config.auto_switch_controller = true;
+        let mut harness = create_test_service(profile);
+        let service = &mut harness.service;
+        let (mut seen, mut force_publish) = (Vec::new(), false);
+
+        service.handle_controller_changes(&[pad("pad-1")], &mut seen, &mut force_publish);
+        assert_eq!(service.profile.active_controller_id, "pad-1");
+        assert_eq!(service.profile.config.target_controller_id.as_deref(), Some("pad-1"));
+        // Without a priority entry the default profile is left as it is
+        assert!(service.profile.controllers.contains_key(DEFAULT_CONTROLLER_ID));
+        assert!(service.profile.controllers.contains_key("pad-1"));
+        assert!(force_publish);
+    }
+
+    #[test]
+    fn test_no_auto_switch_with_xinput() {
+        let mut profile = UserProfile::default();
+        profile.config.input_method = InputMethod::XInput;
+        profile.config.auto_switch_controller = true;
+        let mut harness = create_test_service(profile);
+        let service = &mut harness.service;
+        let (mut seen, mut force_publish) = (Vec::new(), false);
+
+        service.handle_controller_changes(&[pad("pad-1")], &mut seen, &mut force_publish);
+        assert_eq!(service.profile.active_controller_id, DEFAULT_CONTROLLER_ID);
+        assert!(service.profile.config.target_controller_id.is_none());
+    }
+}
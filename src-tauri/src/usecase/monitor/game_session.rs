@@ -1,49 +1,449 @@
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use log::info;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
 
 use crate::domain::interfaces::InputSource;
+use crate::domain::models::{ActiveSession, LogicalKey, SessionOrigin};
 use crate::infrastructure::persistence::ConfigRepository;
 use crate::infrastructure::process_monitor::ProcessMonitor;
 use crate::usecase::session_manager::SessionManager;
+use crate::usecase::session_report::SessionReporter;
 
 use super::MonitorService;
 
+/// Fires once when a button combo has been held long enough; the combo must be
+/// released before it can fire again.
+#[derive(Debug, Default)]
+pub(crate) struct HotkeyTracker {
+    held_since: Option<Instant>,
+    fired: bool,
+}
+
+impl HotkeyTracker {
+    pub(crate) fn update(&mut self, is_held: bool, now: Instant, hold: Duration) -> bool {
+        if !is_held {
+            self.held_since = None;
+            self.fired = false;
+            return false;
+        }
+        let since = *self.held_since.get_or_insert(now);
+        if !self.fired && now.duration_since(since) >= hold {
+            self.fired = true;
+            return true;
+        }
+        false
+    }
+}
+
 impl<I: InputSource, P: ProcessMonitor, R: ConfigRepository> MonitorService<I, P, R> {
+    /// Start and end process sessions as the game starts and exits.
+    ///
+    /// A process session stays open for `session_grace_secs` after the game exits, so a
+    /// restart of the same game continues it. A game starting during a manual session only
+    /// tags it with the game, and a manual session outlives the game.
     pub(super) fn handle_game_session(
         &mut self,
         is_game_running: bool,
         was_game_running: &mut bool,
         force_publish: &mut bool,
     ) {
+        if self.finish_expired_session(Utc::now()) {
+            *force_publish = true;
+        }
+
         if is_game_running != *was_game_running {
             info!(
                 "Game running state changed: {} -> {}",
                 *was_game_running, is_game_running
             );
 
-            let active_profile = self.profile.controllers.get_mut(&self.profile.active_controller_id).unwrap();
-
             if is_game_running {
-                info!("Game started. Resetting session stats.");
-                self.current_session_start = Some(Utc::now());
-                SessionManager::start_session(active_profile);
-            } else {
-                let end_time = Utc::now();
+                self.on_game_started();
+            } else if self
+                .profile
+                .active_session
+                .as_ref()
+                .is_some_and(|s| s.started_by == SessionOrigin::Process)
+            {
                 info!("Game ended.");
-
-                if let Some(start_time) = self.current_session_start.take() {
-                    let duration_secs = SessionManager::end_session(active_profile, start_time, end_time);
-                    info!("Session recorded: {}s", duration_secs);
+                let resumed = std::mem::take(&mut self.resumed_game_unseen);
+                if let Some(session) = &mut self.profile.active_session {
+                    // The game of a resumed session may have quit while the app was not running
+                    let exited_at = if resumed {
+                        session.last_activity_at.unwrap_or(session.start_time).max(session.start_time)
+                    } else {
+                        Utc::now()
+                    };
+                    session.exited_at = Some(exited_at);
                 }
-
-                for (key, switch) in &active_profile.switches {
-                    if switch.stats.last_session_presses > 0 {
-                        info!("  {}: {} presses", key, switch.stats.last_session_presses);
-                    }
+                if self.profile.config.session_grace_secs == 0 {
+                    self.finish_session();
                 }
             }
             *was_game_running = is_game_running;
             *force_publish = true;
         }
     }
+
+    fn on_game_started(&mut self) {
+        let Some(session) = &mut self.profile.active_session else {
+            self.begin_session(SessionOrigin::Process);
+            return;
+        };
+        if session.exited_at.is_none() {
+            if session.game_id.is_none() {
+                session.game_id = self.running_game_id.clone();
+            }
+            return;
+        }
+
+        if session.game_id == self.running_game_id {
+            info!("Game restarted within the grace period, continuing the session");
+            session.exited_at = None;
+            session.merged = true;
+        } else {
+            // A different game is not a restart
+            self.finish_session();
+            self.begin_session(SessionOrigin::Process);
+        }
+    }
+
+    /// End a session whose game has been gone for longer than the grace period.
+    /// Returns true if a session ended.
+    pub(super) fn finish_expired_session(&mut self, now: DateTime<Utc>) -> bool {
+        let grace = chrono::Duration::seconds(self.profile.config.session_grace_secs as i64);
+        let expired = self
+            .profile
+            .active_session
+            .as_ref()
+            .and_then(|s| s.exited_at)
+            .is_some_and(|exited_at| now - exited_at >= grace);
+        if expired {
+            self.finish_session();
+        }
+        expired
+    }
+
+    /// Whether input currently counts into the session stats.
+    pub(super) fn is_counting_session(&self) -> bool {
+        self.profile.active_session
+            .as_ref()
+            .is_some_and(|s| !s.paused && s.exited_at.is_none())
+    }
+
+    /// Start a session from the start session command or the hotkey.
+    /// Returns false if a session is already running.
+    pub(crate) fn start_manual_session(&mut self, origin: SessionOrigin) -> bool {
+        if self.profile.active_session.is_some() {
+            return false;
+        }
+        self.begin_session(origin);
+        true
+    }
+
+    /// End the running session, whatever started it. A game that is still running does not
+    /// start a new session until it is restarted. Returns false if no session was running.
+    pub(crate) fn stop_session(&mut self) -> bool {
+        if self.profile.active_session.is_none() {
+            return false;
+        }
+        self.finish_session();
+        true
+    }
+
+    /// Returns false if no session is running or it already is in that state.
+    pub(crate) fn set_session_paused(&mut self, paused: bool) -> bool {
+        let Some(session) = &mut self.profile.active_session else {
+            return false;
+        };
+        if !session.set_paused(paused, Utc::now()) {
+            return false;
+        }
+        info!("Session {}", if paused { "paused" } else { "resumed" });
+        true
+    }
+
+    /// Toggle a session when the configured combo has been held long enough.
+    pub(super) fn handle_session_hotkey(
+        &mut self,
+        pressed_keys: &HashSet<LogicalKey>,
+        tracker: &mut HotkeyTracker,
+        now: Instant,
+        force_publish: &mut bool,
+    ) {
+        let Some(hotkey) = &self.profile.config.session_hotkey else {
+            return;
+        };
+        let is_held = !hotkey.keys.is_empty() && hotkey.keys.iter().all(|k| pressed_keys.contains(k));
+        if !tracker.update(is_held, now, Duration::from_millis(hotkey.hold_ms)) {
+            return;
+        }
+
+        if self.profile.active_session.is_some() {
+            self.stop_session();
+            self.publisher.update_status("Session stopped by hotkey".to_string());
+        } else {
+            self.start_manual_session(SessionOrigin::Hotkey);
+            self.publisher.update_status("Session started by hotkey".to_string());
+        }
+        *force_publish = true;
+    }
+
+    /// Add the counts since the last sample to the timeline of every controller in the session.
+    /// Timeline offsets leave out the time the session was paused.
+    pub(super) fn sample_session_timeline(&mut self, now: DateTime<Utc>) {
+        let Some(session) = &self.profile.active_session else {
+            return;
+        };
+        let offset_secs = session.duration_secs(now);
+
+        if let Some(active_profile) = self.profile.controllers.get_mut(&self.profile.active_controller_id) {
+            SessionManager::record_timeline(active_profile, offset_secs);
+        }
+        for (id, device) in &self.other_controllers {
+            if let (true, Some(profile)) = (device.in_session, self.profile.controllers.get_mut(id)) {
+                SessionManager::record_timeline(profile, offset_secs);
+            }
+        }
+    }
+
+    fn begin_session(&mut self, origin: SessionOrigin) {
+        info!("Session started ({:?}). Resetting session stats.", origin);
+        self.profile.active_session = Some(ActiveSession::new(Utc::now(), self.running_game_id.clone(), origin));
+
+        // Other controllers join when one of their keys is pressed
+        let active_profile = self.profile.controllers.get_mut(&self.profile.active_controller_id).unwrap();
+        SessionManager::start_session(active_profile);
+    }
+
+    /// Record the running session on every controller that took part in it. The session
+    /// ends when its game exited, if it did. Sessions shorter than `min_session_secs` are
+    /// dropped; time spent paused does not count.
+    pub(super) fn finish_session(&mut self) {
+        let Some(session) = self.profile.active_session.take() else {
+            return;
+        };
+        let end_time = session.exited_at.unwrap_or_else(Utc::now);
+
+        let duration_secs = session.duration_secs(end_time);
+        if duration_secs < self.profile.config.min_session_secs {
+            info!(
+                "Session of {}s dropped, shorter than {}s",
+                duration_secs, self.profile.config.min_session_secs
+            );
+            for device in self.other_controllers.values_mut() {
+                device.in_session = false;
+            }
+            return;
+        }
+
+        let active_profile = self.profile.controllers.get_mut(&self.profile.active_controller_id).unwrap();
+        let duration_secs = SessionManager::end_session(active_profile, &session, end_time);
+        info!("Session recorded: {}s", duration_secs);
+
+        let report = SessionReporter::build_for(
+            &active_profile.session_history,
+            None,
+            &active_profile.switches,
+            &self.profile.config,
+        );
+        if let Some(report) = report {
+            for key in &report.keys {
+                info!(
+                    "  {}: {} presses, {} chatters ({:.2}%){}",
+                    key.key,
+                    key.presses,
+                    key.chatters,
+                    key.chatter_rate_percent,
+                    if key.exceeds_alert { " above the alert level" } else { "" }
+                );
+            }
+        }
+
+        // Other controllers only get a record if they were played on
+        for (id, device) in self.other_controllers.iter_mut() {
+            let was_in_session = std::mem::take(&mut device.in_session);
+            let Some(profile) = self.profile.controllers.get_mut(id) else {
+                continue;
+            };
+            let played = profile
+                .switches
+                .values()
+                .any(|s| s.stats.last_session_presses > 0 || s.stats.last_session_chatters > 0);
+            if was_in_session && played {
+                let duration_secs = SessionManager::end_session(profile, &session, end_time);
+                info!("Session recorded for controller {}: {}s", id, duration_secs);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::{SessionHotkey, UserProfile};
+    use crate::usecase::test_helpers::{create_test_service, TestHarness};
+
+    /// Sessions end as soon as the game exits and are recorded however short.
+    fn service_without_debounce() -> TestHarness {
+        let mut profile = UserProfile::default();
+        profile.config.min_session_secs = 0;
+        profile.config.session_grace_secs = 0;
+        create_test_service(profile)
+    }
+
+    fn ago(secs: i64) -> DateTime<Utc> {
+        Utc::now() - chrono::Duration::seconds(secs)
+    }
+
+    #[test]
+    fn test_hotkey_fires_once_per_hold() {
+        let mut tracker = HotkeyTracker::default();
+        let hold = Duration::from_secs(2);
+        let t0 = Instant::now();
+
+        assert!(!tracker.update(true, t0, hold));
+        assert!(!tracker.update(true, t0 + Duration::from_millis(1999), hold));
+        assert!(tracker.update(true, t0 + Duration::from_secs(2), hold));
+        assert!(!tracker.update(true, t0 + Duration::from_secs(5), hold));
+
+        // Letting go restarts the hold time
+        assert!(!tracker.update(false, t0 + Duration::from_secs(6), hold));
+        assert!(!tracker.update(true, t0 + Duration::from_secs(7), hold));
+        assert!(tracker.update(true, t0 + Duration::from_secs(9), hold));
+    }
+
+    #[test]
+    fn test_manual_session_outlives_the_game() {
+        let mut harness = service_without_debounce();
+        let service = &mut harness.service;
+        let (mut was_running, mut force_publish) = (false, false);
+
+        assert!(service.start_manual_session(SessionOrigin::Manual));
+        assert!(!service.start_manual_session(SessionOrigin::Manual));
+
+        service.running_game_id = Some("iidx".to_string());
+        service.handle_game_session(true, &mut was_running, &mut force_publish);
+        service.running_game_id = None;
+        service.handle_game_session(false, &mut was_running, &mut force_publish);
+
+        let session = service.profile.active_session.as_ref().expect("manual session keeps running");
+        assert_eq!(session.started_by, SessionOrigin::Manual);
+        assert_eq!(session.game_id.as_deref(), Some("iidx"));
+
+        assert!(service.stop_session());
+        let record = service.profile.controllers["default"].recent_sessions().last().unwrap();
+        assert_eq!(record.started_by, SessionOrigin::Manual);
+        assert_eq!(record.game_id.as_deref(), Some("iidx"));
+    }
+
+    #[test]
+    fn test_stopped_process_session_waits_for_restart() {
+        let mut harness = service_without_debounce();
+        let service = &mut harness.service;
+        let (mut was_running, mut force_publish) = (false, false);
+
+        service.handle_game_session(true, &mut was_running, &mut force_publish);
+        assert!(service.set_session_paused(true));
+        assert!(!service.is_counting_session());
+        assert!(service.stop_session());
+
+        service.handle_game_session(true, &mut was_running, &mut force_publish);
+        assert!(service.profile.active_session.is_none());
+
+        service.handle_game_session(false, &mut was_running, &mut force_publish);
+        service.handle_game_session(true, &mut was_running, &mut force_publish);
+        assert_eq!(service.profile.active_session.as_ref().unwrap().started_by, SessionOrigin::Process);
+    }
+
+    #[test]
+    fn test_hotkey_toggles_session() {
+        let mut harness = service_without_debounce();
+        let service = &mut harness.service;
+        service.profile.config.session_hotkey = Some(SessionHotkey {
+            keys: vec![LogicalKey::E1, LogicalKey::E2],
+            hold_ms: 2000,
+        });
+        let mut tracker = HotkeyTracker::default();
+        let mut force_publish = false;
+        let combo: HashSet<LogicalKey> = [LogicalKey::E1, LogicalKey::E2].into();
+        let t0 = Instant::now();
+
+        service.handle_session_hotkey(&[LogicalKey::E1].into(), &mut tracker, t0, &mut force_publish);
+        service.handle_session_hotkey(&combo, &mut tracker, t0, &mut force_publish);
+        service.handle_session_hotkey(&combo, &mut tracker, t0 + Duration::from_secs(2), &mut force_publish);
+        assert_eq!(service.profile.active_session.as_ref().unwrap().started_by, SessionOrigin::Hotkey);
+
+        service.handle_session_hotkey(&HashSet::new(), &mut tracker, t0 + Duration::from_secs(3), &mut force_publish);
+        service.handle_session_hotkey(&combo, &mut tracker, t0 + Duration::from_secs(4), &mut force_publish);
+        service.handle_session_hotkey(&combo, &mut tracker, t0 + Duration::from_secs(6), &mut force_publish);
+        assert!(service.profile.active_session.is_none());
+        assert_eq!(
+            service.profile.controllers["default"].recent_sessions().last().unwrap().started_by,
+            SessionOrigin::Hotkey
+        );
+    }
+
+    #[test]
+    fn test_restart_within_grace_continues_session() {
+        let mut harness = create_test_service(UserProfile::default());
+        let service = &mut harness.service;
+        let (mut was_running, mut force_publish) = (false, false);
+        service.running_game_id = Some("iidx".to_string());
+
+        service.handle_game_session(true, &mut was_running, &mut force_publish);
+        service.profile.active_session.as_mut().unwrap().start_time = ago(600);
+        service.handle_game_session(false, &mut was_running, &mut force_publish);
+        assert!(!service.is_counting_session());
+
+        service.handle_game_session(true, &mut was_running, &mut force_publish);
+        service.handle_game_session(false, &mut was_running, &mut force_publish);
+        let exited_at = ago(61);
+        service.profile.active_session.as_mut().unwrap().exited_at = Some(exited_at);
+        service.handle_game_session(false, &mut was_running, &mut force_publish);
+
+        let sessions = service.profile.controllers["default"].recent_sessions();
+        assert_eq!(sessions.len(), 1);
+        assert!(sessions[0].merged);
+        // Ends when the game exited, not when the grace period ran out
+        assert_eq!(sessions[0].end_time, exited_at);
+    }
+
+    #[test]
+    fn test_other_game_during_grace_starts_new_session() {
+        let mut harness = create_test_service(UserProfile::default());
+        let service = &mut harness.service;
+        let (mut was_running, mut force_publish) = (false, false);
+
+        service.running_game_id = Some("iidx".to_string());
+        service.handle_game_session(true, &mut was_running, &mut force_publish);
+        service.profile.active_session.as_mut().unwrap().start_time = ago(600);
+        service.handle_game_session(false, &mut was_running, &mut force_publish);
+
+        service.running_game_id = Some("sdvx".to_string());
+        service.handle_game_session(true, &mut was_running, &mut force_publish);
+
+        let sessions = service.profile.controllers["default"].recent_sessions();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].game_id.as_deref(), Some("iidx"));
+        assert!(!sessions[0].merged);
+        assert_eq!(service.profile.active_session.as_ref().unwrap().game_id.as_deref(), Some("sdvx"));
+    }
+
+    #[test]
+    fn test_short_session_is_dropped() {
+        let mut harness = create_test_service(UserProfile::default());
+        let service = &mut harness.service;
+
+        service.start_manual_session(SessionOrigin::Manual);
+        service.profile.active_session.as_mut().unwrap().start_time = ago(10);
+        service.stop_session();
+        assert!(service.profile.controllers["default"].recent_sessions().is_empty());
+
+        service.start_manual_session(SessionOrigin::Manual);
+        service.profile.active_session.as_mut().unwrap().start_time = ago(60);
+        service.stop_session();
+        assert_eq!(service.profile.controllers["default"].recent_sessions().len(), 1);
+    }
 }
@@ -3,23 +3,45 @@ use std::collections::HashSet;
 use std::time::{Duration, Instant};
 
 use crate::domain::errors::InputError;
-use crate::domain::interfaces::InputSource;
-use crate::domain::models::LogicalKey;
+use crate::domain::interfaces::{InputSnapshot, InputSource};
+use crate::domain::models::{ControllerProfile, InputMethod, LogicalKey};
 use crate::infrastructure::persistence::ConfigRepository;
 use crate::infrastructure::process_monitor::ProcessMonitor;
 use crate::infrastructure::timer::HighResolutionTimer;
+use crate::usecase::input_monitor::ChatterDetector;
 
 use super::MonitorService;
 
 impl<I: InputSource, P: ProcessMonitor, R: ConfigRepository> MonitorService<I, P, R> {
+    /// Detect which configured game is running and remember it in `running_game_id`.
     pub(super) fn check_game_running(&mut self) -> bool {
-        self.process_monitor
-            .is_process_running(&self.profile.config.target_process_name)
+        let games = self.profile.config.game_definitions();
+        let found = self.process_monitor.match_game(&games);
+        let running = found.as_ref().map(|m| m.game_id.clone());
+        if running != self.running_game_id {
+            match &found {
+                Some(m) => info!("Running game: {} (pid {:?}, rule {:?})", m.game_id, m.pid, m.rule),
+                None => info!("Running game: None"),
+            }
+            self.running_game_id = running;
+        }
+        self.running_game_id.is_some()
+    }
+
+    /// Runtime index of the configured controller, or `None` if it is not connected.
+    pub(super) fn resolve_target_index(&mut self) -> Option<u32> {
+        let config = &self.profile.config;
+        match &config.target_controller_id {
+            Some(id) if config.input_method != InputMethod::XInput => {
+                self.input_source.find_controller_index(id)
+            }
+            _ => Some(config.target_controller_index),
+        }
     }
 
-    pub(super) fn handle_connection_state(
+    pub(super) fn handle_connection_state<T>(
         &mut self,
-        input_result: &Result<u32, InputError>,
+        input_result: &Result<T, InputError>,
         was_connected: &mut bool,
         force_publish: &mut bool,
     ) -> bool {
@@ -39,6 +61,8 @@ impl<I: InputSource, P: ProcessMonitor, R: ConfigRepository> MonitorService<I, P
             );
             *was_connected = is_connected;
             *force_publish = true;
+            self.poll_timing.reset();
+            self.alert_connection_changed(is_connected, chrono::Utc::now());
 
             if is_connected {
                 if self.high_res_timer.is_none() {
@@ -65,54 +89,132 @@ impl<I: InputSource, P: ProcessMonitor, R: ConfigRepository> MonitorService<I, P
         if last_process_check.elapsed() >= process_check_interval {
             let running = self.check_game_running();
             *last_process_check = Instant::now();
+            if running {
+                self.resumed_game_unseen = false;
+            }
             running
         } else {
             was_game_running
         }
     }
 
+    /// Feed the active controller's snapshots through chatter detection.
+    /// Returns the raw buttons of the latest snapshot.
     pub(super) fn process_input(
         &mut self,
-        input_result: Result<u32, InputError>,
-        is_game_running: bool,
+        input_result: Result<Vec<InputSnapshot>, InputError>,
+        in_session: bool,
         current_pressed_keys: &mut HashSet<LogicalKey>,
     ) -> u32 {
         current_pressed_keys.clear();
-        let mut current_raw_buttons = 0;
 
-        if let Ok(w_buttons) = input_result {
-            current_raw_buttons = w_buttons;
-            let now_ms = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_millis() as u64;
+        match input_result {
+            Ok(snapshots) => {
+                let active_profile = self.profile.controllers.get_mut(&self.profile.active_controller_id).unwrap();
+                feed_snapshots(
+                    active_profile,
+                    &mut self.chatter_detector,
+                    &snapshots,
+                    in_session,
+                    current_pressed_keys,
+                )
+            }
+            Err(_) => 0,
+        }
+    }
+}
+
+/// Feed every snapshot, oldest first, through `detector` into the stats of `profile`.
+/// Fills `pressed_keys` from the latest snapshot and returns its raw buttons.
+pub(super) fn feed_snapshots(
+    profile: &mut ControllerProfile,
+    detector: &mut ChatterDetector,
+    snapshots: &[InputSnapshot],
+    in_session: bool,
+    pressed_keys: &mut HashSet<LogicalKey>,
+) -> u32 {
+    let mut current_raw_buttons = 0;
 
-            let active_profile = self.profile.controllers.get_mut(&self.profile.active_controller_id).unwrap();
+    for snapshot in snapshots {
+        current_raw_buttons = snapshot.buttons;
 
-            for (key, &mask) in &active_profile.mapping.bindings {
-                let is_pressed = (w_buttons & mask) != 0;
-                if is_pressed {
-                    current_pressed_keys.insert(key.clone());
+        for (key, &mask) in &profile.mapping.bindings {
+            let is_pressed = (snapshot.buttons & mask) != 0;
+
+            let switch_data = profile.switches.entry(key.clone()).or_insert_with(|| {
+                crate::domain::models::SwitchData {
+                    switch_model_id: "generic_unknown".to_string(),
+                    stats: crate::domain::models::ButtonStats::default(),
+                    last_replaced_at: None,
                 }
+            });
 
-                let switch_data =
-                    active_profile.switches.entry(key.clone()).or_insert_with(|| {
-                        crate::domain::models::SwitchData {
-                            switch_model_id: "generic_unknown".to_string(),
-                            stats: crate::domain::models::ButtonStats::default(),
-                            last_replaced_at: None,
-                        }
-                    });
-
-                self.chatter_detector.process_button(
-                    key,
-                    is_pressed,
-                    now_ms,
-                    &mut switch_data.stats,
-                    is_game_running,
-                );
-            }
+            detector.process_button(
+                key,
+                is_pressed,
+                snapshot.timestamp_ms,
+                &mut switch_data.stats,
+                in_session,
+            );
         }
-        current_raw_buttons
+    }
+
+    for (key, &mask) in &profile.mapping.bindings {
+        if (current_raw_buttons & mask) != 0 {
+            pressed_keys.insert(key.clone());
+        }
+    }
+    current_raw_buttons
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::{ControllerInfo, UserProfile};
+    use crate::usecase::test_helpers::create_test_service;
+
+    #[test]
+    fn test_resolve_target_by_controller_id() {
+        let mut harness = create_test_service(UserProfile::default());
+        harness.service.input_source.controllers = ["pad-a", "pad-b"]
+            .iter()
+            .map(|id| ControllerInfo { id: id.to_string(), name: "Pad".to_string() })
+            .collect();
+        harness.service.profile.config.target_controller_index = 0;
+
+        harness.service.profile.config.target_controller_id = Some("pad-b".to_string());
+        assert_eq!(harness.service.resolve_target_index(), Some(1));
+
+        // A missing controller is treated as disconnected rather than falling back to the index
+        harness.service.profile.config.target_controller_id = Some("pad-c".to_string());
+        assert_eq!(harness.service.resolve_target_index(), None);
+
+        harness.service.profile.config.target_controller_id = None;
+        assert_eq!(harness.service.resolve_target_index(), Some(0));
+    }
+
+    #[test]
+    fn test_process_input_feeds_every_snapshot_in_order() {
+        let mut profile = UserProfile::default();
+        let controller = profile.controllers.get_mut("default").unwrap();
+        controller.mapping.bindings.clear();
+        controller.mapping.bindings.insert(LogicalKey::Key1, 0b1);
+        let mut harness = create_test_service(profile);
+
+        // Press, release and a bounce 3ms later, all within a single poll
+        let snapshots = vec![
+            InputSnapshot { buttons: 0b1, timestamp_ms: 1_000 },
+            InputSnapshot { buttons: 0b0, timestamp_ms: 1_040 },
+            InputSnapshot { buttons: 0b1, timestamp_ms: 1_043 },
+        ];
+        let mut pressed = HashSet::new();
+        let raw = harness.service.process_input(Ok(snapshots), false, &mut pressed);
+
+        assert_eq!(raw, 0b1);
+        assert!(pressed.contains(&LogicalKey::Key1));
+        let stats = &harness.service.profile.controllers["default"].switches[&LogicalKey::Key1].stats;
+        assert_eq!(stats.total_presses, 1);
+        assert_eq!(stats.total_releases, 1);
+        assert_eq!(stats.total_chatters, 1);
     }
 }
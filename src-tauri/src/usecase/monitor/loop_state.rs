@@ -1,7 +1,11 @@
-use std::collections::HashSet;
-use std::time::Instant;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 
 use crate::domain::models::{ControllerInfo, LogicalKey};
+use crate::infrastructure::suspend_clock::suspended_since_boot;
+
+use super::game_session::HotkeyTracker;
 
 /// Consolidated mutable state for the monitor main loop.
 /// Previously these were scattered local variables in `run()`.
@@ -9,11 +13,24 @@ pub struct MonitorLoopState {
     pub was_connected: bool,
     pub was_game_running: bool,
     pub last_save_at: Instant,
+    pub last_snapshot_at: Instant,
     pub last_process_check: Instant,
     pub last_publish: Instant,
     pub last_enumerate: Instant,
+    pub last_timeline_sample: Instant,
+    pub last_alert_check: Instant,
     pub current_pressed_keys: HashSet<LogicalKey>,
     pub connected_controllers: Vec<ControllerInfo>,
+    /// Input index of each connected controller, resolved on enumeration.
+    pub controller_indices: HashMap<String, u32>,
+    /// Sorted ids of the controllers the auto-switch last acted on.
+    pub seen_controller_ids: Vec<String>,
+    pub session_hotkey: HotkeyTracker,
+    /// Wall-clock and monotonic time of the last iteration, to notice the computer sleeping.
+    pub last_loop_at: DateTime<Utc>,
+    pub last_loop_instant: Instant,
+    /// Time suspended since boot at the last iteration, where the platform reports it.
+    pub last_suspended: Option<Duration>,
 }
 
 impl Default for MonitorLoopState {
@@ -23,11 +40,42 @@ impl Default for MonitorLoopState {
             was_connected: false,
             was_game_running: false,
             last_save_at: now,
+            last_snapshot_at: now,
             last_process_check: now,
             last_publish: now,
             last_enumerate: now,
+            last_timeline_sample: now,
+            last_alert_check: now,
             current_pressed_keys: HashSet::new(),
             connected_controllers: Vec::new(),
+            controller_indices: HashMap::new(),
+            seen_controller_ids: Vec::new(),
+            session_hotkey: HotkeyTracker::default(),
+            last_loop_at: Utc::now(),
+            last_loop_instant: now,
+            last_suspended: suspended_since_boot(),
         }
     }
 }
+
+impl MonitorLoopState {
+    /// How long the computer was suspended since the previous call.
+    ///
+    /// Where the platform cannot report suspended time, this is the wall-clock time the
+    /// monotonic clock did not see, which a wall-clock change can also produce.
+    pub fn time_asleep(&mut self, now: DateTime<Utc>) -> Duration {
+        let instant = Instant::now();
+        let suspended = suspended_since_boot();
+        let asleep = match (self.last_suspended, suspended) {
+            (Some(before), Some(after)) => after.saturating_sub(before),
+            _ => (now - self.last_loop_at)
+                .to_std()
+                .unwrap_or_default()
+                .saturating_sub(instant - self.last_loop_instant),
+        };
+        self.last_loop_at = now;
+        self.last_loop_instant = instant;
+        self.last_suspended = suspended;
+        asleep
+    }
+}
@@ -6,36 +6,52 @@
 //! - `commands` — `MonitorCommand` enum
 //! - `command_handler` — command dispatch logic
 //! - `input_processing` — input polling, connection state, game status
-//! - `game_session` — game session start/end lifecycle
-//! - `publishing` — state publishing, autosave, shutdown
+//! - `game_session` — session start/end lifecycle: game process, commands and hotkey
+//! - `controller_switching` — automatic profile selection on controller connect
+//! - `multi_controller` — polling the other connected controllers alongside the active one
+//! - `session_recovery` — resuming a saved session at startup, sleep detection
+//! - `alerts` — evaluating alert rules and raising alerts
+//! - `publishing` — state publishing, counter snapshots, autosave, shutdown
 //! - `loop_state` — `MonitorLoopState` consolidating loop variables
+//! - `poll_timing` — measured polling intervals and slow-loop warnings
 
+mod alerts;
 mod command_handler;
 pub mod commands;
+mod controller_switching;
 mod game_session;
 mod input_processing;
 mod loop_state;
+mod multi_controller;
+mod poll_timing;
 mod publishing;
+mod session_recovery;
 
 // Re-export public API so that `use crate::usecase::monitor::{MonitorService, MonitorCommand}` works.
 pub use commands::MonitorCommand;
 
 use anyhow::Result;
+use chrono::Utc;
 use crossbeam_channel::Receiver;
 use log::{error, info};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 use std::time::Duration;
 
+use crate::domain::errors::InputError;
 use crate::domain::interfaces::InputSource;
-use crate::domain::models::{LogicalKey, UserProfile};
+use crate::domain::models::{get_default_switch_models, LogicalKey, SwitchData, UserProfile};
 use crate::infrastructure::persistence::ConfigRepository;
 use crate::infrastructure::process_monitor::ProcessMonitor;
 use crate::infrastructure::timer::HighResolutionTimer;
+use crate::usecase::alert_engine::AlertEngine;
 use crate::usecase::input_monitor::ChatterDetector;
 use crate::usecase::state_publisher::StatePublisher;
 
 use loop_state::MonitorLoopState;
+use multi_controller::DeviceMonitorState;
+use poll_timing::PollTimingTracker;
+use publishing::PlayHistoryCache;
 
 pub struct MonitorService<I, P, R> {
     pub(crate) input_source: I,
@@ -45,6 +61,8 @@ pub struct MonitorService<I, P, R> {
     // Made public for testing
     pub profile: UserProfile,
     pub(crate) chatter_detector: ChatterDetector,
+    // Connected controllers other than the active one, by controller id
+    pub(crate) other_controllers: BTreeMap<String, DeviceMonitorState>,
 
     pub(crate) command_rx: Receiver<MonitorCommand>,
     pub(crate) publisher: StatePublisher,
@@ -52,11 +70,21 @@ pub struct MonitorService<I, P, R> {
     // Timer Resolution Control
     pub(crate) high_res_timer: Option<HighResolutionTimer>,
 
-    // Track session start time
-    pub(crate) current_session_start: Option<chrono::DateTime<chrono::Utc>>,
+    // Game detected by the last process check
+    pub(crate) running_game_id: Option<String>,
+    // A process session was resumed from the profile and no check has seen its game yet
+    pub(crate) resumed_game_unseen: bool,
 
     // Cached Arc for bindings to avoid recreating it when not changed
     pub(crate) cached_bindings: Arc<HashMap<LogicalKey, u32>>,
+    // Published switches of each monitored controller, replaced only when they change
+    pub(crate) cached_switches: HashMap<String, Arc<HashMap<LogicalKey, SwitchData>>>,
+    pub(crate) play_history_cache: PlayHistoryCache,
+    pub(crate) alert_engine: AlertEngine,
+
+    // Number of completed polling iterations, exported as a metric
+    pub(crate) polls_total: u64,
+    pub(crate) poll_timing: PollTimingTracker,
 }
 
 impl<I: InputSource, P: ProcessMonitor, R: ConfigRepository> MonitorService<I, P, R> {
@@ -74,27 +102,46 @@ impl<I: InputSource, P: ProcessMonitor, R: ConfigRepository> MonitorService<I, P
 
         // Initialize input method from profile
         input_source.set_input_method(profile.config.input_method.clone());
+        input_source.set_keyboard_bindings(&profile.config.keyboard_bindings);
+        input_source.set_midi_config(&profile.config.midi);
 
         let chatter_detector = ChatterDetector::new(profile.config.chatter_threshold_ms);
 
         let cached_bindings = {
             let active_profile = profile.controllers.get(&profile.active_controller_id)
                 .expect("Active controller profile must exist");
+            input_source.set_button_layout(active_profile.mapping.layout);
             Arc::new(active_profile.mapping.bindings.clone())
         };
 
-        Ok(Self {
+        let mut service = Self {
             input_source,
             process_monitor,
             repository,
             profile,
             chatter_detector,
+            other_controllers: BTreeMap::new(),
             command_rx,
             publisher,
             high_res_timer: None,
-            current_session_start: None,
+            running_game_id: None,
+            resumed_game_unseen: false,
             cached_bindings,
-        })
+            cached_switches: HashMap::new(),
+            play_history_cache: PlayHistoryCache::default(),
+            alert_engine: AlertEngine::new(get_default_switch_models()),
+            polls_total: 0,
+            poll_timing: PollTimingTracker::default(),
+        };
+        service.recover_session(Utc::now());
+        Ok(service)
+    }
+
+    /// Tell the input source which button layout the active mapping was learned with.
+    pub(crate) fn apply_button_layout(&mut self) {
+        if let Some(active_profile) = self.profile.controllers.get(&self.profile.active_controller_id) {
+            self.input_source.set_button_layout(active_profile.mapping.layout);
+        }
     }
 
     pub fn run(mut self) {
@@ -104,8 +151,14 @@ impl<I: InputSource, P: ProcessMonitor, R: ConfigRepository> MonitorService<I, P
         let process_check_interval = Duration::from_secs(2);
         let publish_interval = Duration::from_millis(30); // ~33Hz throttle
         let enumerate_interval = Duration::from_secs(3);
+        let timeline_interval = Duration::from_secs(1);
+        let alert_interval = Duration::from_secs(1);
 
-        let mut state = MonitorLoopState::default();
+        let mut state = MonitorLoopState {
+            was_game_running: self.has_process_session(),
+            ..Default::default()
+        };
+        self.take_counter_snapshots();
 
         // Main Loop
         'monitor_loop: loop {
@@ -121,8 +174,22 @@ impl<I: InputSource, P: ProcessMonitor, R: ConfigRepository> MonitorService<I, P
                 break 'monitor_loop;
             }
 
+            let now = Utc::now();
+            let asleep = state.time_asleep(now);
+            self.handle_sleep(asleep, now, &mut state.was_game_running, &mut force_publish);
+
+            if state.was_connected {
+                let configured = Duration::from_millis(self.profile.config.polling_rate_ms_connected);
+                self.poll_timing.record_poll(std::time::Instant::now(), configured);
+            }
+
             // 3. Input Polling & Connection State
-            let input_result = self.input_source.get_state(self.profile.config.target_controller_index);
+            let target_index = self.resolve_target_index();
+            let input_result = match target_index {
+                Some(index) => self.input_source.poll_events(index),
+                None => Err(InputError::Disconnected),
+            };
+            self.polls_total += 1;
             let is_connected = self.handle_connection_state(&input_result, &mut state.was_connected, &mut force_publish);
 
             // 4. Process Monitor (Check Game Status)
@@ -131,17 +198,46 @@ impl<I: InputSource, P: ProcessMonitor, R: ConfigRepository> MonitorService<I, P
             // 5. Enumerate controllers
             if state.last_enumerate.elapsed() >= enumerate_interval {
                 if let Ok(controllers) = self.input_source.enumerate_controllers() {
+                    state.controller_indices = self.resolve_controller_indices(&controllers);
                     state.connected_controllers = controllers;
+                    self.handle_controller_changes(
+                        &state.connected_controllers,
+                        &mut state.seen_controller_ids,
+                        &mut force_publish,
+                    );
                     force_publish = true;
                 }
                 state.last_enumerate = std::time::Instant::now();
             }
 
             // 6. Process Input
-            let current_raw_buttons = self.process_input(input_result, is_game_running, &mut state.current_pressed_keys);
+            let in_session = self.is_counting_session();
+            let current_raw_buttons = self.process_input(input_result, in_session, &mut state.current_pressed_keys);
+            self.poll_other_controllers(
+                &state.connected_controllers,
+                &state.controller_indices,
+                target_index,
+                in_session,
+                &mut force_publish,
+            );
+            self.note_session_activity(!state.current_pressed_keys.is_empty(), now);
 
             // 7. Session Logic
             self.handle_game_session(is_game_running, &mut state.was_game_running, &mut force_publish);
+            self.handle_session_hotkey(
+                &state.current_pressed_keys,
+                &mut state.session_hotkey,
+                std::time::Instant::now(),
+                &mut force_publish,
+            );
+            if state.last_timeline_sample.elapsed() >= timeline_interval {
+                self.sample_session_timeline(now);
+                state.last_timeline_sample = std::time::Instant::now();
+            }
+            if state.last_alert_check.elapsed() >= alert_interval {
+                self.evaluate_alerts(now, &mut force_publish);
+                state.last_alert_check = std::time::Instant::now();
+            }
 
             // 8. Publish State
             if force_publish || state.last_publish.elapsed() >= publish_interval {
@@ -149,7 +245,8 @@ impl<I: InputSource, P: ProcessMonitor, R: ConfigRepository> MonitorService<I, P
                 state.last_publish = std::time::Instant::now();
             }
 
-            // 9. Auto Save
+            // 9. Counter Snapshots & Auto Save
+            self.handle_counter_snapshots(&mut state.last_snapshot_at);
             self.handle_autosave(&mut state.last_save_at, save_interval);
         }
 
@@ -0,0 +1,321 @@
+use log::info;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::domain::errors::InputError;
+use crate::domain::interfaces::InputSource;
+use crate::domain::models::{ControllerInfo, LogicalKey, SwitchData};
+use crate::infrastructure::persistence::ConfigRepository;
+use crate::infrastructure::process_monitor::ProcessMonitor;
+use crate::usecase::input_monitor::ChatterDetector;
+use crate::usecase::session_manager::SessionManager;
+use crate::usecase::state_publisher::MonitoredControllerState;
+
+use super::input_processing::feed_snapshots;
+use super::MonitorService;
+
+/// Runtime state of a controller monitored alongside the active one.
+pub(crate) struct DeviceMonitorState {
+    pub(crate) name: String,
+    pub(crate) chatter_detector: ChatterDetector,
+    pub(crate) is_connected: bool,
+    pub(crate) raw_buttons: u32,
+    pub(crate) pressed_keys: HashSet<LogicalKey>,
+    /// Session stats were reset for the running session. A controller only joins a session
+    /// once one of its keys is pressed, so an unused one keeps its previous session stats.
+    pub(crate) in_session: bool,
+}
+
+impl DeviceMonitorState {
+    pub(crate) fn new(name: &str, chatter_threshold_ms: u64) -> Self {
+        Self {
+            name: name.to_string(),
+            chatter_detector: ChatterDetector::new(chatter_threshold_ms),
+            is_connected: false,
+            raw_buttons: 0,
+            pressed_keys: HashSet::new(),
+            in_session: false,
+        }
+    }
+}
+
+impl<I: InputSource, P: ProcessMonitor, R: ConfigRepository> MonitorService<I, P, R> {
+    /// Input index of every connected controller that has one. Resolved once per
+    /// enumeration rather than on every poll.
+    pub(super) fn resolve_controller_indices(&mut self, connected: &[ControllerInfo]) -> HashMap<String, u32> {
+        connected
+            .iter()
+            .filter_map(|c| Some((c.id.clone(), self.input_source.find_controller_index(&c.id)?)))
+            .collect()
+    }
+
+    /// Poll every connected controller that has a profile, except the active one,
+    /// and count its input into its own profile.
+    ///
+    /// `indices` comes from `resolve_controller_indices`. `active_index` is skipped so a
+    /// device targeted by index is never read twice.
+    pub(super) fn poll_other_controllers(
+        &mut self,
+        connected: &[ControllerInfo],
+        indices: &HashMap<String, u32>,
+        active_index: Option<u32>,
+        in_session: bool,
+        force_publish: &mut bool,
+    ) {
+        let active_id = &self.profile.active_controller_id;
+        let controllers = &self.profile.controllers;
+        self.other_controllers
+            .retain(|id, _| id != active_id && controllers.contains_key(id));
+
+        let threshold = self.profile.config.chatter_threshold_ms;
+        for controller in connected {
+            if controller.id == self.profile.active_controller_id
+                || !self.profile.controllers.contains_key(&controller.id)
+            {
+                continue;
+            }
+
+            let index = indices.get(&controller.id).copied().filter(|&i| Some(i) != active_index);
+            let result = match index {
+                Some(index) => self.input_source.poll_events(index),
+                None => Err(InputError::Disconnected),
+            };
+
+            let device = self
+                .other_controllers
+                .entry(controller.id.clone())
+                .or_insert_with(|| DeviceMonitorState::new(&controller.name, threshold));
+            device.name.clone_from(&controller.name);
+
+            let profile = self.profile.controllers.get_mut(&controller.id).unwrap();
+            if result.is_ok() != device.is_connected {
+                info!(
+                    "Controller {} ({}) connected: {}",
+                    controller.id, controller.name, result.is_ok()
+                );
+                device.is_connected = result.is_ok();
+                *force_publish = true;
+            }
+            if let Ok(snapshots) = &result {
+                let key_pressed = snapshots
+                    .iter()
+                    .any(|s| profile.mapping.bindings.values().any(|&mask| s.buttons & mask != 0));
+                if key_pressed && self.profile.active_session.is_some() && !device.in_session {
+                    // Joined the running session
+                    SessionManager::start_session(profile);
+                    device.in_session = true;
+                }
+            }
+
+            device.pressed_keys.clear();
+            device.raw_buttons = match result {
+                Ok(snapshots) => feed_snapshots(
+                    profile,
+                    &mut device.chatter_detector,
+                    &snapshots,
+                    in_session && device.in_session,
+                    &mut device.pressed_keys,
+                ),
+                Err(_) => 0,
+            };
+        }
+
+        for (id, device) in self.other_controllers.iter_mut() {
+            if device.is_connected && !connected.iter().any(|c| c.id == *id) {
+                info!("Controller {} ({}) connected: false", id, device.name);
+                device.is_connected = false;
+                device.raw_buttons = 0;
+                device.pressed_keys.clear();
+                *force_publish = true;
+            }
+        }
+    }
+
+    /// Per-controller section of the shared state, active controller first.
+    pub(super) fn monitored_controller_states(
+        &mut self,
+        is_connected: bool,
+        pressed_keys: &HashSet<LogicalKey>,
+        raw_buttons: u32,
+        connected_controllers: &[ControllerInfo],
+    ) -> Vec<MonitoredControllerState> {
+        let cached = &mut self.cached_switches;
+        cached.retain(|id, _| self.profile.controllers.contains_key(id));
+        let mut shared_switches = |id: &str, switches: &HashMap<LogicalKey, SwitchData>| {
+            match cached.get(id) {
+                Some(shared) if **shared == *switches => shared.clone(),
+                _ => {
+                    let shared = Arc::new(switches.clone());
+                    cached.insert(id.to_string(), shared.clone());
+                    shared
+                }
+            }
+        };
+
+        let active_id = &self.profile.active_controller_id;
+        let active_profile = &self.profile.controllers[active_id];
+        let active_name = connected_controllers
+            .iter()
+            .find(|c| c.id == *active_id)
+            .map(|c| c.name.clone())
+            .unwrap_or_default();
+
+        let mut states = vec![MonitoredControllerState {
+            controller_id: active_id.clone(),
+            name: active_name,
+            profile_name: active_profile.mapping.profile_name.clone(),
+            is_active: true,
+            is_connected,
+            raw_button_state: raw_buttons,
+            current_pressed_keys: pressed_keys.clone(),
+            switches: shared_switches(active_id, &active_profile.switches),
+        }];
+
+        for (id, device) in &self.other_controllers {
+            let Some(profile) = self.profile.controllers.get(id) else {
+                continue;
+            };
+            states.push(MonitoredControllerState {
+                controller_id: id.clone(),
+                name: device.name.clone(),
+                profile_name: profile.mapping.profile_name.clone(),
+                is_active: false,
+                is_connected: device.is_connected,
+                raw_button_state: device.raw_buttons,
+                current_pressed_keys: device.pressed_keys.clone(),
+                switches: shared_switches(id, &profile.switches),
+            });
+        }
+        states
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::{ButtonStats, ControllerProfile, SwitchData, UserProfile};
+    use crate::usecase::test_helpers::create_test_service;
+
+    fn profile_with_two_controllers() -> UserProfile {
+        let mut profile = UserProfile::default();
+        for id in ["pad-a", "pad-b"] {
+            let mut controller = ControllerProfile::default();
+            controller.mapping.bindings.clear();
+            controller.mapping.bindings.insert(LogicalKey::Key1, 0b1);
+            profile.controllers.insert(id.to_string(), controller);
+        }
+        profile.controllers.remove("default");
+        profile.active_controller_id = "pad-a".to_string();
+        profile
+    }
+
+    fn connected(ids: &[&str]) -> Vec<ControllerInfo> {
+        ids.iter()
+            .map(|id| ControllerInfo { id: id.to_string(), name: format!("Pad {}", id) })
+            .collect()
+    }
+
+    #[test]
+    fn test_other_controllers_count_into_their_own_profile() {
+        let mut harness = create_test_service(profile_with_two_controllers());
+        let controllers = connected(&["pad-a", "pad-b", "pad-unknown"]);
+        harness.service.input_source.controllers = controllers.clone();
+        let indices = harness.service.resolve_controller_indices(&controllers);
+        harness.service.input_source.index_states.insert(1, 0b1);
+
+        let mut force_publish = false;
+        harness.service.poll_other_controllers(&controllers, &indices, Some(0), false, &mut force_publish);
+        assert!(force_publish);
+
+        let profiles = &harness.service.profile.controllers;
+        assert_eq!(profiles["pad-b"].switches[&LogicalKey::Key1].stats.total_presses, 1);
+        assert!(!profiles["pad-a"].switches.contains_key(&LogicalKey::Key1));
+        // Controllers without a profile are not tracked
+        assert!(!profiles.contains_key("pad-unknown"));
+        assert_eq!(harness.service.other_controllers.len(), 1);
+
+        let states = harness.service.monitored_controller_states(true, &HashSet::new(), 0, &controllers);
+        assert_eq!(states.len(), 2);
+        assert!(states[0].is_active);
+        assert_eq!(states[1].controller_id, "pad-b");
+        assert_eq!(states[1].name, "Pad pad-b");
+        assert_eq!(states[1].raw_button_state, 0b1);
+        assert!(states[1].current_pressed_keys.contains(&LogicalKey::Key1));
+
+        // Unchanged switches are shared with the last publish instead of copied
+        let again = harness.service.monitored_controller_states(true, &HashSet::new(), 0, &controllers);
+        assert!(Arc::ptr_eq(&states[1].switches, &again[1].switches));
+        let pad_b = harness.service.profile.controllers.get_mut("pad-b").unwrap();
+        pad_b.switches.get_mut(&LogicalKey::Key1).unwrap().stats.total_presses += 1;
+        let changed = harness.service.monitored_controller_states(true, &HashSet::new(), 0, &controllers);
+        assert!(!Arc::ptr_eq(&states[1].switches, &changed[1].switches));
+        assert_eq!(changed[1].switches[&LogicalKey::Key1].stats.total_presses, 2);
+    }
+
+    #[test]
+    fn test_unplugged_controller_is_marked_disconnected() {
+        let mut harness = create_test_service(profile_with_two_controllers());
+        let controllers = connected(&["pad-a", "pad-b"]);
+        harness.service.input_source.controllers = controllers.clone();
+        let indices = harness.service.resolve_controller_indices(&controllers);
+
+        let mut force_publish = false;
+        harness.service.poll_other_controllers(&controllers, &indices, Some(0), false, &mut force_publish);
+        assert!(harness.service.other_controllers["pad-b"].is_connected);
+
+        harness.service.poll_other_controllers(&controllers[..1], &indices, Some(0), false, &mut force_publish);
+        assert!(!harness.service.other_controllers["pad-b"].is_connected);
+    }
+
+    #[test]
+    fn test_device_resolving_to_active_index_is_skipped() {
+        let mut harness = create_test_service(profile_with_two_controllers());
+        let controllers = connected(&["pad-a", "pad-b"]);
+        harness.service.input_source.controllers = controllers.clone();
+        let indices = harness.service.resolve_controller_indices(&controllers);
+        harness.service.input_source.index_states.insert(1, 0b1);
+
+        let mut force_publish = false;
+        harness.service.poll_other_controllers(&controllers, &indices, Some(1), false, &mut force_publish);
+
+        assert!(!harness.service.other_controllers["pad-b"].is_connected);
+        assert!(harness.service.profile.controllers["pad-b"].switches.is_empty());
+    }
+
+    #[test]
+    fn test_session_recorded_only_for_played_controllers() {
+        let mut profile = profile_with_two_controllers();
+        let mut pad_c = profile.controllers["pad-b"].clone();
+        pad_c.switches.insert(LogicalKey::Key1, SwitchData {
+            switch_model_id: "omron".to_string(),
+            stats: ButtonStats { last_session_presses: 5, ..Default::default() },
+            last_replaced_at: None,
+        });
+        profile.controllers.insert("pad-c".to_string(), pad_c);
+        profile.config.min_session_secs = 0;
+        profile.config.session_grace_secs = 0;
+        let mut harness = create_test_service(profile);
+        let controllers = connected(&["pad-a", "pad-b", "pad-c"]);
+        harness.service.input_source.controllers = controllers.clone();
+        let indices = harness.service.resolve_controller_indices(&controllers);
+        harness.service.input_source.index_states.insert(2, 0b0);
+
+        let mut force_publish = false;
+        let mut was_game_running = false;
+        harness.service.poll_other_controllers(&controllers, &indices, Some(0), false, &mut force_publish);
+        harness.service.handle_game_session(true, &mut was_game_running, &mut force_publish);
+
+        harness.service.input_source.index_states.insert(1, 0b1);
+        harness.service.poll_other_controllers(&controllers, &indices, Some(0), true, &mut force_publish);
+        harness.service.handle_game_session(false, &mut was_game_running, &mut force_publish);
+
+        let profiles = &harness.service.profile.controllers;
+        assert_eq!(profiles["pad-b"].recent_sessions().len(), 1);
+        assert_eq!(profiles["pad-b"].recent_sessions()[0].stats[&LogicalKey::Key1].presses, 1);
+        assert!(profiles["pad-c"].recent_sessions().is_empty());
+        // Not played in this session, so its previous session stats are kept
+        assert_eq!(profiles["pad-c"].switches[&LogicalKey::Key1].stats.last_session_presses, 5);
+        // The active controller always gets its record
+        assert_eq!(profiles["pad-a"].recent_sessions().len(), 1);
+    }
+}
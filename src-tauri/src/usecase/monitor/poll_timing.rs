@@ -0,0 +1,216 @@
+use log::warn;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::usecase::state_publisher::PollTimingStats;
+
+/// Number of recent intervals kept for min/avg/p99/max.
+const WINDOW_SIZE: usize = 1000;
+/// Minimum time between two "polling too slow" warnings.
+const WARN_INTERVAL: Duration = Duration::from_secs(60);
+/// How often `stats` sorts the window again. State is published about 33 times a second,
+/// far more often than the percentiles need to change.
+const STATS_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Measures the actual interval between polls of the monitor loop.
+///
+/// An interval longer than twice the configured rate counts as an overrun. When the
+/// average over a full window falls below half the configured rate, a warning is logged
+/// (at most once per minute), since missed polls merge or hide chatter edges.
+pub struct PollTimingTracker {
+    last_poll_at: Option<Instant>,
+    intervals_us: VecDeque<u64>,
+    /// Sum of `intervals_us`, so the average does not walk the window on every poll.
+    sum_us: u64,
+    overruns: u64,
+    configured_us: u64,
+    last_warned_at: Option<Instant>,
+    /// Last result of `stats` and when it was computed.
+    cached_stats: Option<(Instant, PollTimingStats)>,
+}
+
+impl Default for PollTimingTracker {
+    fn default() -> Self {
+        Self {
+            last_poll_at: None,
+            intervals_us: VecDeque::with_capacity(WINDOW_SIZE),
+            sum_us: 0,
+            overruns: 0,
+            configured_us: 0,
+            last_warned_at: None,
+            cached_stats: None,
+        }
+    }
+}
+
+impl PollTimingTracker {
+    /// Record a poll happening at `now` with the given configured polling interval.
+    pub fn record_poll(&mut self, now: Instant, configured: Duration) {
+        let configured_us = configured.as_micros() as u64;
+        if configured_us != self.configured_us {
+            // Stats are only meaningful against a single configured rate
+            self.reset();
+            self.configured_us = configured_us;
+        }
+
+        if let Some(last) = self.last_poll_at.replace(now) {
+            let interval_us = now.saturating_duration_since(last).as_micros() as u64;
+            if interval_us > configured_us.saturating_mul(2).max(2_000) {
+                self.overruns += 1;
+            }
+            if self.intervals_us.len() == WINDOW_SIZE {
+                self.sum_us -= self.intervals_us.pop_front().unwrap_or(0);
+            }
+            self.intervals_us.push_back(interval_us);
+            self.sum_us += interval_us;
+            self.check_effective_rate(now);
+        }
+    }
+
+    /// Forget all measurements, e.g. when the connection state changes.
+    pub fn reset(&mut self) {
+        self.last_poll_at = None;
+        self.intervals_us.clear();
+        self.sum_us = 0;
+        self.cached_stats = None;
+    }
+
+    /// Interval stats over the window, recomputed at most once per `STATS_REFRESH_INTERVAL`.
+    pub fn stats(&mut self) -> PollTimingStats {
+        let now = Instant::now();
+        if let Some((computed_at, stats)) = &self.cached_stats {
+            if now.saturating_duration_since(*computed_at) < STATS_REFRESH_INTERVAL {
+                return stats.clone();
+            }
+        }
+        let stats = self.compute_stats();
+        self.cached_stats = Some((now, stats.clone()));
+        stats
+    }
+
+    fn compute_stats(&self) -> PollTimingStats {
+        if self.intervals_us.is_empty() {
+            return PollTimingStats {
+                configured_us: self.configured_us,
+                overruns: self.overruns,
+                ..Default::default()
+            };
+        }
+
+        let mut sorted: Vec<u64> = self.intervals_us.iter().copied().collect();
+        sorted.sort_unstable();
+        let p99_index = ((sorted.len() as f64 * 0.99).ceil() as usize).saturating_sub(1);
+
+        PollTimingStats {
+            configured_us: self.configured_us,
+            min_us: sorted[0],
+            avg_us: self.average_us(),
+            p99_us: sorted[p99_index],
+            max_us: sorted[sorted.len() - 1],
+            samples: sorted.len() as u64,
+            overruns: self.overruns,
+            rate_degraded: self.is_rate_degraded(),
+        }
+    }
+
+    fn average_us(&self) -> u64 {
+        if self.intervals_us.is_empty() {
+            return 0;
+        }
+        self.sum_us / self.intervals_us.len() as u64
+    }
+
+    fn is_rate_degraded(&self) -> bool {
+        self.intervals_us.len() == WINDOW_SIZE
+            && self.configured_us > 0
+            && self.average_us() > self.configured_us * 2
+    }
+
+    fn check_effective_rate(&mut self, now: Instant) {
+        if !self.is_rate_degraded() {
+            return;
+        }
+        let should_warn = self
+            .last_warned_at
+            .is_none_or(|t| now.saturating_duration_since(t) >= WARN_INTERVAL);
+        if should_warn {
+            warn!(
+                "Polling is slower than configured: average interval {}us vs configured {}us ({} overruns so far)",
+                self.average_us(),
+                self.configured_us,
+                self.overruns
+            );
+            self.last_warned_at = Some(now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval_stats() {
+        let mut tracker = PollTimingTracker::default();
+        let configured = Duration::from_millis(1);
+        let start = Instant::now();
+
+        let offsets_us = [0, 1_000, 2_000, 3_000, 8_000];
+        for offset in offsets_us {
+            tracker.record_poll(start + Duration::from_micros(offset), configured);
+        }
+
+        let stats = tracker.stats();
+        assert_eq!(stats.samples, 4);
+        assert_eq!(stats.min_us, 1_000);
+        assert_eq!(stats.max_us, 5_000);
+        assert_eq!(stats.p99_us, 5_000);
+        assert_eq!(stats.avg_us, 2_000);
+        assert_eq!(stats.overruns, 1);
+        assert!(!stats.rate_degraded);
+    }
+
+    #[test]
+    fn test_degraded_rate_detected_over_full_window() {
+        let mut tracker = PollTimingTracker::default();
+        let configured = Duration::from_millis(1);
+        let start = Instant::now();
+
+        for i in 0..=WINDOW_SIZE as u64 {
+            tracker.record_poll(start + Duration::from_millis(i * 5), configured);
+        }
+
+        let stats = tracker.stats();
+        assert_eq!(stats.samples, WINDOW_SIZE as u64);
+        assert!(stats.rate_degraded);
+        assert_eq!(stats.overruns, WINDOW_SIZE as u64);
+    }
+
+    #[test]
+    fn test_stats_refreshed_at_most_once_per_interval() {
+        let mut tracker = PollTimingTracker::default();
+        let configured = Duration::from_millis(1);
+        let start = Instant::now();
+        tracker.record_poll(start, configured);
+        tracker.record_poll(start + Duration::from_millis(1), configured);
+        assert_eq!(tracker.stats().samples, 1);
+
+        tracker.record_poll(start + Duration::from_millis(3), configured);
+        assert_eq!(tracker.stats().samples, 1);
+        assert_eq!(tracker.compute_stats().samples, 2);
+        assert_eq!(tracker.compute_stats().avg_us, 1_500);
+    }
+
+    #[test]
+    fn test_reset_on_rate_change() {
+        let mut tracker = PollTimingTracker::default();
+        let start = Instant::now();
+        tracker.record_poll(start, Duration::from_millis(1));
+        tracker.record_poll(start + Duration::from_millis(1), Duration::from_millis(1));
+        assert_eq!(tracker.stats().samples, 1);
+
+        tracker.record_poll(start + Duration::from_millis(2), Duration::from_millis(4));
+        assert_eq!(tracker.stats().samples, 0);
+        assert_eq!(tracker.stats().configured_us, 4_000);
+    }
+}
@@ -1,49 +1,121 @@
-use log::{error, info};
+use chrono::{DateTime, Utc};
+use log::{debug, error, info};
 use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use crate::domain::interfaces::InputSource;
-use crate::domain::models::LogicalKey;
+use crate::domain::models::{CounterSnapshot, LogicalKey, SessionRecord};
 use crate::infrastructure::persistence::ConfigRepository;
 use crate::infrastructure::process_monitor::ProcessMonitor;
 
 use super::MonitorService;
+use crate::usecase::counter_snapshots::CounterSnapshots;
+use crate::usecase::play_stats::PlayStatsAggregator;
+
+/// Published copy of the active controller's session history and counter snapshots, rebuilt
+/// only when they change.
+#[derive(Default)]
+pub(crate) struct PlayHistoryCache {
+    key: Option<PlayHistoryKey>,
+    sessions: Arc<Vec<SessionRecord>>,
+    snapshots: Arc<Vec<CounterSnapshot>>,
+}
+
+/// Changes whenever sessions or snapshots are added, or the active controller changes.
+#[derive(PartialEq)]
+struct PlayHistoryKey {
+    controller_id: String,
+    history_len: usize,
+    latest_session: Option<DateTime<Utc>>,
+    latest_snapshot: Option<DateTime<Utc>>,
+}
 
 impl<I: InputSource, P: ProcessMonitor, R: ConfigRepository> MonitorService<I, P, R> {
     pub(super) fn publish_current_state(
-        &self,
+        &mut self,
         is_connected: bool,
         is_game_running: bool,
         pressed_keys: &HashSet<LogicalKey>,
         raw_buttons: u32,
         connected_controllers: &[crate::domain::models::ControllerInfo],
     ) {
+        let monitored_controllers =
+            self.monitored_controller_states(is_connected, pressed_keys, raw_buttons, connected_controllers);
         let active_profile = self.profile.controllers.get(&self.profile.active_controller_id).unwrap();
 
+        let history_key = PlayHistoryKey {
+            controller_id: self.profile.active_controller_id.clone(),
+            history_len: active_profile.session_history.len(),
+            latest_session: active_profile.session_history.last().map(|s| s.start_time),
+            latest_snapshot: active_profile.counter_snapshots.last().map(|s| s.taken_at),
+        };
+        if self.play_history_cache.key.as_ref() != Some(&history_key) {
+            self.play_history_cache = PlayHistoryCache {
+                key: Some(history_key),
+                sessions: Arc::new(PlayStatsAggregator::sessions(active_profile)),
+                snapshots: Arc::new(active_profile.counter_snapshots.clone()),
+            };
+        }
+
         self.publisher.publish(
             is_connected,
             is_game_running,
+            self.running_game_id.clone(),
+            self.profile.active_session.clone(),
             self.profile.config.clone(),
             active_profile.mapping.profile_name.clone(),
             self.cached_bindings.clone(),
+            active_profile.mapping.layout,
             active_profile.switches.clone(),
             Arc::new(active_profile.switch_history.clone()),
             pressed_keys.clone(),
             raw_buttons,
-            active_profile.recent_sessions.clone(),
+            active_profile.recent_sessions().to_vec(),
+            self.play_history_cache.sessions.clone(),
+            self.play_history_cache.snapshots.clone(),
+            active_profile.game_stats.clone(),
+            self.profile.alerts.alerts.clone(),
             self.profile.active_controller_id.clone(),
             connected_controllers.to_vec(),
+            monitored_controllers,
+            self.polls_total,
+            self.poll_timing.stats(),
         );
     }
 
+    /// Snapshot the totals of every controller that changed, every `snapshot_interval_secs`.
+    pub(super) fn handle_counter_snapshots(&mut self, last_snapshot_at: &mut Instant) {
+        let interval = self.profile.config.snapshot_interval_secs;
+        if interval == 0 || last_snapshot_at.elapsed() < Duration::from_secs(interval) {
+            return;
+        }
+        self.take_counter_snapshots();
+        *last_snapshot_at = Instant::now();
+    }
+
+    /// Also taken at startup and shutdown, so usage is not attributed to the wrong run.
+    pub(super) fn take_counter_snapshots(&mut self) {
+        if self.profile.config.snapshot_interval_secs == 0 {
+            return;
+        }
+        let now = Utc::now();
+        for (id, profile) in self.profile.controllers.iter_mut() {
+            if CounterSnapshots::take_if_changed(profile, now) {
+                debug!("Counter snapshot taken for controller {}", id);
+            }
+        }
+    }
+
     pub(super) fn handle_autosave(&mut self, last_save_at: &mut Instant, save_interval: Duration) {
         if last_save_at.elapsed() >= save_interval {
             if let Err(e) = self.repository.save(&self.profile) {
                 error!("Auto save failed: {}", e);
                 self.publisher.update_save_result(false, format!("Auto save failed: {}", e));
+                self.alert_save_result(Some(e.to_string()), Utc::now());
             } else {
                 self.publisher.update_save_result(true, "Auto save succeeded".to_string());
+                self.alert_save_result(None, Utc::now());
             }
             *last_save_at = Instant::now();
         }
@@ -51,6 +123,7 @@ impl<I: InputSource, P: ProcessMonitor, R: ConfigRepository> MonitorService<I, P
 
     pub(super) fn handle_shutdown(&mut self) {
         info!("Monitor loop exiting. Saving profile...");
+        self.take_counter_snapshots();
         if let Err(e) = self.repository.save(&self.profile) {
             error!("Exit save failed: {}", e);
             self.publisher.update_save_result(false, format!("Exit save failed: {}", e));
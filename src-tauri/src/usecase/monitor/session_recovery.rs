@@ -0,0 +1,221 @@
+use chrono::{DateTime, Duration, Utc};
+use log::info;
+
+use crate::domain::interfaces::InputSource;
+use crate::domain::models::{SessionOrigin, SleepAction};
+use crate::infrastructure::persistence::ConfigRepository;
+use crate::infrastructure::process_monitor::ProcessMonitor;
+
+use super::MonitorService;
+
+/// Suspended time shorter than this between two loop iterations is not treated as a sleep.
+const SLEEP_GAP_SECS: i64 = 30;
+
+impl<I: InputSource, P: ProcessMonitor, R: ConfigRepository> MonitorService<I, P, R> {
+    /// Continue or close the session a previous run saved with the profile.
+    ///
+    /// The session continues if the app starts again within `session_resume_secs` of its last
+    /// activity. Otherwise it is recorded as ending at the last activity, so a crash or power
+    /// loss does not count the time the app was not running. A resumed process session whose
+    /// game is found gone exits at its last activity as well.
+    pub(super) fn recover_session(&mut self, now: DateTime<Utc>) {
+        let resume_window = Duration::seconds(self.profile.config.session_resume_secs as i64);
+        let Some(session) = &mut self.profile.active_session else {
+            return;
+        };
+        let last_seen = session
+            .exited_at
+            .or(session.last_activity_at)
+            .unwrap_or(session.start_time)
+            .max(session.start_time);
+
+        if now - last_seen <= resume_window {
+            info!("Resuming the session started at {}", session.start_time);
+            self.resumed_game_unseen = session.started_by == SessionOrigin::Process && session.exited_at.is_none();
+            return;
+        }
+
+        info!(
+            "Closing the session started at {} at its last activity {}",
+            session.start_time, last_seen
+        );
+        session.exited_at = Some(last_seen);
+        self.finish_session();
+    }
+
+    /// Whether a recovered session is waiting for its game, so the usual exit path ends it
+    /// if the game is gone.
+    pub(super) fn has_process_session(&self) -> bool {
+        self.profile
+            .active_session
+            .as_ref()
+            .is_some_and(|s| s.started_by == SessionOrigin::Process && s.exited_at.is_none())
+    }
+
+    /// Remember when a key was last held during a counting session.
+    pub(super) fn note_session_activity(&mut self, any_key_held: bool, now: DateTime<Utc>) {
+        if !any_key_held || !self.is_counting_session() {
+            return;
+        }
+        if let Some(session) = &mut self.profile.active_session {
+            session.last_activity_at = Some(now);
+        }
+    }
+
+    /// Apply `sleep_action` to the running session if the computer was suspended for
+    /// `asleep` before waking at `now`.
+    ///
+    /// An ended process session lets a game that is still running start a new one.
+    pub(super) fn handle_sleep(
+        &mut self,
+        asleep: std::time::Duration,
+        now: DateTime<Utc>,
+        was_game_running: &mut bool,
+        force_publish: &mut bool,
+    ) {
+        let Ok(asleep) = Duration::from_std(asleep) else {
+            return;
+        };
+        if asleep < Duration::seconds(SLEEP_GAP_SECS) {
+            return;
+        }
+        let asleep_at = now - asleep;
+        info!("Computer was asleep from {} to {}", asleep_at, now);
+        let Some(session) = &mut self.profile.active_session else {
+            return;
+        };
+
+        match self.profile.config.sleep_action {
+            SleepAction::End => {
+                session.exited_at.get_or_insert(asleep_at);
+                self.finish_session();
+                *was_game_running = false;
+                self.publisher.update_status("Session ended by sleep".to_string());
+            }
+            SleepAction::Pause => {
+                // The session carries on after wake without the time asleep. A session
+                // paused by hand already leaves it out and stays paused.
+                if !session.set_paused(true, asleep_at) {
+                    return;
+                }
+                session.set_paused(false, now);
+                self.publisher.update_status("Session paused while asleep".to_string());
+            }
+        }
+        *force_publish = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::{ActiveSession, ButtonStats, LogicalKey, SwitchData, UserProfile};
+    use crate::usecase::test_helpers::create_test_service;
+
+    fn ago(secs: i64) -> DateTime<Utc> {
+        Utc::now() - Duration::seconds(secs)
+    }
+
+    /// A profile saved while a session was running, with one press counted in it.
+    fn profile_with_session(last_activity_secs_ago: i64) -> UserProfile {
+        let mut profile = UserProfile::default();
+        profile.config.min_session_secs = 0;
+        let mut session = ActiveSession::new(ago(3600), None, SessionOrigin::Manual);
+        session.last_activity_at = Some(ago(last_activity_secs_ago));
+        profile.active_session = Some(session);
+        let active = profile.controllers.get_mut(&profile.active_controller_id).unwrap();
+        active.switches.insert(LogicalKey::Key1, SwitchData {
+            switch_model_id: "omron".to_string(),
+            stats: ButtonStats { last_session_presses: 1, ..Default::default() },
+            last_replaced_at: None,
+        });
+        profile
+    }
+
+    #[test]
+    fn test_recent_session_is_resumed() {
+        let harness = create_test_service(profile_with_session(60));
+        let service = &harness.service;
+
+        assert!(service.is_counting_session());
+        let active = &service.profile.controllers[&service.profile.active_controller_id];
+        assert!(active.recent_sessions().is_empty());
+        assert_eq!(active.switches[&LogicalKey::Key1].stats.last_session_presses, 1);
+    }
+
+    #[test]
+    fn test_stale_session_is_closed_at_last_activity() {
+        let harness = create_test_service(profile_with_session(1800));
+        let service = &harness.service;
+
+        assert!(service.profile.active_session.is_none());
+        let active = &service.profile.controllers[&service.profile.active_controller_id];
+        let record = active.recent_sessions().last().expect("session recorded");
+        let duration = record.duration_secs as i64;
+        assert!((1799..=1801).contains(&duration), "duration {}", duration);
+        assert_eq!(record.stats[&LogicalKey::Key1].presses, 1);
+    }
+
+    #[test]
+    fn test_resumed_process_session_without_game_exits_at_last_activity() {
+        let mut profile = profile_with_session(60);
+        profile.config.session_grace_secs = 600;
+        profile.active_session.as_mut().unwrap().started_by = SessionOrigin::Process;
+        let mut harness = create_test_service(profile);
+        let service = &mut harness.service;
+        let (mut was_running, mut force_publish) = (service.has_process_session(), false);
+
+        service.handle_game_session(false, &mut was_running, &mut force_publish);
+        let session = service.profile.active_session.as_ref().unwrap();
+        assert_eq!(session.exited_at, session.last_activity_at);
+    }
+
+    #[test]
+    fn test_sleep_ends_or_pauses_the_session() {
+        let mut harness = create_test_service(UserProfile::default());
+        let service = &mut harness.service;
+        service.profile.config.min_session_secs = 0;
+        let (mut was_running, mut force_publish) = (true, false);
+        let now = Utc::now();
+        let asleep = std::time::Duration::from_secs(600);
+
+        // A short suspend is not a sleep
+        service.profile.active_session = Some(ActiveSession::new(now - Duration::seconds(900), None, SessionOrigin::Process));
+        service.handle_sleep(std::time::Duration::from_secs(5), now, &mut was_running, &mut force_publish);
+        assert!(service.is_counting_session());
+        assert!(!force_publish);
+
+        // The paused session continues after wake without the time asleep
+        service.profile.config.sleep_action = SleepAction::Pause;
+        service.handle_sleep(asleep, now, &mut was_running, &mut force_publish);
+        assert!(service.is_counting_session());
+        let session = service.profile.active_session.as_ref().unwrap();
+        assert_eq!(session.duration_secs(now), 300);
+        assert_eq!(session.duration_secs(now + Duration::seconds(60)), 360);
+        assert!(was_running);
+
+        service.profile.config.sleep_action = SleepAction::End;
+        service.handle_sleep(asleep, now, &mut was_running, &mut force_publish);
+        assert!(service.profile.active_session.is_none());
+        assert!(!was_running);
+        let active = &service.profile.controllers[&service.profile.active_controller_id];
+        assert_eq!(active.recent_sessions().last().unwrap().end_time, now - Duration::seconds(600));
+    }
+
+    #[test]
+    fn test_sleep_keeps_a_session_paused_by_hand_paused() {
+        let mut harness = create_test_service(UserProfile::default());
+        let service = &mut harness.service;
+        service.profile.config.sleep_action = SleepAction::Pause;
+        let (mut was_running, mut force_publish) = (true, false);
+        let now = Utc::now();
+        let mut session = ActiveSession::new(now - Duration::seconds(900), None, SessionOrigin::Manual);
+        session.set_paused(true, now - Duration::seconds(800));
+        service.profile.active_session = Some(session);
+
+        service.handle_sleep(std::time::Duration::from_secs(600), now, &mut was_running, &mut force_publish);
+        let session = service.profile.active_session.as_ref().unwrap();
+        assert!(session.paused);
+        assert_eq!(session.duration_secs(now), 100);
+    }
+}
@@ -0,0 +1,276 @@
+use chrono::{DateTime, Datelike, Duration, FixedOffset, Local, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::domain::models::{
+    ControllerProfile, CounterSnapshot, LogicalKey, SessionKeyStats, SessionRecord, SessionTimeline,
+};
+use crate::usecase::counter_snapshots::CounterSnapshots;
+use crate::usecase::stats_export::{push_csv_row, ExportFormat, SessionKeyRow};
+
+/// Calendar period of a rollup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RollupPeriod {
+    Day,
+    /// Weeks start on Monday.
+    Week,
+    Month,
+}
+
+impl RollupPeriod {
+    /// First day of the period containing `date`.
+    pub fn start_of(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            RollupPeriod::Day => date,
+            RollupPeriod::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+            RollupPeriod::Month => date.with_day(1).expect("every month has a first day"),
+        }
+    }
+}
+
+/// Play statistics of one calendar period.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayRollup {
+    /// First local day of the period.
+    pub period_start: NaiveDate,
+    pub sessions: u64,
+    pub play_time_secs: u64,
+    /// Presses and chatters during sessions.
+    pub presses: u64,
+    pub chatters: u64,
+    /// Presses and chatters seen by counter snapshots, also outside sessions. Counted in the
+    /// period of the snapshot that saw them.
+    pub all_presses: u64,
+    pub all_chatters: u64,
+    /// Session presses per key, most pressed first.
+    pub keys: Vec<SessionKeyRow>,
+}
+
+/// Aggregates recorded sessions into daily, weekly and monthly rollups.
+pub struct PlayStatsAggregator;
+
+impl PlayStatsAggregator {
+    /// All recorded sessions of a controller without timelines, oldest first.
+    pub fn sessions(profile: &ControllerProfile) -> Vec<SessionRecord> {
+        profile
+            .session_history
+            .iter()
+            .map(|session| SessionRecord {
+                timeline: SessionTimeline::default(),
+                ..session.clone()
+            })
+            .collect()
+    }
+
+    /// Rollups of `sessions` and the usage between `snapshots` in the calendar of `tz`,
+    /// oldest first.
+    ///
+    /// A session counts into the period its start falls in. Periods without sessions or
+    /// usage are left out.
+    pub fn rollup<Tz: TimeZone>(
+        sessions: &[SessionRecord],
+        snapshots: &[CounterSnapshot],
+        period: RollupPeriod,
+        tz: &Tz,
+    ) -> Vec<PlayRollup> {
+        let mut periods: BTreeMap<NaiveDate, PeriodTotals> = BTreeMap::new();
+
+        for session in sessions {
+            let totals = period_totals(&mut periods, period.start_of(local_date(&session.start_time, tz)));
+            totals.rollup.sessions += 1;
+            totals.rollup.play_time_secs += session.duration_secs;
+            for (key, stats) in &session.stats {
+                totals.rollup.presses += stats.presses;
+                totals.rollup.chatters += stats.chatters;
+                let key_total = totals.keys.entry(key.clone()).or_default();
+                key_total.presses += stats.presses;
+                key_total.chatters += stats.chatters;
+            }
+        }
+
+        for usage in CounterSnapshots::usage(snapshots) {
+            let totals = period_totals(&mut periods, period.start_of(local_date(&usage.to, tz)));
+            for stats in usage.keys.values() {
+                totals.rollup.all_presses += stats.presses;
+                totals.rollup.all_chatters += stats.chatters;
+            }
+        }
+
+        periods
+            .into_values()
+            .map(|PeriodTotals { mut rollup, keys }| {
+                let mut keys: Vec<(LogicalKey, SessionKeyStats)> = keys.into_iter().collect();
+                keys.sort_by(|a, b| b.1.presses.cmp(&a.1.presses).then_with(|| a.0.cmp(&b.0)));
+                rollup.keys = keys
+                    .into_iter()
+                    .map(|(key, stats)| SessionKeyRow {
+                        key: key.to_string(),
+                        presses: stats.presses,
+                        chatters: stats.chatters,
+                    })
+                    .collect();
+                rollup
+            })
+            .collect()
+    }
+
+    /// `rollup` in a fixed UTC offset, or in the system timezone if there is none.
+    pub fn rollup_at_offset(
+        sessions: &[SessionRecord],
+        snapshots: &[CounterSnapshot],
+        period: RollupPeriod,
+        utc_offset_minutes: Option<i32>,
+    ) -> Vec<PlayRollup> {
+        match utc_offset_minutes.and_then(|minutes| FixedOffset::east_opt(minutes * 60)) {
+            Some(offset) => Self::rollup(sessions, snapshots, period, &offset),
+            None => Self::rollup(sessions, snapshots, period, &Local),
+        }
+    }
+
+    pub fn render(rollups: &[PlayRollup], format: ExportFormat) -> serde_json::Result<String> {
+        match format {
+            ExportFormat::Json => serde_json::to_string_pretty(rollups),
+            ExportFormat::Csv => Ok(Self::render_csv(rollups)),
+        }
+    }
+
+    /// One row per period and key, most pressed key first. Periods without any input get a
+    /// single row with an empty key.
+    fn render_csv(rollups: &[PlayRollup]) -> String {
+        let mut out = String::from("period_start,sessions,play_time_secs,total_presses,total_chatters,all_presses,all_chatters,key,presses,chatters\n");
+        for rollup in rollups {
+            let period_fields = [
+                rollup.period_start.to_string(),
+                rollup.sessions.to_string(),
+                rollup.play_time_secs.to_string(),
+                rollup.presses.to_string(),
+                rollup.chatters.to_string(),
+                rollup.all_presses.to_string(),
+                rollup.all_chatters.to_string(),
+            ];
+            if rollup.keys.is_empty() {
+                let mut fields = period_fields.to_vec();
+                fields.extend([String::new(), String::new(), String::new()]);
+                push_csv_row(&mut out, &fields);
+            }
+            for key in &rollup.keys {
+                let mut fields = period_fields.to_vec();
+                fields.extend([key.key.clone(), key.presses.to_string(), key.chatters.to_string()]);
+                push_csv_row(&mut out, &fields);
+            }
+        }
+        out
+    }
+}
+
+/// A rollup while its per-key totals are still being summed.
+struct PeriodTotals {
+    rollup: PlayRollup,
+    keys: HashMap<LogicalKey, SessionKeyStats>,
+}
+
+fn period_totals(periods: &mut BTreeMap<NaiveDate, PeriodTotals>, period_start: NaiveDate) -> &mut PeriodTotals {
+    periods.entry(period_start).or_insert_with(|| PeriodTotals {
+        rollup: PlayRollup {
+            period_start,
+            sessions: 0,
+            play_time_secs: 0,
+            presses: 0,
+            chatters: 0,
+            all_presses: 0,
+            all_chatters: 0,
+            keys: Vec::new(),
+        },
+        keys: HashMap::new(),
+    })
+}
+
+fn local_date<Tz: TimeZone>(time: &DateTime<Utc>, tz: &Tz) -> NaiveDate {
+    time.with_timezone(tz).date_naive()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::models::SessionOrigin;
+
+    fn session(start: &str, key_presses: &[(LogicalKey, u64)]) -> SessionRecord {
+        let start_time = DateTime::parse_from_rfc3339(start).unwrap().with_timezone(&Utc);
+        SessionRecord {
+            start_time,
+            end_time: start_time + Duration::minutes(30),
+            duration_secs: 1800,
+            stats: key_presses
+                .iter()
+                .map(|(key, presses)| (key.clone(), SessionKeyStats { presses: *presses, chatters: 1 }))
+                .collect(),
+            game_id: None,
+            started_by: SessionOrigin::Process,
+            merged: false,
+            timeline: SessionTimeline::default(),
+        }
+    }
+
+    fn sessions() -> Vec<SessionRecord> {
+        vec![
+            // Sunday evening in UTC, already Monday in Tokyo
+            session("2025-06-01T20:00:00Z", &[(LogicalKey::Key1, 100)]),
+            session("2025-06-02T10:00:00Z", &[(LogicalKey::Key1, 50), (LogicalKey::Key2, 200)]),
+            session("2025-06-30T23:30:00Z", &[(LogicalKey::Key3, 10)]),
+        ]
+    }
+
+    #[test]
+    fn test_daily_rollup_follows_timezone() {
+        let utc = PlayStatsAggregator::rollup(&sessions(), &[], RollupPeriod::Day, &Utc);
+        let days: Vec<String> = utc.iter().map(|r| r.period_start.to_string()).collect();
+        assert_eq!(days, ["2025-06-01", "2025-06-02", "2025-06-30"]);
+
+        let tokyo = PlayStatsAggregator::rollup_at_offset(&sessions(), &[], RollupPeriod::Day, Some(9 * 60));
+        let days: Vec<String> = tokyo.iter().map(|r| r.period_start.to_string()).collect();
+        assert_eq!(days, ["2025-06-02", "2025-07-01"]);
+        assert_eq!(tokyo[0].sessions, 2);
+        assert_eq!(tokyo[0].presses, 350);
+        assert_eq!(tokyo[0].play_time_secs, 3600);
+    }
+
+    #[test]
+    fn test_weekly_and_monthly_rollups() {
+        let weeks = PlayStatsAggregator::rollup(&sessions(), &[], RollupPeriod::Week, &Utc);
+        let starts: Vec<String> = weeks.iter().map(|r| r.period_start.to_string()).collect();
+        assert_eq!(starts, ["2025-05-26", "2025-06-02", "2025-06-30"]);
+
+        let months = PlayStatsAggregator::rollup(&sessions(), &[], RollupPeriod::Month, &Utc);
+        assert_eq!(months.len(), 1);
+        assert_eq!(months[0].period_start.to_string(), "2025-06-01");
+        let keys: Vec<(&str, u64)> = months[0].keys.iter().map(|k| (k.key.as_str(), k.presses)).collect();
+        assert_eq!(keys, [("Key2", 200), ("Key1", 150), ("Key3", 10)]);
+    }
+
+    #[test]
+    fn test_snapshot_usage_counts_outside_sessions() {
+        let snapshot = |at: &str, presses: u64| CounterSnapshot {
+            taken_at: DateTime::parse_from_rfc3339(at).unwrap().with_timezone(&Utc),
+            totals: [(LogicalKey::Key1, SessionKeyStats { presses, chatters: 0 })].into_iter().collect(),
+        };
+        let snapshots = [
+            snapshot("2025-06-02T08:00:00Z", 1000),
+            snapshot("2025-06-02T11:00:00Z", 1400),
+            snapshot("2025-06-03T09:00:00Z", 1500),
+        ];
+
+        let days = PlayStatsAggregator::rollup(&sessions()[1..2], &snapshots, RollupPeriod::Day, &Utc);
+        assert_eq!(days.len(), 2);
+        assert_eq!((days[0].presses, days[0].all_presses), (250, 400));
+        assert_eq!((days[1].sessions, days[1].all_presses), (0, 100));
+    }
+
+    #[test]
+    fn test_csv_lists_keys_per_period() {
+        let rollups = PlayStatsAggregator::rollup(&sessions()[1..2], &[], RollupPeriod::Day, &Utc);
+        let csv = PlayStatsAggregator::render(&rollups, ExportFormat::Csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[1], "2025-06-02,1,1800,250,2,0,0,Key2,200,1");
+        assert_eq!(lines[2], "2025-06-02,1,1800,250,2,0,0,Key1,50,1");
+    }
+}
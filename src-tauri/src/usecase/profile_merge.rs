@@ -0,0 +1,341 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::domain::models::{
+    ButtonStats, ControllerProfile, CounterSnapshot, GameStats, LogicalKey, SessionRecord, SwitchData,
+};
+use crate::usecase::counter_snapshots::MAX_COUNTER_SNAPSHOTS;
+use crate::usecase::session_manager::SessionManager;
+
+/// A disagreement between two profiles that could not be merged automatically.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum MergeConflict {
+    /// Both sides track the key, but with different switch models.
+    /// The side that was replaced more recently is kept (local on a tie).
+    SwitchModel {
+        key: LogicalKey,
+        local_model_id: String,
+        remote_model_id: String,
+        kept_model_id: String,
+    },
+}
+
+/// Result of merging two controller profiles.
+#[derive(Debug, Clone)]
+pub struct MergeOutcome {
+    pub merged: ControllerProfile,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Merges statistics of the same physical controller recorded on two machines.
+pub struct ProfileMerger;
+
+impl ProfileMerger {
+    /// Merge `remote` into `local`.
+    ///
+    /// With a `baseline` (the state both sides started from, e.g. an earlier exported bundle),
+    /// counters are combined as `baseline + (local - baseline) + (remote - baseline)`.
+    /// Without one there is no way to tell shared counts apart, so each counter takes the
+    /// larger of the two values.
+    ///
+    /// The local mapping is kept. History, sessions and counter snapshots are unioned and
+    /// deduplicated by timestamp.
+    pub fn merge(
+        baseline: Option<&ControllerProfile>,
+        local: &ControllerProfile,
+        remote: &ControllerProfile,
+    ) -> MergeOutcome {
+        let mut merged = local.clone();
+        let mut conflicts = Vec::new();
+
+        for (key, remote_switch) in &remote.switches {
+            let Some(local_switch) = merged.switches.get_mut(key) else {
+                merged.switches.insert(key.clone(), remote_switch.clone());
+                continue;
+            };
+
+            let remote_is_newer = remote_switch.last_replaced_at > local_switch.last_replaced_at;
+
+            if local_switch.switch_model_id != remote_switch.switch_model_id {
+                let kept = if remote_is_newer { remote_switch } else { &*local_switch };
+                conflicts.push(MergeConflict::SwitchModel {
+                    key: key.clone(),
+                    local_model_id: local_switch.switch_model_id.clone(),
+                    remote_model_id: remote_switch.switch_model_id.clone(),
+                    kept_model_id: kept.switch_model_id.clone(),
+                });
+                if remote_is_newer {
+                    *local_switch = remote_switch.clone();
+                }
+                continue;
+            }
+
+            if local_switch.last_replaced_at != remote_switch.last_replaced_at {
+                // One side replaced the switch; the other side's counts belong to the old one.
+                if remote_is_newer {
+                    *local_switch = remote_switch.clone();
+                }
+                continue;
+            }
+
+            let base_stats = baseline
+                .and_then(|b| b.switches.get(key))
+                .filter(|b| Self::is_same_switch(b, local_switch));
+
+            local_switch.stats = match base_stats {
+                Some(base) => Self::sum_deltas(&base.stats, &local_switch.stats, &remote_switch.stats),
+                None => Self::max_stats(&local_switch.stats, &remote_switch.stats),
+            };
+        }
+
+        for (game_id, remote_game) in &remote.game_stats {
+            let base_game = baseline.and_then(|b| b.game_stats.get(game_id));
+            let local_game = merged.game_stats.entry(game_id.clone()).or_default();
+            *local_game = Self::merge_game_stats(base_game, local_game, remote_game);
+        }
+
+        for entry in &remote.switch_history {
+            let exists = merged.switch_history.iter().any(|e| {
+                e.date == entry.date && e.key == entry.key && e.event_type == entry.event_type
+            });
+            if !exists {
+                merged.switch_history.push(entry.clone());
+            }
+        }
+        merged.switch_history.sort_by_key(|e| e.date);
+
+        Self::union_sessions(&mut merged.session_history, &remote.session_history);
+        Self::union_snapshots(&mut merged.counter_snapshots, &remote.counter_snapshots);
+
+        MergeOutcome { merged, conflicts }
+    }
+
+    /// Add the sessions of `remote` missing from `local`, within the history limits.
+    fn union_sessions(local: &mut Vec<SessionRecord>, remote: &[SessionRecord]) {
+        let known: HashSet<DateTime<Utc>> = local.iter().map(|s| s.start_time).collect();
+        local.extend(remote.iter().filter(|s| !known.contains(&s.start_time)).cloned());
+        local.sort_by_key(|s| s.start_time);
+        SessionManager::trim_history(local);
+    }
+
+    /// Add the snapshots of `remote` missing from `local`, keeping the newest
+    /// `MAX_COUNTER_SNAPSHOTS`.
+    fn union_snapshots(local: &mut Vec<CounterSnapshot>, remote: &[CounterSnapshot]) {
+        let known: HashSet<DateTime<Utc>> = local.iter().map(|s| s.taken_at).collect();
+        local.extend(remote.iter().filter(|s| !known.contains(&s.taken_at)).cloned());
+        local.sort_by_key(|s| s.taken_at);
+        if local.len() > MAX_COUNTER_SNAPSHOTS {
+            let excess = local.len() - MAX_COUNTER_SNAPSHOTS;
+            local.drain(..excess);
+        }
+    }
+
+    fn is_same_switch(a: &SwitchData, b: &SwitchData) -> bool {
+        a.switch_model_id == b.switch_model_id && a.last_replaced_at == b.last_replaced_at
+    }
+
+    fn sum_deltas(base: &ButtonStats, local: &ButtonStats, remote: &ButtonStats) -> ButtonStats {
+        let combine = |b: u64, l: u64, r: u64| {
+            b + l.saturating_sub(b) + r.saturating_sub(b)
+        };
+        ButtonStats {
+            total_presses: combine(base.total_presses, local.total_presses, remote.total_presses),
+            total_releases: combine(base.total_releases, local.total_releases, remote.total_releases),
+            total_chatters: combine(base.total_chatters, local.total_chatters, remote.total_chatters),
+            total_chatter_releases: combine(
+                base.total_chatter_releases,
+                local.total_chatter_releases,
+                remote.total_chatter_releases,
+            ),
+            // Session counters describe the last session on this machine
+            ..local.clone()
+        }
+    }
+
+    /// Game totals follow the same rule as switch counters: sum of deltas with a baseline,
+    /// the larger value without one.
+    fn merge_game_stats(base: Option<&GameStats>, local: &GameStats, remote: &GameStats) -> GameStats {
+        let combine = |b: Option<u64>, l: u64, r: u64| match b {
+            Some(b) => b + l.saturating_sub(b) + r.saturating_sub(b),
+            None => l.max(r),
+        };
+        let mut merged = GameStats {
+            sessions: combine(base.map(|b| b.sessions), local.sessions, remote.sessions),
+            play_time_secs: combine(base.map(|b| b.play_time_secs), local.play_time_secs, remote.play_time_secs),
+            keys: local.keys.clone(),
+        };
+        for (key, remote_key) in &remote.keys {
+            let base_key = base.and_then(|b| b.keys.get(key));
+            let local_key = merged.keys.entry(key.clone()).or_default();
+            local_key.presses = combine(base_key.map(|b| b.presses), local_key.presses, remote_key.presses);
+            local_key.chatters = combine(base_key.map(|b| b.chatters), local_key.chatters, remote_key.chatters);
+        }
+        merged
+    }
+
+    fn max_stats(local: &ButtonStats, remote: &ButtonStats) -> ButtonStats {
+        ButtonStats {
+            total_presses: local.total_presses.max(remote.total_presses),
+            total_releases: local.total_releases.max(remote.total_releases),
+            total_chatters: local.total_chatters.max(remote.total_chatters),
+            total_chatter_releases: local.total_chatter_releases.max(remote.total_chatter_releases),
+            ..local.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+    use crate::domain::models::SessionOrigin;
+    use std::collections::HashMap;
+
+    fn switch(model: &str, presses: u64, chatters: u64) -> SwitchData {
+        SwitchData {
+            switch_model_id: model.to_string(),
+            stats: ButtonStats {
+                total_presses: presses,
+                total_releases: presses,
+                total_chatters: chatters,
+                ..Default::default()
+            },
+            last_replaced_at: None,
+        }
+    }
+
+    fn profile_with(key: LogicalKey, data: SwitchData) -> ControllerProfile {
+        let mut profile = ControllerProfile::default();
+        profile.switches.insert(key, data);
+        profile
+    }
+
+    #[test]
+    fn test_merge_sums_deltas_since_baseline() {
+        let baseline = profile_with(LogicalKey::Key1, switch("omron", 1000, 10));
+        let local = profile_with(LogicalKey::Key1, switch("omron", 1300, 12));
+        let remote = profile_with(LogicalKey::Key1, switch("omron", 1500, 15));
+
+        let outcome = ProfileMerger::merge(Some(&baseline), &local, &remote);
+        let stats = &outcome.merged.switches[&LogicalKey::Key1].stats;
+        assert_eq!(stats.total_presses, 1800);
+        assert_eq!(stats.total_releases, 1800);
+        assert_eq!(stats.total_chatters, 17);
+        assert!(outcome.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_merge_without_baseline_takes_max() {
+        let local = profile_with(LogicalKey::Key1, switch("omron", 1300, 20));
+        let remote = profile_with(LogicalKey::Key1, switch("omron", 1500, 15));
+
+        let outcome = ProfileMerger::merge(None, &local, &remote);
+        let stats = &outcome.merged.switches[&LogicalKey::Key1].stats;
+        assert_eq!(stats.total_presses, 1500);
+        assert_eq!(stats.total_chatters, 20);
+    }
+
+    #[test]
+    fn test_merge_reports_model_conflict() {
+        let local = profile_with(LogicalKey::Key2, switch("omron", 100, 0));
+        let mut newer = switch("kailh", 5, 0);
+        newer.last_replaced_at = Some(Utc::now());
+        let remote = profile_with(LogicalKey::Key2, newer);
+
+        let outcome = ProfileMerger::merge(None, &local, &remote);
+        assert_eq!(
+            outcome.conflicts,
+            vec![MergeConflict::SwitchModel {
+                key: LogicalKey::Key2,
+                local_model_id: "omron".to_string(),
+                remote_model_id: "kailh".to_string(),
+                kept_model_id: "kailh".to_string(),
+            }]
+        );
+        assert_eq!(outcome.merged.switches[&LogicalKey::Key2].stats.total_presses, 5);
+    }
+
+    #[test]
+    fn test_merge_dedups_sessions() {
+        let start = Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap();
+        let record = |offset_hours: i64| SessionRecord {
+            start_time: start + Duration::hours(offset_hours),
+            end_time: start + Duration::hours(offset_hours) + Duration::minutes(10),
+            duration_secs: 600,
+            stats: HashMap::new(),
+            game_id: None,
+            started_by: SessionOrigin::Process,
+            merged: false,
+            timeline: Default::default(),
+        };
+
+        let local = ControllerProfile {
+            session_history: vec![record(0), record(2)],
+            ..Default::default()
+        };
+        let remote = ControllerProfile {
+            session_history: vec![record(1), record(2)],
+            ..Default::default()
+        };
+
+        let outcome = ProfileMerger::merge(None, &local, &remote);
+        let starts: Vec<_> = outcome.merged.session_history.iter().map(|s| s.start_time).collect();
+        assert_eq!(starts, vec![start, start + Duration::hours(1), start + Duration::hours(2)]);
+    }
+
+    #[test]
+    fn test_merge_unions_counter_snapshots() {
+        let start = Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap();
+        let snapshot = |offset_hours: i64| CounterSnapshot {
+            taken_at: start + Duration::hours(offset_hours),
+            totals: HashMap::new(),
+        };
+        let local = ControllerProfile {
+            counter_snapshots: vec![snapshot(0), snapshot(2)],
+            ..Default::default()
+        };
+        let remote = ControllerProfile {
+            counter_snapshots: vec![snapshot(1), snapshot(2)],
+            ..Default::default()
+        };
+
+        let outcome = ProfileMerger::merge(None, &local, &remote);
+        let taken: Vec<_> = outcome.merged.counter_snapshots.iter().map(|s| s.taken_at).collect();
+        assert_eq!(taken, vec![start, start + Duration::hours(1), start + Duration::hours(2)]);
+
+        // Only the newest are kept
+        let many = ControllerProfile {
+            counter_snapshots: (3..MAX_COUNTER_SNAPSHOTS as i64 + 3).map(snapshot).collect(),
+            ..Default::default()
+        };
+        let outcome = ProfileMerger::merge(None, &local, &many);
+        assert_eq!(outcome.merged.counter_snapshots.len(), MAX_COUNTER_SNAPSHOTS);
+        assert_eq!(outcome.merged.counter_snapshots[0].taken_at, start + Duration::hours(3));
+    }
+
+    #[test]
+    fn test_merge_game_stats_sums_deltas_since_baseline() {
+        let game = |sessions: u64, presses: u64| {
+            let mut stats = GameStats { sessions, play_time_secs: sessions * 60, ..Default::default() };
+            stats.keys.insert(LogicalKey::Key1, crate::domain::models::SessionKeyStats { presses, chatters: 0 });
+            stats
+        };
+        let with_game = |stats: GameStats| {
+            let mut profile = ControllerProfile::default();
+            profile.game_stats.insert("lr2".to_string(), stats);
+            profile
+        };
+        let baseline = with_game(game(10, 1000));
+        let local = with_game(game(12, 1200));
+        let remote = with_game(game(11, 1050));
+
+        let merged = ProfileMerger::merge(Some(&baseline), &local, &remote).merged;
+        assert_eq!(merged.game_stats["lr2"].sessions, 13);
+        assert_eq!(merged.game_stats["lr2"].play_time_secs, 780);
+        assert_eq!(merged.game_stats["lr2"].keys[&LogicalKey::Key1].presses, 1250);
+
+        let merged = ProfileMerger::merge(None, &local, &remote).merged;
+        assert_eq!(merged.game_stats["lr2"].sessions, 12);
+    }
+}
@@ -0,0 +1,240 @@
+use chrono::Utc;
+
+use crate::domain::errors::ProfileTransferError;
+use crate::domain::models::{
+    ControllerProfile, ImportMode, ProfileBundle, UserProfile, PROFILE_BUNDLE_FORMAT_VERSION,
+};
+use crate::usecase::profile_merge::{MergeConflict, ProfileMerger};
+
+/// Result of applying an imported bundle.
+#[derive(Debug, Clone)]
+pub struct ImportOutcome {
+    /// Id of the controller profile that received the data.
+    pub controller_id: String,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Profile import/export service for moving a controller's data between machines.
+pub struct ProfileTransfer;
+
+impl ProfileTransfer {
+    /// Package a single controller profile into a portable bundle.
+    pub fn export_bundle(
+        profile: &UserProfile,
+        controller_id: &str,
+        controller_name: Option<String>,
+    ) -> Result<ProfileBundle, ProfileTransferError> {
+        let controller = profile
+            .controllers
+            .get(controller_id)
+            .ok_or_else(|| ProfileTransferError::ControllerNotFound(controller_id.to_string()))?;
+
+        Ok(ProfileBundle {
+            format_version: PROFILE_BUNDLE_FORMAT_VERSION,
+            exported_at: Utc::now(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            controller_id: controller_id.to_string(),
+            controller_name,
+            profile: controller.clone(),
+        })
+    }
+
+    /// Apply a bundle to the user profile.
+    ///
+    /// `baseline` is only used when merging; see `ProfileMerger::merge` for the conflict rules.
+    pub fn import_bundle(
+        profile: &mut UserProfile,
+        bundle: ProfileBundle,
+        mode: ImportMode,
+        baseline: Option<&ControllerProfile>,
+    ) -> Result<ImportOutcome, ProfileTransferError> {
+        if bundle.format_version > PROFILE_BUNDLE_FORMAT_VERSION {
+            return Err(ProfileTransferError::UnsupportedVersion(bundle.format_version));
+        }
+
+        match mode {
+            ImportMode::AddNew { controller_id: Some(id) } => {
+                if profile.controllers.contains_key(&id) {
+                    return Err(ProfileTransferError::ControllerExists(id));
+                }
+                profile.controllers.insert(id.clone(), bundle.profile);
+                Ok(ImportOutcome { controller_id: id, conflicts: Vec::new() })
+            }
+            ImportMode::AddNew { controller_id: None } => {
+                let id = Self::unique_controller_id(profile, &bundle.controller_id);
+                profile.controllers.insert(id.clone(), bundle.profile);
+                Ok(ImportOutcome { controller_id: id, conflicts: Vec::new() })
+            }
+            ImportMode::MergeInto { controller_id, .. } => {
+                let target = profile
+                    .controllers
+                    .get_mut(&controller_id)
+                    .ok_or_else(|| ProfileTransferError::ControllerNotFound(controller_id.clone()))?;
+                let outcome = ProfileMerger::merge(baseline, target, &bundle.profile);
+                *target = outcome.merged;
+                Ok(ImportOutcome { controller_id, conflicts: outcome.conflicts })
+            }
+        }
+    }
+
+    /// Returns `base_id` if unused, otherwise `base_id-imported`, `base_id-imported-2`, ...
+    fn unique_controller_id(profile: &UserProfile, base_id: &str) -> String {
+        if !profile.controllers.contains_key(base_id) {
+            return base_id.to_string();
+        }
+        let mut candidate = format!("{}-imported", base_id);
+        let mut n = 2;
+        while profile.controllers.contains_key(&candidate) {
+            candidate = format!("{}-imported-{}", base_id, n);
+            n += 1;
+        }
+        candidate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use crate::domain::models::{ButtonStats, LogicalKey, SessionOrigin, SessionRecord, SwitchData, SwitchHistoryEntry};
+    use std::collections::HashMap;
+
+    fn switch(model: &str, presses: u64, replaced_day: Option<u32>) -> SwitchData {
+        SwitchData {
+            switch_model_id: model.to_string(),
+            stats: ButtonStats {
+                total_presses: presses,
+                total_releases: presses,
+                ..Default::default()
+            },
+            last_replaced_at: replaced_day.map(|d| Utc.with_ymd_and_hms(2025, 1, d, 0, 0, 0).unwrap()),
+        }
+    }
+
+    fn session(hour: u32) -> SessionRecord {
+        let start = Utc.with_ymd_and_hms(2025, 2, 1, hour, 0, 0).unwrap();
+        SessionRecord {
+            start_time: start,
+            end_time: start + Duration::minutes(30),
+            duration_secs: 1800,
+            stats: HashMap::new(),
+            game_id: None,
+            started_by: SessionOrigin::Process,
+            merged: false,
+            timeline: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_export_missing_controller() {
+        let profile = UserProfile::default();
+        let result = ProfileTransfer::export_bundle(&profile, "missing", None);
+        assert!(matches!(result, Err(ProfileTransferError::ControllerNotFound(_))));
+    }
+
+    #[test]
+    fn test_import_as_new_generates_unique_id() {
+        let mut profile = UserProfile::default();
+        let bundle = ProfileTransfer::export_bundle(&profile, "default", None).unwrap();
+
+        let id = ProfileTransfer::import_bundle(
+            &mut profile,
+            bundle.clone(),
+            ImportMode::AddNew { controller_id: None },
+            None,
+        )
+        .unwrap()
+        .controller_id;
+        assert_eq!(id, "default-imported");
+
+        let id = ProfileTransfer::import_bundle(
+            &mut profile,
+            bundle.clone(),
+            ImportMode::AddNew { controller_id: None },
+            None,
+        )
+        .unwrap()
+        .controller_id;
+        assert_eq!(id, "default-imported-2");
+
+        let result = ProfileTransfer::import_bundle(
+            &mut profile,
+            bundle,
+            ImportMode::AddNew { controller_id: Some("default".to_string()) },
+            None,
+        );
+        assert!(matches!(result, Err(ProfileTransferError::ControllerExists(_))));
+    }
+
+    #[test]
+    fn test_import_rejects_newer_format() {
+        let mut profile = UserProfile::default();
+        let mut bundle = ProfileTransfer::export_bundle(&profile, "default", None).unwrap();
+        bundle.format_version = PROFILE_BUNDLE_FORMAT_VERSION + 1;
+
+        let result = ProfileTransfer::import_bundle(
+            &mut profile,
+            bundle,
+            ImportMode::AddNew { controller_id: None },
+            None,
+        );
+        assert!(matches!(result, Err(ProfileTransferError::UnsupportedVersion(_))));
+    }
+
+    #[test]
+    fn test_merge_conflict_rules() {
+        let mut profile = UserProfile::default();
+        {
+            let local = profile.controllers.get_mut("default").unwrap();
+            local.switches.insert(LogicalKey::Key1, switch("omron", 100, Some(1)));
+            local.switches.insert(LogicalKey::Key2, switch("omron", 500, Some(1)));
+            local.session_history.push(session(1));
+            local.switch_history.push(SwitchHistoryEntry {
+                date: Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap(),
+                key: LogicalKey::Key1,
+                old_model_id: "generic_unknown".to_string(),
+                new_model_id: "omron".to_string(),
+                previous_stats: ButtonStats::default(),
+                event_type: "Replace".to_string(),
+            });
+        }
+
+        let mut incoming = profile.controllers.get("default").unwrap().clone();
+        // Same switch, counted further on the other machine
+        incoming.switches.insert(LogicalKey::Key1, switch("omron", 250, Some(1)));
+        // Replaced more recently on the other machine
+        incoming.switches.insert(LogicalKey::Key2, switch("kailh", 10, Some(5)));
+        // Only known on the other machine
+        incoming.switches.insert(LogicalKey::Key3, switch("omron", 42, None));
+        incoming.session_history.push(session(2));
+
+        let bundle = ProfileBundle {
+            format_version: PROFILE_BUNDLE_FORMAT_VERSION,
+            exported_at: Utc::now(),
+            app_version: "test".to_string(),
+            controller_id: "default".to_string(),
+            controller_name: None,
+            profile: incoming,
+        };
+
+        let outcome = ProfileTransfer::import_bundle(
+            &mut profile,
+            bundle,
+            ImportMode::MergeInto { controller_id: "default".to_string(), baseline_path: None },
+            None,
+        )
+        .unwrap();
+        assert_eq!(outcome.conflicts.len(), 1);
+
+        let merged = profile.controllers.get("default").unwrap();
+        assert_eq!(merged.switches[&LogicalKey::Key1].stats.total_presses, 250);
+        assert_eq!(merged.switches[&LogicalKey::Key2].switch_model_id, "kailh");
+        assert_eq!(merged.switches[&LogicalKey::Key2].stats.total_presses, 10);
+        assert_eq!(merged.switches[&LogicalKey::Key3].stats.total_presses, 42);
+
+        // Duplicates are dropped, new entries kept in order
+        assert_eq!(merged.switch_history.len(), 1);
+        assert_eq!(merged.session_history.len(), 2);
+        assert!(merged.session_history[0].start_time < merged.session_history[1].start_time);
+    }
+}
@@ -1,7 +1,13 @@
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 
-use crate::domain::models::{ControllerProfile, SessionKeyStats, SessionRecord};
+use crate::domain::models::{
+    ActiveSession, ControllerProfile, SessionKeyStats, SessionRecord, SessionTimeline, MAX_RECENT_SESSIONS,
+};
+
+/// Maximum number of session records kept in `ControllerProfile.session_history`, years of
+/// regular play.
+pub const MAX_SESSION_HISTORY: usize = 10_000;
 
 /// Session management service — extracted from `ControllerProfile` methods.
 /// Handles game session start/end logic.
@@ -13,16 +19,32 @@ impl SessionManager {
         for switch in profile.switches.values_mut() {
             switch.stats.reset_session_stats();
         }
+        profile.session_timeline = SessionTimeline::default();
+    }
+
+    /// Add the session counts since the last call to the running session's timeline.
+    pub fn record_timeline(profile: &mut ControllerProfile, offset_secs: u64) {
+        for (key, switch) in &profile.switches {
+            let session_stats = SessionKeyStats {
+                presses: switch.stats.last_session_presses,
+                chatters: switch.stats.last_session_chatters,
+            };
+            profile.session_timeline.record(key, &session_stats, offset_secs);
+        }
     }
 
     /// End a session: collect session stats, create a record, and store it.
-    /// Returns the duration in seconds.
-    pub fn end_session(
-        profile: &mut ControllerProfile,
-        start_time: DateTime<Utc>,
-        end_time: DateTime<Utc>,
-    ) -> u64 {
-        let duration_secs = (end_time - start_time).num_seconds().max(0) as u64;
+    /// Sessions of a known game also count into its `game_stats`.
+    /// Returns the duration in seconds, without the time the session was paused.
+    pub fn end_session(profile: &mut ControllerProfile, session: &ActiveSession, end_time: DateTime<Utc>) -> u64 {
+        let start_time = session.start_time;
+        let game_id = session.game_id.as_deref();
+        let duration_secs = session.duration_secs(end_time);
+
+        // The end itself is outside the session
+        Self::record_timeline(profile, duration_secs.saturating_sub(1));
+        let mut timeline = std::mem::take(&mut profile.session_timeline);
+        timeline.counted.clear();
 
         let mut stats = HashMap::new();
         for (key, switch) in &profile.switches {
@@ -37,19 +59,46 @@ impl SessionManager {
             }
         }
 
+        if let Some(game_id) = game_id {
+            let game = profile.game_stats.entry(game_id.to_string()).or_default();
+            game.sessions += 1;
+            game.play_time_secs += duration_secs;
+            for (key, session_stats) in &stats {
+                let total = game.keys.entry(key.clone()).or_default();
+                total.presses += session_stats.presses;
+                total.chatters += session_stats.chatters;
+            }
+        }
+
         let record = SessionRecord {
             start_time,
             end_time,
             duration_secs,
             stats,
+            game_id: game_id.map(str::to_string),
+            started_by: session.started_by,
+            merged: session.merged,
+            timeline,
         };
 
-        profile.recent_sessions.push(record);
-        if profile.recent_sessions.len() > 10 {
-            // Increased to 10 for better history
-            profile.recent_sessions.remove(0);
-        }
+        profile.session_history.push(record);
+        Self::trim_history(&mut profile.session_history);
 
         duration_secs
     }
+
+    /// Cap `history` at `MAX_SESSION_HISTORY` and drop the timelines of all but the newest
+    /// `MAX_RECENT_SESSIONS` sessions.
+    pub fn trim_history(history: &mut Vec<SessionRecord>) {
+        if history.len() > MAX_SESSION_HISTORY {
+            let excess = history.len() - MAX_SESSION_HISTORY;
+            history.drain(..excess);
+        }
+        let recent_start = history.len().saturating_sub(MAX_RECENT_SESSIONS);
+        for record in &mut history[..recent_start] {
+            if !record.timeline.buckets.is_empty() {
+                record.timeline = SessionTimeline::default();
+            }
+        }
+    }
 }
@@ -0,0 +1,403 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::domain::models::{
+    get_default_switch_models, AppConfig, GameDefinition, LogicalKey, SessionOrigin, SessionRecord,
+    SwitchData, SwitchModelInfo,
+};
+
+/// Output format of a rendered session report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+/// End-of-session summary of one recorded session.
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionReport {
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub duration_secs: u64,
+    pub game_id: Option<String>,
+    pub game_name: Option<String>,
+    pub started_by: SessionOrigin,
+    pub merged: bool,
+    pub total_presses: u64,
+    pub total_chatters: u64,
+    pub chatter_rate_percent: f64,
+    pub presses_per_minute: f64,
+    /// Over the other recorded sessions; `None` if there are none.
+    pub average_presses_per_minute: Option<f64>,
+    /// From the `AlertRule::ChatterRate` rule; `None` without one.
+    pub chatter_alert_percent: Option<f64>,
+    /// Keys played in the session, in `LogicalKey` order.
+    pub keys: Vec<KeyReport>,
+}
+
+/// One key's part of a `SessionReport`.
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyReport {
+    pub key: String,
+    pub switch_model_id: Option<String>,
+    pub presses: u64,
+    pub chatters: u64,
+    /// Chatters per press.
+    pub chatter_rate_percent: f64,
+    /// Presses per session over the other recorded sessions.
+    pub average_presses: Option<f64>,
+    /// Chatters per press over the other recorded sessions, if the key was pressed in them.
+    pub average_chatter_rate_percent: Option<f64>,
+    /// The chatter rate is above `SessionReport::chatter_alert_percent`.
+    pub exceeds_alert: bool,
+    /// Share of the switch's rated lifespan used in this session.
+    pub lifespan_used_percent: Option<f64>,
+}
+
+/// Builds and renders end-of-session reports.
+pub struct SessionReporter;
+
+impl SessionReporter {
+    /// Report on the session in `sessions` that started at `start_time`, or the latest one.
+    pub fn build_for(
+        sessions: &[SessionRecord],
+        start_time: Option<DateTime<Utc>>,
+        switches: &HashMap<LogicalKey, SwitchData>,
+        config: &AppConfig,
+    ) -> Option<SessionReport> {
+        let record = match start_time {
+            Some(start) => sessions.iter().find(|s| s.start_time == start)?,
+            None => sessions.last()?,
+        };
+        Some(Self::build(
+            record,
+            sessions,
+            switches,
+            &get_default_switch_models(),
+            &config.game_definitions(),
+            config.chatter_alert_percent(),
+        ))
+    }
+
+    /// Report on `record`, compared with the other sessions in `history`.
+    pub fn build(
+        record: &SessionRecord,
+        history: &[SessionRecord],
+        switches: &HashMap<LogicalKey, SwitchData>,
+        models: &[SwitchModelInfo],
+        games: &[GameDefinition],
+        chatter_alert_percent: Option<f64>,
+    ) -> SessionReport {
+        let others: Vec<&SessionRecord> = history
+            .iter()
+            .filter(|s| s.start_time != record.start_time)
+            .collect();
+
+        let mut keys: Vec<(&LogicalKey, _)> = record.stats.iter().collect();
+        keys.sort_by(|a, b| a.0.cmp(b.0));
+        let keys: Vec<KeyReport> = keys
+            .into_iter()
+            .map(|(key, stats)| {
+                let switch = switches.get(key);
+                let rated = switch
+                    .and_then(|s| models.iter().find(|m| m.id == s.switch_model_id))
+                    .map(|m| m.rated_lifespan_presses)
+                    .filter(|&r| r > 0);
+                let chatter_rate_percent = percent(stats.chatters, stats.presses);
+
+                let (other_presses, other_chatters) = others
+                    .iter()
+                    .filter_map(|s| s.stats.get(key))
+                    .fold((0, 0), |(p, c), s| (p + s.presses, c + s.chatters));
+
+                KeyReport {
+                    key: key.to_string(),
+                    switch_model_id: switch.map(|s| s.switch_model_id.clone()),
+                    presses: stats.presses,
+                    chatters: stats.chatters,
+                    chatter_rate_percent,
+                    average_presses: (!others.is_empty()).then(|| other_presses as f64 / others.len() as f64),
+                    average_chatter_rate_percent: (other_presses > 0).then(|| percent(other_chatters, other_presses)),
+                    exceeds_alert: chatter_alert_percent.is_some_and(|alert| chatter_rate_percent > alert),
+                    lifespan_used_percent: rated.map(|r| stats.presses as f64 / r as f64 * 100.0),
+                }
+            })
+            .collect();
+
+        let total_presses = keys.iter().map(|k| k.presses).sum();
+        let total_chatters = keys.iter().map(|k| k.chatters).sum();
+        let other_minutes: f64 = others.iter().map(|s| s.duration_secs as f64 / 60.0).sum();
+        let other_presses: u64 = others.iter().flat_map(|s| s.stats.values()).map(|s| s.presses).sum();
+
+        SessionReport {
+            start_time: record.start_time,
+            end_time: record.end_time,
+            duration_secs: record.duration_secs,
+            game_id: record.game_id.clone(),
+            game_name: record
+                .game_id
+                .as_ref()
+                .and_then(|id| games.iter().find(|g| g.id == *id))
+                .map(|g| g.display_name.clone()),
+            started_by: record.started_by,
+            merged: record.merged,
+            total_presses,
+            total_chatters,
+            chatter_rate_percent: percent(total_chatters, total_presses),
+            presses_per_minute: per_minute(total_presses, record.duration_secs),
+            average_presses_per_minute: (other_minutes > 0.0).then(|| other_presses as f64 / other_minutes),
+            chatter_alert_percent,
+            keys,
+        }
+    }
+
+    pub fn render(report: &SessionReport, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Markdown => Self::render_markdown(report),
+            ReportFormat::Html => Self::render_html(report),
+        }
+    }
+
+    fn render_markdown(report: &SessionReport) -> String {
+        let mut out = String::from("# Session Report\n\n");
+        for (label, value) in summary_lines(report) {
+            let _ = writeln!(out, "- **{}**: {}", label, value);
+        }
+
+        out.push_str("\n## Keys\n\n");
+        out.push_str("| Key | Switch | Presses | Avg presses | Chatters | Chatter rate | Avg rate | Lifespan used |\n");
+        out.push_str("|---|---|---:|---:|---:|---:|---:|---:|\n");
+        for key in &report.keys {
+            let _ = writeln!(out, "| {} |", key_cells(key).join(" | "));
+        }
+
+        out.push_str("\n## Chatter Alerts\n\n");
+        let Some(alert_percent) = report.chatter_alert_percent else {
+            out.push_str("No chatter rate alert rule is configured.\n");
+            return out;
+        };
+        let alerts: Vec<&KeyReport> = report.keys.iter().filter(|k| k.exceeds_alert).collect();
+        if alerts.is_empty() {
+            let _ = writeln!(out, "No key exceeded the alert level of {:.2}%.", alert_percent);
+        }
+        for key in alerts {
+            let _ = writeln!(
+                out,
+                "- **{}**: {:.2}% (alert level {:.2}%)",
+                key.key, key.chatter_rate_percent, alert_percent
+            );
+        }
+        out
+    }
+
+    fn render_html(report: &SessionReport) -> String {
+        let mut out = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Session Report</title>\n\
+<style>body{font-family:sans-serif}table{border-collapse:collapse}td,th{border:1px solid #ccc;padding:4px 8px}\
+td.num{text-align:right}tr.alert{color:#c00;font-weight:bold}</style>\n</head>\n<body>\n<h1>Session Report</h1>\n<ul>\n",
+        );
+        for (label, value) in summary_lines(report) {
+            let _ = writeln!(out, "<li><b>{}</b>: {}</li>", label, html_escape(&value));
+        }
+        out.push_str("</ul>\n<h2>Keys</h2>\n<table>\n<tr><th>Key</th><th>Switch</th><th>Presses</th>\
+<th>Avg presses</th><th>Chatters</th><th>Chatter rate</th><th>Avg rate</th><th>Lifespan used</th></tr>\n");
+        for key in &report.keys {
+            let cells: Vec<String> = key_cells(key)
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| {
+                    let class = if i >= 2 { " class=\"num\"" } else { "" };
+                    format!("<td{}>{}</td>", class, html_escape(cell))
+                })
+                .collect();
+            let class = if key.exceeds_alert { " class=\"alert\"" } else { "" };
+            let _ = writeln!(out, "<tr{}>{}</tr>", class, cells.concat());
+        }
+        out.push_str("</table>\n");
+        if let Some(alert_percent) = report.chatter_alert_percent {
+            let _ = writeln!(out, "<p>Keys above the chatter alert level of {:.2}% are highlighted.</p>", alert_percent);
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+}
+
+fn percent(part: u64, whole: u64) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64 * 100.0
+    }
+}
+
+fn per_minute(count: u64, duration_secs: u64) -> f64 {
+    if duration_secs == 0 {
+        0.0
+    } else {
+        count as f64 / (duration_secs as f64 / 60.0)
+    }
+}
+
+fn summary_lines(report: &SessionReport) -> Vec<(&'static str, String)> {
+    let mut lines = vec![
+        ("Start", report.start_time.format("%Y-%m-%d %H:%M:%S UTC").to_string()),
+        ("Duration", format_duration(report.duration_secs)),
+    ];
+    if let Some(game_id) = &report.game_id {
+        lines.push(("Game", report.game_name.clone().unwrap_or_else(|| game_id.clone())));
+    }
+    let mut started_by = format!("{:?}", report.started_by);
+    if report.merged {
+        started_by.push_str(" (merged restarts)");
+    }
+    lines.push(("Started by", started_by));
+    lines.push((
+        "Presses",
+        format!(
+            "{} ({:.1}/min, average {})",
+            report.total_presses,
+            report.presses_per_minute,
+            report
+                .average_presses_per_minute
+                .map_or("-".to_string(), |a| format!("{:.1}/min", a))
+        ),
+    ));
+    lines.push((
+        "Chatters",
+        format!("{} ({:.2}%)", report.total_chatters, report.chatter_rate_percent),
+    ));
+    lines
+}
+
+fn key_cells(key: &KeyReport) -> Vec<String> {
+    vec![
+        key.key.clone(),
+        key.switch_model_id.clone().unwrap_or_else(|| "-".to_string()),
+        key.presses.to_string(),
+        key.average_presses.map_or("-".to_string(), |a| format!("{:.1}", a)),
+        key.chatters.to_string(),
+        format!("{:.2}%", key.chatter_rate_percent),
+        key.average_chatter_rate_percent.map_or("-".to_string(), |a| format!("{:.2}%", a)),
+        key.lifespan_used_percent.map_or("-".to_string(), |p| format!("{:.4}%", p)),
+    ]
+}
+
+fn format_duration(secs: u64) -> String {
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {}s", m, s),
+        (h, m, s) => format!("{}h {}m {}s", h, m, s),
+    }
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use crate::domain::models::{default_game_definitions, get_default_switch_models, AlertRule, SessionKeyStats};
+
+    fn record(hour: u32, key_stats: &[(LogicalKey, u64, u64)]) -> SessionRecord {
+        let start = Utc.with_ymd_and_hms(2025, 5, 1, hour, 0, 0).unwrap();
+        SessionRecord {
+            start_time: start,
+            end_time: start + chrono::Duration::minutes(10),
+            duration_secs: 600,
+            stats: key_stats
+                .iter()
+                .map(|(key, presses, chatters)| (key.clone(), SessionKeyStats { presses: *presses, chatters: *chatters }))
+                .collect(),
+            game_id: Some("lr2".to_string()),
+            started_by: SessionOrigin::Process,
+            merged: false,
+            timeline: Default::default(),
+        }
+    }
+
+    fn report() -> SessionReport {
+        let history = vec![
+            record(18, &[(LogicalKey::Key1, 800, 0), (LogicalKey::Key2, 400, 4)]),
+            record(19, &[(LogicalKey::Key1, 1200, 2)]),
+            record(20, &[(LogicalKey::Key1, 1000, 5), (LogicalKey::Key2, 500, 10)]),
+        ];
+        let mut switches = HashMap::new();
+        switches.insert(LogicalKey::Key1, SwitchData {
+            switch_model_id: "omron_d2mv_01_1c3".to_string(),
+            stats: Default::default(),
+            last_replaced_at: None,
+        });
+        SessionReporter::build(
+            &history[2],
+            &history,
+            &switches,
+            &get_default_switch_models(),
+            &default_game_definitions(),
+            Some(1.0),
+        )
+    }
+
+    #[test]
+    fn test_report_compares_with_other_sessions() {
+        let report = report();
+        assert_eq!(report.game_name.as_deref(), Some("Lunatic Rave 2"));
+        assert_eq!(report.total_presses, 1500);
+        assert_eq!(report.presses_per_minute, 150.0);
+        assert_eq!(report.average_presses_per_minute, Some(120.0));
+
+        let key1 = &report.keys[0];
+        assert_eq!(key1.average_presses, Some(1000.0));
+        assert_eq!(key1.average_chatter_rate_percent, Some(0.1));
+        assert!((key1.lifespan_used_percent.unwrap() - 0.01).abs() < 1e-9);
+        assert!(!key1.exceeds_alert);
+
+        let key2 = &report.keys[1];
+        assert_eq!(key2.chatter_rate_percent, 2.0);
+        assert_eq!(key2.average_presses, Some(200.0));
+        assert!(key2.exceeds_alert);
+        assert_eq!(key2.lifespan_used_percent, None);
+    }
+
+    #[test]
+    fn test_markdown_lists_alerts() {
+        let markdown = SessionReporter::render(&report(), ReportFormat::Markdown);
+        assert!(markdown.contains("- **Game**: Lunatic Rave 2\n"));
+        assert!(markdown.contains("| Key2 | - | 500 | 200.0 | 10 | 2.00% | 1.00% | - |\n"));
+        assert!(markdown.contains("- **Key2**: 2.00% (alert level 1.00%)\n"));
+        assert!(!markdown.contains("- **Key1**:"));
+    }
+
+    #[test]
+    fn test_alert_level_comes_from_chatter_rate_rule() {
+        let history = vec![record(20, &[(LogicalKey::Key1, 1000, 15), (LogicalKey::Key2, 500, 15)])];
+        let mut config = AppConfig {
+            alert_rules: vec![AlertRule::ChatterRate { percent: 2.0, window_presses: 500 }],
+            ..Default::default()
+        };
+
+        let report = SessionReporter::build_for(&history, None, &HashMap::new(), &config).unwrap();
+        assert_eq!(report.chatter_alert_percent, Some(2.0));
+        let flagged: Vec<&str> = report.keys.iter().filter(|k| k.exceeds_alert).map(|k| k.key.as_str()).collect();
+        assert_eq!(flagged, ["Key2"]);
+
+        config.alert_rules = vec![AlertRule::DisconnectedInSession];
+        let report = SessionReporter::build_for(&history, None, &HashMap::new(), &config).unwrap();
+        assert!(report.keys.iter().all(|k| !k.exceeds_alert));
+        let markdown = SessionReporter::render(&report, ReportFormat::Markdown);
+        assert!(markdown.ends_with("No chatter rate alert rule is configured.\n"));
+    }
+
+    #[test]
+    fn test_html_highlights_alerts() {
+        let html = SessionReporter::render(&report(), ReportFormat::Html);
+        assert!(html.contains("<tr class=\"alert\"><td>Key2</td>"));
+        assert!(html.contains("<tr><td>Key1</td>"));
+    }
+}
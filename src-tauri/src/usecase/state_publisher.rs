@@ -1,10 +1,13 @@
 use arc_swap::ArcSwap;
 use chrono::{DateTime, Utc};
 use serde::Serialize;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 
-use crate::domain::models::{AppConfig, ControllerInfo, LogicalKey, SessionRecord, SwitchData, SwitchHistoryEntry};
+use crate::domain::models::{
+    ActiveSession, Alert, AppConfig, ButtonLayout, ControllerInfo, CounterSnapshot, GameStats, LogicalKey, SessionRecord,
+    SwitchData, SwitchHistoryEntry,
+};
 
 /// Result of the last save operation.
 #[derive(Debug, Clone, Serialize)]
@@ -14,16 +17,61 @@ pub struct LastSaveResult {
     pub timestamp: DateTime<Utc>,
 }
 
+/// Monotonic counters describing the monitor loop itself.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MonitorCounters {
+    pub polls_total: u64,
+    pub saves_succeeded: u64,
+    pub saves_failed: u64,
+}
+
+/// Measured polling intervals of the monitor loop while a controller is connected.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PollTimingStats {
+    pub configured_us: u64,
+    pub min_us: u64,
+    pub avg_us: u64,
+    pub p99_us: u64,
+    pub max_us: u64,
+    /// Number of intervals the min/avg/p99/max are computed over.
+    pub samples: u64,
+    /// Intervals longer than twice the configured rate since startup.
+    pub overruns: u64,
+    /// True when the average interval is more than twice the configured one.
+    pub rate_degraded: bool,
+}
+
+/// Live state of one monitored controller.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MonitoredControllerState {
+    pub controller_id: String,
+    pub name: String,
+    pub profile_name: String,
+    /// The active controller also drives the top-level fields of `MonitorSharedState`.
+    pub is_active: bool,
+    pub is_connected: bool,
+    pub raw_button_state: u32,
+    pub current_pressed_keys: HashSet<LogicalKey>,
+    /// Shared with the monitor until the switches change, so publishing does not copy them.
+    pub switches: Arc<HashMap<LogicalKey, SwitchData>>,
+}
+
 /// Snapshot of the monitor state for UI consumption.
 #[derive(Debug, Clone, Default, Serialize)]
 pub struct MonitorSharedState {
     pub is_connected: bool,
     pub is_game_running: bool,
+    /// `GameDefinition::id` of the detected game.
+    pub running_game_id: Option<String>,
+    /// The running session, which may be manual and not tied to a game.
+    pub current_session: Option<ActiveSession>,
     pub config: AppConfig,
 
     pub profile_name: String,
     // Use Arc to avoid cloning the map every update
     pub bindings: Arc<HashMap<LogicalKey, u32>>,
+    /// How the buttons in `bindings` are numbered.
+    pub button_layout: ButtonLayout,
     pub switches: HashMap<LogicalKey, SwitchData>,
     pub switch_history: Arc<Vec<SwitchHistoryEntry>>,
 
@@ -35,9 +83,24 @@ pub struct MonitorSharedState {
     pub last_save_result: Option<LastSaveResult>,
 
     pub recent_sessions: Vec<SessionRecord>,
+    /// All recorded sessions of the active controller without timelines, for calendar rollups.
+    /// Too large for every state update; the UI reads it through the play stats commands.
+    #[serde(skip)]
+    pub session_history: Arc<Vec<SessionRecord>>,
+    #[serde(skip)]
+    pub counter_snapshots: Arc<Vec<CounterSnapshot>>,
+    /// Per-game totals of the active controller.
+    pub game_stats: BTreeMap<String, GameStats>,
+    /// Active and recently resolved alerts of all controllers, oldest first.
+    pub alerts: Vec<Alert>,
 
     pub active_controller_id: String,
     pub connected_controllers: Vec<ControllerInfo>,
+    /// Every connected controller with a profile, active one first.
+    pub monitored_controllers: Vec<MonitoredControllerState>,
+
+    pub counters: MonitorCounters,
+    pub poll_timing: PollTimingStats,
 }
 
 /// Publisher to handle updating the shared state for the UI
@@ -54,25 +117,38 @@ impl StatePublisher {
         &self,
         is_connected: bool,
         is_game_running: bool,
+        running_game_id: Option<String>,
+        current_session: Option<ActiveSession>,
         config: AppConfig,
         profile_name: String,
         bindings: Arc<HashMap<LogicalKey, u32>>,
+        button_layout: ButtonLayout,
         switches: HashMap<LogicalKey, SwitchData>,
         switch_history: Arc<Vec<SwitchHistoryEntry>>,
         pressed_keys: HashSet<LogicalKey>,
         raw_buttons: u32,
         recent_sessions: Vec<SessionRecord>,
+        session_history: Arc<Vec<SessionRecord>>,
+        counter_snapshots: Arc<Vec<CounterSnapshot>>,
+        game_stats: BTreeMap<String, GameStats>,
+        alerts: Vec<Alert>,
         active_controller_id: String,
         connected_controllers: Vec<ControllerInfo>,
+        monitored_controllers: Vec<MonitoredControllerState>,
+        polls_total: u64,
+        poll_timing: PollTimingStats,
     ) {
         let old_state = self.shared_state.load();
 
         let new_state = MonitorSharedState {
             is_connected,
             is_game_running,
+            running_game_id,
+            current_session,
             config,
             profile_name,
             bindings,
+            button_layout,
             switches,
             switch_history,
             current_pressed_keys: pressed_keys,
@@ -80,8 +156,18 @@ impl StatePublisher {
             last_status_message: old_state.last_status_message.clone(),
             last_save_result: old_state.last_save_result.clone(),
             recent_sessions,
+            session_history,
+            counter_snapshots,
+            game_stats,
+            alerts,
             active_controller_id,
             connected_controllers,
+            monitored_controllers,
+            counters: MonitorCounters {
+                polls_total,
+                ..old_state.counters.clone()
+            },
+            poll_timing,
         };
 
         self.shared_state.store(Arc::new(new_state));
@@ -97,6 +183,11 @@ impl StatePublisher {
     pub fn update_save_result(&self, success: bool, message: String) {
         let old_state = self.shared_state.load();
         let mut new_state = (**old_state).clone();
+        if success {
+            new_state.counters.saves_succeeded += 1;
+        } else {
+            new_state.counters.saves_failed += 1;
+        }
         new_state.last_save_result = Some(LastSaveResult {
             success,
             message,
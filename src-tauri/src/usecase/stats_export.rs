@@ -0,0 +1,535 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::domain::models::{
+    ControllerProfile, GameDefinition, LogicalKey, SessionKeyStats, SessionOrigin, SessionRecord,
+    SwitchModelInfo,
+};
+
+/// Which data set to export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExportKind {
+    Stats,
+    History,
+    Sessions,
+    /// Per-game totals from `ControllerProfile::game_stats`.
+    Games,
+    /// Per-minute session timelines, one row per session, bucket and key.
+    Timeline,
+}
+
+/// Output format of an export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+/// Current per-key statistics with lifespan information.
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyStatsRow {
+    pub key: String,
+    pub switch_model_id: String,
+    pub switch_model_name: Option<String>,
+    pub rated_lifespan_presses: Option<u64>,
+    pub lifespan_used_percent: Option<f64>,
+    pub total_presses: u64,
+    pub total_releases: u64,
+    pub total_chatters: u64,
+    pub total_chatter_releases: u64,
+    pub last_replaced_at: Option<DateTime<Utc>>,
+}
+
+/// One `switch_history` entry, flattened.
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryRow {
+    pub date: DateTime<Utc>,
+    pub key: String,
+    pub event_type: String,
+    pub old_model_id: String,
+    pub new_model_id: String,
+    pub previous_total_presses: u64,
+    pub previous_total_releases: u64,
+    pub previous_total_chatters: u64,
+    pub previous_total_chatter_releases: u64,
+}
+
+/// A recorded session with its per-key breakdown.
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionExport {
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub duration_secs: u64,
+    pub game_id: Option<String>,
+    pub started_by: SessionOrigin,
+    pub merged: bool,
+    pub keys: Vec<SessionKeyRow>,
+    pub timeline: Vec<TimelineRow>,
+}
+
+/// One key's counts in one bucket of a session timeline. Buckets without input are left out.
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelineRow {
+    pub session_start: DateTime<Utc>,
+    /// Start of the bucket, in seconds from the session start.
+    pub offset_secs: u64,
+    pub bucket_secs: u64,
+    pub key: String,
+    pub presses: u64,
+    pub chatters: u64,
+}
+
+/// Totals of one game with its per-key breakdown.
+#[derive(Debug, Clone, Serialize)]
+pub struct GameExport {
+    pub game_id: String,
+    /// From the game definitions; `None` for games no longer configured.
+    pub display_name: Option<String>,
+    pub sessions: u64,
+    pub play_time_secs: u64,
+    pub keys: Vec<SessionKeyRow>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionKeyRow {
+    pub key: String,
+    pub presses: u64,
+    pub chatters: u64,
+}
+
+/// Builds deterministic CSV/JSON exports of a controller profile.
+/// Rows are always sorted (keys in `LogicalKey` order, entries by time) so exports diff cleanly.
+pub struct StatsExporter;
+
+impl StatsExporter {
+    pub fn render(
+        profile: &ControllerProfile,
+        models: &[SwitchModelInfo],
+        games: &[GameDefinition],
+        kind: ExportKind,
+        format: ExportFormat,
+    ) -> serde_json::Result<String> {
+        match (kind, format) {
+            (ExportKind::Stats, ExportFormat::Json) => {
+                serde_json::to_string_pretty(&Self::stats_rows(profile, models))
+            }
+            (ExportKind::History, ExportFormat::Json) => {
+                serde_json::to_string_pretty(&Self::history_rows(profile))
+            }
+            (ExportKind::Sessions, ExportFormat::Json) => {
+                serde_json::to_string_pretty(&Self::sessions(profile))
+            }
+            (ExportKind::Games, ExportFormat::Json) => {
+                serde_json::to_string_pretty(&Self::games(profile, games))
+            }
+            (ExportKind::Timeline, ExportFormat::Json) => {
+                serde_json::to_string_pretty(&Self::timeline_rows(profile))
+            }
+            (ExportKind::Stats, ExportFormat::Csv) => Ok(Self::stats_csv(profile, models)),
+            (ExportKind::History, ExportFormat::Csv) => Ok(Self::history_csv(profile)),
+            (ExportKind::Sessions, ExportFormat::Csv) => Ok(Self::sessions_csv(profile)),
+            (ExportKind::Games, ExportFormat::Csv) => Ok(Self::games_csv(profile, games)),
+            (ExportKind::Timeline, ExportFormat::Csv) => Ok(Self::timeline_csv(profile)),
+        }
+    }
+
+    pub fn stats_rows(profile: &ControllerProfile, models: &[SwitchModelInfo]) -> Vec<KeyStatsRow> {
+        let mut keys: Vec<&LogicalKey> = profile.switches.keys().collect();
+        keys.sort();
+
+        keys.into_iter()
+            .map(|key| {
+                let switch = &profile.switches[key];
+                let model = models.iter().find(|m| m.id == switch.switch_model_id);
+                let rated = model.map(|m| m.rated_lifespan_presses);
+                KeyStatsRow {
+                    key: key.to_string(),
+                    switch_model_id: switch.switch_model_id.clone(),
+                    switch_model_name: model.map(|m| format!("{} {}", m.manufacturer, m.name)),
+                    rated_lifespan_presses: rated,
+                    lifespan_used_percent: rated
+                        .filter(|&r| r > 0)
+                        .map(|r| switch.stats.total_presses as f64 / r as f64 * 100.0),
+                    total_presses: switch.stats.total_presses,
+                    total_releases: switch.stats.total_releases,
+                    total_chatters: switch.stats.total_chatters,
+                    total_chatter_releases: switch.stats.total_chatter_releases,
+                    last_replaced_at: switch.last_replaced_at,
+                }
+            })
+            .collect()
+    }
+
+    pub fn history_rows(profile: &ControllerProfile) -> Vec<HistoryRow> {
+        let mut rows: Vec<HistoryRow> = profile
+            .switch_history
+            .iter()
+            .map(|entry| HistoryRow {
+                date: entry.date,
+                key: entry.key.to_string(),
+                event_type: entry.event_type.clone(),
+                old_model_id: entry.old_model_id.clone(),
+                new_model_id: entry.new_model_id.clone(),
+                previous_total_presses: entry.previous_stats.total_presses,
+                previous_total_releases: entry.previous_stats.total_releases,
+                previous_total_chatters: entry.previous_stats.total_chatters,
+                previous_total_chatter_releases: entry.previous_stats.total_chatter_releases,
+            })
+            .collect();
+        // Stable sort keeps insertion order for entries with the same timestamp
+        rows.sort_by_key(|r| r.date);
+        rows
+    }
+
+    /// Every recorded session. Only the newest ones still have a timeline to export.
+    pub fn sessions(profile: &ControllerProfile) -> Vec<SessionExport> {
+        let mut sessions: Vec<SessionExport> = profile
+            .session_history
+            .iter()
+            .map(|record| SessionExport {
+                start_time: record.start_time,
+                end_time: record.end_time,
+                duration_secs: record.duration_secs,
+                game_id: record.game_id.clone(),
+                started_by: record.started_by,
+                merged: record.merged,
+                keys: key_rows(&record.stats),
+                timeline: timeline_rows(record),
+            })
+            .collect();
+        sessions.sort_by_key(|s| s.start_time);
+        sessions
+    }
+
+    /// Timeline rows of all sessions, in session and bucket order.
+    pub fn timeline_rows(profile: &ControllerProfile) -> Vec<TimelineRow> {
+        Self::sessions(profile).into_iter().flat_map(|s| s.timeline).collect()
+    }
+
+    /// Game totals in game id order.
+    pub fn games(profile: &ControllerProfile, games: &[GameDefinition]) -> Vec<GameExport> {
+        profile
+            .game_stats
+            .iter()
+            .map(|(game_id, stats)| GameExport {
+                game_id: game_id.clone(),
+                display_name: games.iter().find(|g| g.id == *game_id).map(|g| g.display_name.clone()),
+                sessions: stats.sessions,
+                play_time_secs: stats.play_time_secs,
+                keys: key_rows(&stats.keys),
+            })
+            .collect()
+    }
+
+    fn stats_csv(profile: &ControllerProfile, models: &[SwitchModelInfo]) -> String {
+        let mut out = String::from(
+            "key,switch_model_id,switch_model_name,rated_lifespan_presses,lifespan_used_percent,\
+total_presses,total_releases,total_chatters,total_chatter_releases,last_replaced_at\n",
+        );
+        for row in Self::stats_rows(profile, models) {
+            push_csv_row(
+                &mut out,
+                &[
+                    row.key,
+                    row.switch_model_id,
+                    row.switch_model_name.unwrap_or_default(),
+                    opt_to_string(row.rated_lifespan_presses),
+                    row.lifespan_used_percent.map(|p| format!("{:.4}", p)).unwrap_or_default(),
+                    row.total_presses.to_string(),
+                    row.total_releases.to_string(),
+                    row.total_chatters.to_string(),
+                    row.total_chatter_releases.to_string(),
+                    row.last_replaced_at.map(|d| d.to_rfc3339()).unwrap_or_default(),
+                ],
+            );
+        }
+        out
+    }
+
+    fn history_csv(profile: &ControllerProfile) -> String {
+        let mut out = String::from(
+            "date,key,event_type,old_model_id,new_model_id,previous_total_presses,\
+previous_total_releases,previous_total_chatters,previous_total_chatter_releases\n",
+        );
+        for row in Self::history_rows(profile) {
+            push_csv_row(
+                &mut out,
+                &[
+                    row.date.to_rfc3339(),
+                    row.key,
+                    row.event_type,
+                    row.old_model_id,
+                    row.new_model_id,
+                    row.previous_total_presses.to_string(),
+                    row.previous_total_releases.to_string(),
+                    row.previous_total_chatters.to_string(),
+                    row.previous_total_chatter_releases.to_string(),
+                ],
+            );
+        }
+        out
+    }
+
+    /// One row per session and key. Sessions without any input get a single row with an empty key.
+    fn sessions_csv(profile: &ControllerProfile) -> String {
+        let mut out = String::from("start_time,end_time,duration_secs,game_id,started_by,merged,key,presses,chatters\n");
+        for session in Self::sessions(profile) {
+            let session_fields = [
+                session.start_time.to_rfc3339(),
+                session.end_time.to_rfc3339(),
+                session.duration_secs.to_string(),
+                session.game_id.unwrap_or_default(),
+                format!("{:?}", session.started_by),
+                session.merged.to_string(),
+            ];
+            if session.keys.is_empty() {
+                let mut fields = session_fields.to_vec();
+                fields.extend([String::new(), String::new(), String::new()]);
+                push_csv_row(&mut out, &fields);
+            }
+            for key in session.keys {
+                let mut fields = session_fields.to_vec();
+                fields.extend([key.key, key.presses.to_string(), key.chatters.to_string()]);
+                push_csv_row(&mut out, &fields);
+            }
+        }
+        out
+    }
+
+    fn timeline_csv(profile: &ControllerProfile) -> String {
+        let mut out = String::from("session_start,offset_secs,bucket_secs,key,presses,chatters\n");
+        for row in Self::timeline_rows(profile) {
+            push_csv_row(
+                &mut out,
+                &[
+                    row.session_start.to_rfc3339(),
+                    row.offset_secs.to_string(),
+                    row.bucket_secs.to_string(),
+                    row.key,
+                    row.presses.to_string(),
+                    row.chatters.to_string(),
+                ],
+            );
+        }
+        out
+    }
+
+    /// One row per game and key, same layout rules as `sessions_csv`.
+    fn games_csv(profile: &ControllerProfile, games: &[GameDefinition]) -> String {
+        let mut out = String::from("game_id,display_name,sessions,play_time_secs,key,presses,chatters\n");
+        for game in Self::games(profile, games) {
+            let game_fields = [
+                game.game_id,
+                game.display_name.unwrap_or_default(),
+                game.sessions.to_string(),
+                game.play_time_secs.to_string(),
+            ];
+            if game.keys.is_empty() {
+                let mut fields = game_fields.to_vec();
+                fields.extend([String::new(), String::new(), String::new()]);
+                push_csv_row(&mut out, &fields);
+            }
+            for key in game.keys {
+                let mut fields = game_fields.to_vec();
+                fields.extend([key.key, key.presses.to_string(), key.chatters.to_string()]);
+                push_csv_row(&mut out, &fields);
+            }
+        }
+        out
+    }
+}
+
+fn key_rows(stats: &HashMap<LogicalKey, SessionKeyStats>) -> Vec<SessionKeyRow> {
+    let mut keys: Vec<(&LogicalKey, &SessionKeyStats)> = stats.iter().collect();
+    keys.sort_by(|a, b| a.0.cmp(b.0));
+    keys.into_iter()
+        .map(|(key, stats)| SessionKeyRow {
+            key: key.to_string(),
+            presses: stats.presses,
+            chatters: stats.chatters,
+        })
+        .collect()
+}
+
+fn timeline_rows(record: &SessionRecord) -> Vec<TimelineRow> {
+    let timeline = &record.timeline;
+    timeline
+        .buckets
+        .iter()
+        .enumerate()
+        .flat_map(|(index, bucket)| {
+            key_rows(bucket).into_iter().map(move |key| TimelineRow {
+                session_start: record.start_time,
+                offset_secs: index as u64 * timeline.bucket_secs,
+                bucket_secs: timeline.bucket_secs,
+                key: key.key,
+                presses: key.presses,
+                chatters: key.chatters,
+            })
+        })
+        .collect()
+}
+
+fn opt_to_string<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+pub(crate) fn push_csv_row(out: &mut String, fields: &[String]) {
+    let escaped: Vec<String> = fields.iter().map(|f| csv_escape(f)).collect();
+    out.push_str(&escaped.join(","));
+    out.push('\n');
+}
+
+/// Quotes a CSV field if it contains a separator, quote or newline.
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use crate::domain::models::{
+        default_game_definitions, get_default_switch_models, ButtonStats, GameStats, SessionOrigin,
+        SessionRecord, SwitchData, MAX_RECENT_SESSIONS,
+    };
+
+    fn sample_profile() -> ControllerProfile {
+        let mut profile = ControllerProfile::default();
+        for (key, presses) in [(LogicalKey::E1, 10), (LogicalKey::Key2, 2_000_000), (LogicalKey::Key1, 500)] {
+            profile.switches.insert(key, SwitchData {
+                switch_model_id: "omron_d2mv_01_1c3".to_string(),
+                stats: ButtonStats {
+                    total_presses: presses,
+                    ..Default::default()
+                },
+                last_replaced_at: None,
+            });
+        }
+
+        let start = Utc.with_ymd_and_hms(2025, 4, 1, 20, 0, 0).unwrap();
+        let mut stats = HashMap::new();
+        stats.insert(LogicalKey::Key3, SessionKeyStats { presses: 30, chatters: 1 });
+        stats.insert(LogicalKey::Key1, SessionKeyStats { presses: 12, chatters: 0 });
+        profile.session_history.push(SessionRecord {
+            start_time: start,
+            end_time: start + chrono::Duration::minutes(45),
+            duration_secs: 2700,
+            stats,
+            game_id: None,
+            started_by: SessionOrigin::Process,
+            merged: false,
+            timeline: Default::default(),
+        });
+        profile
+    }
+
+    #[test]
+    fn test_stats_csv_sorted_with_lifespan() {
+        let csv = StatsExporter::render(
+            &sample_profile(),
+            &get_default_switch_models(),
+            &[],
+            ExportKind::Stats,
+            ExportFormat::Csv,
+        )
+        .unwrap();
+
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].starts_with("Key1,"));
+        assert!(lines[2].starts_with("Key2,omron_d2mv_01_1c3,Omron D2MV-01-1C3 (50g),10000000,20.0000,2000000,"));
+        assert!(lines[3].starts_with("E1,"));
+    }
+
+    #[test]
+    fn test_sessions_csv_one_row_per_key() {
+        let csv = StatsExporter::render(
+            &sample_profile(),
+            &[],
+            &[],
+            ExportKind::Sessions,
+            ExportFormat::Csv,
+        )
+        .unwrap();
+
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].ends_with(",2700,,Process,false,Key1,12,0"));
+        assert!(lines[2].ends_with(",2700,,Process,false,Key3,30,1"));
+    }
+
+    #[test]
+    fn test_sessions_cover_the_whole_history() {
+        let mut profile = sample_profile();
+        let first = profile.session_history[0].clone();
+        for day in 1..=MAX_RECENT_SESSIONS as i64 {
+            profile.session_history.push(SessionRecord {
+                start_time: first.start_time + chrono::Duration::days(day),
+                ..first.clone()
+            });
+        }
+
+        let sessions = StatsExporter::sessions(&profile);
+        assert_eq!(sessions.len(), MAX_RECENT_SESSIONS + 1);
+        assert_eq!(sessions[0].start_time, first.start_time);
+    }
+
+    #[test]
+    fn test_games_csv_names_known_games() {
+        let mut profile = sample_profile();
+        let mut lr2 = GameStats { sessions: 2, play_time_secs: 600, ..Default::default() };
+        lr2.keys.insert(LogicalKey::Key2, SessionKeyStats { presses: 40, chatters: 2 });
+        profile.game_stats.insert("lr2".to_string(), lr2);
+        profile.game_stats.insert("removed".to_string(), GameStats::default());
+
+        let csv = StatsExporter::render(
+            &profile,
+            &[],
+            &default_game_definitions(),
+            ExportKind::Games,
+            ExportFormat::Csv,
+        )
+        .unwrap();
+
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], "lr2,Lunatic Rave 2,2,600,Key2,40,2");
+        assert_eq!(lines[2], "removed,,0,0,,,");
+    }
+
+    #[test]
+    fn test_timeline_csv_skips_empty_buckets() {
+        let mut profile = sample_profile();
+        let timeline = &mut profile.session_history[0].timeline;
+        timeline.record(&LogicalKey::Key3, &SessionKeyStats { presses: 20, chatters: 0 }, 30);
+        timeline.record(&LogicalKey::Key3, &SessionKeyStats { presses: 30, chatters: 1 }, 130);
+
+        let csv = StatsExporter::render(&profile, &[], &[], ExportKind::Timeline, ExportFormat::Csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], "2025-04-01T20:00:00+00:00,0,60,Key3,20,0");
+        assert_eq!(lines[2], "2025-04-01T20:00:00+00:00,120,60,Key3,10,1");
+    }
+
+    #[test]
+    fn test_json_export_is_deterministic() {
+        let profile = sample_profile();
+        let first = StatsExporter::render(&profile, &[], &[], ExportKind::Sessions, ExportFormat::Json).unwrap();
+        let second = StatsExporter::render(&profile.clone(), &[], &[], ExportKind::Sessions, ExportFormat::Json).unwrap();
+        assert_eq!(first, second);
+        assert!(first.find("\"Key1\"").unwrap() < first.find("\"Key3\"").unwrap());
+    }
+
+    #[test]
+    fn test_csv_escape() {
+        assert_eq!(csv_escape("plain"), "plain");
+        assert_eq!(csv_escape("a,b"), "\"a,b\"");
+        assert_eq!(csv_escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
@@ -1,6 +1,7 @@
 //! Shared test utilities for MonitorService tests.
 //! Provides common mock implementations and factory helpers to reduce boilerplate.
 
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use crossbeam_channel::{bounded, Sender, Receiver};
 use arc_swap::ArcSwap;
@@ -8,7 +9,7 @@ use anyhow::Result;
 
 use crate::domain::errors::InputError;
 use crate::domain::interfaces::InputSource;
-use crate::domain::models::UserProfile;
+use crate::domain::models::{ControllerInfo, UserProfile};
 use crate::infrastructure::persistence::ConfigRepository;
 use crate::infrastructure::process_monitor::ProcessMonitor;
 use crate::usecase::monitor::{MonitorCommand, MonitorService};
@@ -21,10 +22,14 @@ use crate::usecase::state_publisher::{MonitorSharedState, StatePublisher};
 /// Configurable mock input source.
 /// - `state_val`: the raw button bitmap to return from `get_state()`
 /// - `is_disconnected`: if true, returns `InputError::Disconnected`
+/// - `controllers`: returned from `enumerate_controllers()`
+/// - `index_states`: per-index bitmaps overriding `state_val`
 #[derive(Clone)]
 pub struct MockInputSource {
     pub state_val: Arc<Mutex<u32>>,
     pub is_disconnected: Arc<Mutex<bool>>,
+    pub controllers: Vec<ControllerInfo>,
+    pub index_states: HashMap<u32, u32>,
 }
 
 impl MockInputSource {
@@ -33,6 +38,8 @@ impl MockInputSource {
         Self {
             state_val: Arc::new(Mutex::new(0)),
             is_disconnected: Arc::new(Mutex::new(false)),
+            controllers: Vec::new(),
+            index_states: HashMap::new(),
         }
     }
 
@@ -43,21 +50,29 @@ impl MockInputSource {
         let source = Self {
             state_val: state_val.clone(),
             is_disconnected: is_disconnected.clone(),
+            controllers: Vec::new(),
+            index_states: HashMap::new(),
         };
         (source, state_val, is_disconnected)
     }
 }
 
 impl InputSource for MockInputSource {
-    fn get_state(&mut self, _controller_index: u32) -> Result<u32, InputError> {
+    fn get_state(&mut self, controller_index: u32) -> Result<u32, InputError> {
         let is_disc = *self.is_disconnected.lock().unwrap();
         if is_disc {
             Err(InputError::Disconnected)
+        } else if let Some(&val) = self.index_states.get(&controller_index) {
+            Ok(val)
         } else {
             let val = *self.state_val.lock().unwrap();
             Ok(val)
         }
     }
+
+    fn enumerate_controllers(&mut self) -> Result<Vec<ControllerInfo>, InputError> {
+        Ok(self.controllers.clone())
+    }
 }
 
 /// Mock process monitor with controllable `is_running` state.
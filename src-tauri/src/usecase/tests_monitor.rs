@@ -115,7 +115,7 @@ pub mod tests {
         new_config.target_controller_index = 2;
         new_config.target_process_name = "test_game.exe".to_string();
 
-        service.handle_command(MonitorCommand::UpdateConfig(new_config.clone()));
+        service.handle_command(MonitorCommand::UpdateConfig(Box::new(new_config.clone())));
 
         // Verify internal state updated
         assert_eq!(service.profile.config.target_controller_index, 2);
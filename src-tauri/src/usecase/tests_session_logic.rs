@@ -1,5 +1,9 @@
-use crate::domain::models::{ButtonStats, LogicalKey};
+use crate::domain::models::{
+    ActiveSession, ButtonStats, ControllerProfile, LogicalKey, SessionKeyStats, SessionOrigin, SwitchData,
+    MAX_RECENT_SESSIONS,
+};
 use crate::usecase::input_monitor::ChatterDetector;
+use crate::usecase::session_manager::SessionManager;
 
 #[test]
 fn test_input_outside_session_updates_total_only() {
@@ -64,3 +68,112 @@ fn test_chatter_inside_and_outside_session() {
     assert_eq!(stats.total_chatters, 2);
     assert_eq!(stats.last_session_chatters, 1);
 }
+
+#[test]
+fn test_session_end_adds_to_game_totals() {
+    let mut profile = ControllerProfile::default();
+    profile.switches.insert(
+        LogicalKey::Key1,
+        SwitchData {
+            switch_model_id: "omron_d2mv_01_1c3".to_string(),
+            stats: ButtonStats::default(),
+            last_replaced_at: None,
+        },
+    );
+    let mut detector = ChatterDetector::new(15);
+    let start = chrono::Utc::now();
+
+    for (i, game_id) in [Some("lr2"), Some("lr2"), None].into_iter().enumerate() {
+        let t = i as u64 * 1000;
+        SessionManager::start_session(&mut profile);
+        let stats = &mut profile.switches.get_mut(&LogicalKey::Key1).unwrap().stats;
+        detector.process_button(&LogicalKey::Key1, true, t + 100, stats, true);
+        detector.process_button(&LogicalKey::Key1, false, t + 200, stats, true);
+        let session = ActiveSession::new(start, game_id.map(str::to_string), SessionOrigin::Process);
+        SessionManager::end_session(&mut profile, &session, start + chrono::Duration::seconds(90));
+    }
+
+    let lr2 = &profile.game_stats["lr2"];
+    assert_eq!(lr2.sessions, 2);
+    assert_eq!(lr2.play_time_secs, 180);
+    assert_eq!(lr2.keys[&LogicalKey::Key1].presses, 2);
+    // Untagged sessions are only in the history
+    assert_eq!(profile.game_stats.len(), 1);
+    assert_eq!(profile.recent_sessions()[1].game_id.as_deref(), Some("lr2"));
+    assert_eq!(profile.recent_sessions()[2].game_id, None);
+}
+
+#[test]
+fn test_session_end_keeps_timeline_in_record() {
+    let mut profile = ControllerProfile::default();
+    profile.switches.insert(
+        LogicalKey::Key1,
+        SwitchData {
+            switch_model_id: "omron_d2mv_01_1c3".to_string(),
+            stats: ButtonStats::default(),
+            last_replaced_at: None,
+        },
+    );
+    let start = chrono::Utc::now();
+    SessionManager::start_session(&mut profile);
+
+    let press = |profile: &mut ControllerProfile, count: u64| {
+        profile.switches.get_mut(&LogicalKey::Key1).unwrap().stats.last_session_presses += count;
+    };
+    press(&mut profile, 3);
+    SessionManager::record_timeline(&mut profile, 30);
+    press(&mut profile, 2);
+    SessionManager::record_timeline(&mut profile, 70);
+    // Counted after the last sample, lands in the last minute
+    press(&mut profile, 4);
+
+    let session = ActiveSession::new(start, None, SessionOrigin::Manual);
+    SessionManager::end_session(&mut profile, &session, start + chrono::Duration::seconds(180));
+
+    let timeline = &profile.recent_sessions()[0].timeline;
+    let presses: Vec<u64> = timeline
+        .buckets
+        .iter()
+        .map(|b| b.get(&LogicalKey::Key1).map_or(0, |s| s.presses))
+        .collect();
+    assert_eq!(presses, vec![3, 2, 4]);
+    assert!(timeline.counted.is_empty());
+    assert!(profile.session_timeline.buckets.is_empty());
+}
+
+#[test]
+fn test_paused_time_is_not_part_of_the_duration() {
+    let mut profile = ControllerProfile::default();
+    let start = chrono::Utc::now();
+    let at = |secs| start + chrono::Duration::seconds(secs);
+
+    let mut session = ActiveSession::new(start, Some("lr2".to_string()), SessionOrigin::Manual);
+    assert!(session.set_paused(true, at(60)));
+    assert!(!session.set_paused(true, at(90)));
+    assert!(session.set_paused(false, at(360)));
+    assert_eq!(session.paused_secs, 300);
+    // A pause still running at the end does not count either
+    session.set_paused(true, at(400));
+    assert_eq!(session.duration_secs(at(500)), 100);
+
+    assert_eq!(SessionManager::end_session(&mut profile, &session, at(500)), 100);
+    assert_eq!(profile.recent_sessions()[0].duration_secs, 100);
+    assert_eq!(profile.game_stats["lr2"].play_time_secs, 100);
+}
+
+#[test]
+fn test_only_recent_sessions_keep_their_timeline() {
+    let mut profile = ControllerProfile::default();
+    let start = chrono::Utc::now();
+
+    for i in 0..=MAX_RECENT_SESSIONS as i64 {
+        let session = ActiveSession::new(start + chrono::Duration::hours(i), None, SessionOrigin::Manual);
+        profile.session_timeline.record(&LogicalKey::Key1, &SessionKeyStats { presses: 1, chatters: 0 }, 0);
+        SessionManager::end_session(&mut profile, &session, session.start_time + chrono::Duration::seconds(60));
+    }
+
+    assert_eq!(profile.session_history.len(), MAX_RECENT_SESSIONS + 1);
+    assert!(profile.session_history[0].timeline.buckets.is_empty());
+    assert_eq!(profile.recent_sessions().len(), MAX_RECENT_SESSIONS);
+    assert!(profile.recent_sessions().iter().all(|s| !s.timeline.buckets.is_empty()));
+}
@@ -18,6 +18,9 @@ function App() {
     return <Text p="md">Connecting to backend...</Text>;
   }
 
+  const gameName = state.config.games.find(g => g.id === state.running_game_id)?.display_name
+    ?? state.running_game_id;
+
   return (
     <AppShell
       header={{ height: 60 }}
@@ -32,7 +35,11 @@ function App() {
             <Badge color={state.is_connected ? "green" : "red"}>
               {state.is_connected ? "Connected" : "Disconnected"}
             </Badge>
-            {state.is_game_running && <Badge color="blue">In Game</Badge>}
+            {state.is_game_running && (
+              <Badge color="blue">
+                In Game{gameName ? `: ${gameName}` : ""}
+              </Badge>
+            )}
           </Group>
         </Group>
       </AppShell.Header>
@@ -1,3 +1,5 @@
+import { AppConfig } from './types';
+
 export interface SwitchModelInfo {
     id: string;
     name: string;
@@ -32,6 +34,12 @@ export const SWITCH_MODELS: SwitchModelInfo[] = [
     },
 ];
 
+/** Chatter rate (%) of the first ChatterRate alert rule, above which keys are flagged. */
+export function chatterAlertPercent(config: AppConfig): number | null {
+    const rule = config.alert_rules.find(r => r.type === "ChatterRate");
+    return rule?.type === "ChatterRate" ? rule.percent : null;
+}
+
 export const ORDERED_KEYS = [
     "Key1", "Key2", "Key3", "Key4", "Key5", "Key6", "Key7",
     "E1", "E2", "E3", "E4"
@@ -4,6 +4,7 @@ import { ORDERED_KEYS } from '../../constants';
 import { useDashboard } from './useDashboard';
 import { SwitchCard } from './components/SwitchCard';
 import { SessionInfoPanel } from './components/SessionInfoPanel';
+import { AlertPanel } from './components/AlertPanel';
 import { BulkActionPanel } from './components/BulkActionPanel';
 import { DateEditModal } from './components/DateEditModal';
 
@@ -62,6 +63,8 @@ export function Dashboard({ state }: DashboardProps) {
                 onResetStats={handleBulkReset}
             />
 
+            <AlertPanel alerts={state.alerts ?? []} />
+
             <SessionInfoPanel state={state} />
 
             <Grid>
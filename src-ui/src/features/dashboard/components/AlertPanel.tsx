@@ -0,0 +1,37 @@
+import { Paper, Group, Title, Stack, Text, Button, Badge } from '@mantine/core';
+import { invoke } from '@tauri-apps/api/core';
+import { Alert } from '../../../types';
+
+interface AlertPanelProps {
+    alerts: Alert[];
+}
+
+export function AlertPanel({ alerts }: AlertPanelProps) {
+    const active = alerts.filter((alert) => alert.resolved_at === null).reverse();
+    if (active.length === 0) {
+        return null;
+    }
+
+    return (
+        <Paper shadow="xs" p="md" mb="lg" withBorder>
+            <Title order={5} mb="xs">Alerts</Title>
+            <Stack gap="xs">
+                {active.map((alert) => (
+                    <Group key={alert.id} justify="space-between">
+                        <Group gap="xs">
+                            <Badge color={alert.acknowledged_at ? "gray" : "red"} variant="light">{alert.kind}</Badge>
+                            <Text size="sm">{alert.message}</Text>
+                            <Text size="xs" c="dimmed">{new Date(alert.raised_at).toLocaleString()}</Text>
+                        </Group>
+                        {!alert.acknowledged_at && (
+                            <Button size="compact-xs" variant="light"
+                                onClick={() => invoke('acknowledge_alert', { id: alert.id })}>
+                                Acknowledge
+                            </Button>
+                        )}
+                    </Group>
+                ))}
+            </Stack>
+        </Paper>
+    );
+}
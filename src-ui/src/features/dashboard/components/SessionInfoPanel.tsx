@@ -1,4 +1,5 @@
-import { Grid, Paper, Group, Title, Badge, Stack, Text } from '@mantine/core';
+import { Grid, Paper, Group, Title, Badge, Stack, Text, Button } from '@mantine/core';
+import { invoke } from '@tauri-apps/api/core';
 import { MonitorSharedState } from '../../../types';
 import { ORDERED_KEYS } from '../../../constants';
 
@@ -7,13 +8,33 @@ interface SessionInfoPanelProps {
 }
 
 export function SessionInfoPanel({ state }: SessionInfoPanelProps) {
+    const session = state.current_session;
+
     return (
         <Grid mb="lg">
             <Grid.Col span={{ base: 12, md: 6 }}>
                 <Paper shadow="xs" p="md" withBorder h="100%">
                     <Group justify="space-between" mb="xs">
-                        <Title order={5}>Session Stats ({state.is_game_running ? "Live" : "Previous"})</Title>
-                        {state.is_game_running && <Badge color="green" variant="dot">Running</Badge>}
+                        <Title order={5}>Session Stats ({session ? "Live" : "Previous"})</Title>
+                        <Group gap="xs">
+                            {session && (
+                                <Badge color={session.paused ? "yellow" : "green"} variant="dot">
+                                    {session.paused ? "Paused" : `Running (${session.started_by})`}
+                                </Badge>
+                            )}
+                            {!session && (
+                                <Button size="compact-xs" variant="light" onClick={() => invoke('start_session')}>Start</Button>
+                            )}
+                            {session && (
+                                <Button size="compact-xs" variant="light" color="yellow"
+                                    onClick={() => invoke(session.paused ? 'resume_session' : 'pause_session')}>
+                                    {session.paused ? "Resume" : "Pause"}
+                                </Button>
+                            )}
+                            {session && (
+                                <Button size="compact-xs" variant="light" color="red" onClick={() => invoke('stop_session')}>Stop</Button>
+                            )}
+                        </Group>
                     </Group>
                     <Group grow>
                         <Stack gap={0}>
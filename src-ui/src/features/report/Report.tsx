@@ -1,6 +1,6 @@
 import { Container, Title, Table, Text, Badge, Card, Group } from '@mantine/core';
 import { MonitorSharedState, SwitchData } from '../../types';
-import { ORDERED_KEYS } from '../../constants';
+import { ORDERED_KEYS, chatterAlertPercent } from '../../constants';
 
 interface ReportProps {
     state: MonitorSharedState;
@@ -11,6 +11,8 @@ export function Report({ state }: ReportProps) {
     const totalSessionPresses = ORDERED_KEYS.reduce((acc, key) => acc + (state.switches[key]?.stats.last_session_presses || 0), 0);
     const totalSessionChatters = ORDERED_KEYS.reduce((acc, key) => acc + (state.switches[key]?.stats.last_session_chatters || 0), 0);
 
+    const alertPercent = chatterAlertPercent(state.config);
+
     const rows = ORDERED_KEYS.map(key => {
         const switchData = state.switches[key] || {
             stats: { last_session_presses: 0, last_session_chatters: 0 }
@@ -20,7 +22,7 @@ export function Report({ state }: ReportProps) {
         const chatters = switchData.stats.last_session_chatters;
 
         const rate = presses > 0 ? (chatters / presses) * 100 : 0;
-        const isHighChatter = rate > 1.0; // Highlight if > 1%
+        const isHighChatter = alertPercent !== null && rate > alertPercent;
 
         return (
             <Table.Tr key={key}>
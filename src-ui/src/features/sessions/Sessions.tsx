@@ -1,6 +1,7 @@
-import { Container, Title, Table, Text, Badge, Card, Group, Stack, Select } from '@mantine/core';
+import { Container, Title, Table, Text, Badge, Card, Group, Stack, Select, Button } from '@mantine/core';
+import { invoke } from '@tauri-apps/api/core';
 import { MonitorSharedState, SessionRecord } from '../../types';
-import { ORDERED_KEYS } from '../../constants';
+import { ORDERED_KEYS, chatterAlertPercent } from '../../constants';
 import { useState, useMemo } from 'react';
 
 interface SessionsProps {
@@ -22,14 +23,31 @@ export function Sessions({ state }: SessionsProps) {
         return state.recent_sessions.map((s, i) => ({
             value: i.toString(),
             label: `${new Date(s.start_time).toLocaleString()} (${s.duration_secs}s)`
+                + (s.game_id ? ` - ${gameName(s.game_id)}` : '')
+                + (s.merged ? ' (merged)' : '')
         })).reverse();
-    }, [state.recent_sessions]);
+    }, [state.recent_sessions, state.config.games]);
+
+    function gameName(gameId: string) {
+        return state.config.games.find(g => g.id === gameId)?.display_name ?? gameId;
+    }
+
+    const copyReport = async (session: SessionRecord) => {
+        const markdown = await invoke<string | null>('render_session_report', {
+            startTime: session.start_time,
+            format: 'Markdown',
+        });
+        if (markdown) {
+            await navigator.clipboard.writeText(markdown);
+        }
+    };
 
     const renderSessionStats = (session: SessionRecord) => {
         const stats = session.stats || {};
         
         let totalPresses = 0;
         let totalChatters = 0;
+        const alertPercent = chatterAlertPercent(state.config);
         
         const rows = ORDERED_KEYS.map(key => {
             const keyStats = stats[key] || { presses: 0, chatters: 0 };
@@ -40,7 +58,7 @@ export function Sessions({ state }: SessionsProps) {
             totalChatters += chatters;
             
             const rate = presses > 0 ? (chatters / presses) * 100 : 0;
-            const isHighChatter = rate > 1.0;
+            const isHighChatter = alertPercent !== null && rate > alertPercent;
 
             if (presses === 0 && chatters === 0) return null;
 
@@ -114,14 +132,23 @@ export function Sessions({ state }: SessionsProps) {
         <Container fluid>
             <Group justify="space-between" mb="md">
                 <Title order={4}>Past Sessions</Title>
-                <Select
-                    placeholder="Select a session"
-                    data={sessionOptions}
-                    value={selectedSessionIndex}
-                    onChange={setSelectedSessionIndex}
-                    allowDeselect={false}
-                    style={{ width: 300 }}
-                />
+                <Group>
+                    <Button
+                        variant="light"
+                        disabled={!selectedSession}
+                        onClick={() => selectedSession && copyReport(selectedSession)}
+                    >
+                        Copy Report
+                    </Button>
+                    <Select
+                        placeholder="Select a session"
+                        data={sessionOptions}
+                        value={selectedSessionIndex}
+                        onChange={setSelectedSessionIndex}
+                        allowDeselect={false}
+                        style={{ width: 300 }}
+                    />
+                </Group>
             </Group>
 
             {selectedSession ? (
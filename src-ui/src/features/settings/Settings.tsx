@@ -1,6 +1,6 @@
-import { Container, Grid, Card, Text, Select, Button, Stack, Title, Table, Group, NumberInput, Divider, Alert, Code } from '@mantine/core';
-import { MonitorSharedState, AppConfig } from '../../types';
-import { ORDERED_KEYS } from '../../constants';
+import { Container, Grid, Card, Text, Select, Button, Stack, Title, Table, Group, NumberInput, Divider, Alert, Code, MultiSelect, Switch } from '@mantine/core';
+import { MonitorSharedState, AppConfig, InputMethod, SleepAction, AlertKind, NotificationConfig, ButtonLayout } from '../../types';
+import { ORDERED_KEYS, chatterAlertPercent } from '../../constants';
 import { invoke } from '@tauri-apps/api/core';
 import { useState } from 'react';
 import { KeyBindingModal } from './KeyBindingModal';
@@ -17,12 +17,28 @@ export function Settings({ state }: SettingsProps) {
         invoke('update_config', { config: newConfig });
     };
 
+    const handleNotificationChange = <K extends keyof NotificationConfig>(key: K, value: NotificationConfig[K]) => {
+        handleConfigChange('notifications', { ...state.config.notifications, [key]: value });
+    };
+
+    const handleChatterAlertChange = (percent: number) => {
+        handleConfigChange('alert_rules', state.config.alert_rules.map(rule =>
+            rule.type === "ChatterRate" ? { ...rule, percent } : rule
+        ));
+    };
+
     const handleResetMapping = () => {
         if (confirm("Reset all key bindings to default (PhoenixWAN)?")) {
             invoke('reset_to_default_mapping');
         }
     };
 
+    const handleLayoutChange = (layout: ButtonLayout) => {
+        if (layout !== state.button_layout && confirm("Existing bindings use the current numbering. Switch and re-bind every key?")) {
+            invoke('set_button_layout', { layout });
+        }
+    };
+
     const handleBind = (key: string, button: number) => {
         invoke('set_binding', { key, button });
         setLearningKey(null);
@@ -41,15 +57,18 @@ export function Settings({ state }: SettingsProps) {
                                 description="Restart required if changed (usually)"
                                 data={[
                                     { value: 'DirectInput', label: 'DirectInput (HID) - Recommended' },
-                                    { value: 'XInput', label: 'XInput (Xbox)' }
+                                    { value: 'XInput', label: 'XInput (Xbox)' },
+                                    { value: 'Evdev', label: 'evdev (Linux native)' },
+                                    { value: 'Keyboard', label: 'Keyboard (Linux evdev)' },
+                                    { value: 'Midi', label: 'MIDI' }
                                 ]}
                                 value={state.config.input_method}
-                                onChange={(val) => val && handleConfigChange('input_method', val as "XInput" | "DirectInput")}
+                                onChange={(val) => val && handleConfigChange('input_method', val as InputMethod)}
                             />
 
                             <NumberInput
                                 label="Controller Index"
-                                description="Device ID (0 = First controller found)"
+                                description={state.config.target_controller_id ? "Overrides the controller selected on the Dashboard" : "Device ID (0 = First controller found)"}
                                 min={0}
                                 max={16}
                                 value={state.config.target_controller_index}
@@ -65,6 +84,56 @@ export function Settings({ state }: SettingsProps) {
                                 onChange={(val) => handleConfigChange('chatter_threshold_ms', Number(val))}
                             />
 
+                            <NumberInput
+                                label="Chatter Alert Level (%)"
+                                description="Chatter rate over a key's recent presses that raises an alert; also flags keys in session reports"
+                                min={0}
+                                max={100}
+                                step={0.1}
+                                decimalScale={2}
+                                disabled={chatterAlertPercent(state.config) === null}
+                                value={chatterAlertPercent(state.config) ?? ''}
+                                onChange={(val) => handleChatterAlertChange(Number(val))}
+                            />
+
+                            <NumberInput
+                                label="Counter Snapshot Interval (s)"
+                                description="Records key totals over time, also outside sessions. 0 disables"
+                                min={0}
+                                max={86400}
+                                step={600}
+                                value={state.config.snapshot_interval_secs}
+                                onChange={(val) => handleConfigChange('snapshot_interval_secs', Number(val))}
+                            />
+
+                            <Title order={5} mt="sm">Desktop Notifications</Title>
+                            <Switch
+                                label="Notify when an alert is raised"
+                                checked={state.config.notifications.enabled}
+                                onChange={(e) => handleNotificationChange('enabled', e.currentTarget.checked)}
+                            />
+                            <NumberInput
+                                label="Minimum Interval (s)"
+                                description="Per alert type; alerts raised in between are sent together afterwards"
+                                min={0}
+                                max={86400}
+                                step={60}
+                                value={state.config.notifications.min_interval_secs}
+                                onChange={(val) => handleNotificationChange('min_interval_secs', Number(val))}
+                            />
+                            <MultiSelect
+                                label="Muted Alert Types"
+                                data={[
+                                    { value: 'ChatterRate', label: 'Chatter rate' },
+                                    { value: 'Lifespan', label: 'Lifespan' },
+                                    { value: 'SessionChatters', label: 'Session chatters' },
+                                    { value: 'DisconnectedInSession', label: 'Disconnected in session' },
+                                    { value: 'SaveFailed', label: 'Save failed' },
+                                ]}
+                                value={state.config.notifications.muted_kinds}
+                                onChange={(val) => handleNotificationChange('muted_kinds', val as AlertKind[])}
+                            />
+
                             <Title order={5} mt="sm">Polling Rates (ms)</Title>
                             <Group grow>
                                 <NumberInput
@@ -82,6 +151,70 @@ export function Settings({ state }: SettingsProps) {
                                     onChange={(val) => handleConfigChange('polling_rate_ms_disconnected', Number(val))}
                                 />
                             </Group>
+
+                            <Title order={5} mt="sm">Sessions (s)</Title>
+                            <Group grow>
+                                <NumberInput
+                                    label="Minimum Length"
+                                    description="Shorter sessions are not recorded"
+                                    min={0}
+                                    max={3600}
+                                    value={state.config.min_session_secs}
+                                    onChange={(val) => handleConfigChange('min_session_secs', Number(val))}
+                                />
+                                <NumberInput
+                                    label="Restart Grace"
+                                    description="A restarted game continues the session"
+                                    min={0}
+                                    max={3600}
+                                    value={state.config.session_grace_secs}
+                                    onChange={(val) => handleConfigChange('session_grace_secs', Number(val))}
+                                />
+                            </Group>
+                            <Group grow>
+                                <NumberInput
+                                    label="Resume After Restart"
+                                    description="A session continues if the app restarts this soon"
+                                    min={0}
+                                    max={86400}
+                                    value={state.config.session_resume_secs}
+                                    onChange={(val) => handleConfigChange('session_resume_secs', Number(val))}
+                                />
+                                <Select
+                                    label="On Sleep"
+                                    description="What happens to a running session"
+                                    data={[
+                                        { value: 'End', label: 'End the session' },
+                                        { value: 'Pause', label: 'Pause the session while asleep' }
+                                    ]}
+                                    value={state.config.sleep_action}
+                                    onChange={(val) => val && handleConfigChange('sleep_action', val as SleepAction)}
+                                />
+                            </Group>
+
+                            <Title order={5} mt="sm">Session Hotkey</Title>
+                            <Group grow>
+                                <MultiSelect
+                                    label="Buttons"
+                                    description="Hold together to start or stop a session"
+                                    placeholder="Disabled"
+                                    data={ORDERED_KEYS}
+                                    value={state.config.session_hotkey?.keys ?? []}
+                                    onChange={(keys) => handleConfigChange('session_hotkey', keys.length > 0
+                                        ? { keys, hold_ms: state.config.session_hotkey?.hold_ms ?? 2000 }
+                                        : null)}
+                                />
+                                <NumberInput
+                                    label="Hold (ms)"
+                                    min={500}
+                                    max={10000}
+                                    step={500}
+                                    disabled={!state.config.session_hotkey}
+                                    value={state.config.session_hotkey?.hold_ms ?? 2000}
+                                    onChange={(val) => state.config.session_hotkey && handleConfigChange('session_hotkey',
+                                        { ...state.config.session_hotkey, hold_ms: Number(val) })}
+                                />
+                            </Group>
                         </Stack>
                     </Card>
                 </Grid.Col>
@@ -96,6 +229,18 @@ export function Settings({ state }: SettingsProps) {
                             </Button>
                         </Group>
 
+                        <Select
+                            label="Button Numbering"
+                            description="Physical index numbers buttons as the device reports them (up to 32)"
+                            data={[
+                                { value: 'RawIndex', label: 'Physical index' },
+                                { value: 'StandardButtons', label: 'Gamepad buttons (legacy, up to 19)' }
+                            ]}
+                            value={state.button_layout}
+                            onChange={(val) => val && handleLayoutChange(val as ButtonLayout)}
+                            mb="md"
+                        />
+
                         <Table striped highlightOnHover>
                             <Table.Thead>
                                 <Table.Tr>
@@ -10,11 +10,58 @@ export interface ControllerInfo {
 
 export interface AppConfig {
   target_controller_index: number;
-  input_method: "XInput" | "DirectInput";
+  target_controller_id: string | null;
+  input_method: InputMethod;
   chatter_threshold_ms: number;
   polling_rate_ms_connected: number;
   polling_rate_ms_disconnected: number;
   target_process_name: string;
+  games: GameDefinition[];
+  metrics_listen_addr?: string | null;
+  auto_switch_controller: boolean;
+  controller_priority: string[];
+  keyboard_bindings: Record<string, number>; // key name -> bit
+  midi: MidiConfig;
+  session_hotkey: SessionHotkey | null;
+  min_session_secs: number;
+  session_grace_secs: number;
+  session_resume_secs: number;
+  sleep_action: SleepAction;
+  snapshot_interval_secs: number; // 0 disables counter snapshots
+  alert_rules: AlertRule[];
+  notifications: NotificationConfig;
+}
+
+export interface NotificationConfig {
+  enabled: boolean;
+  min_interval_secs: number; // per alert kind
+  muted_kinds: AlertKind[];
+}
+
+export type AlertRule =
+  | { type: "ChatterRate"; percent: number; window_presses: number }
+  | { type: "Lifespan"; percent: number }
+  | { type: "SessionChatters"; count: number }
+  | { type: "DisconnectedInSession" };
+
+export type AlertKind = "ChatterRate" | "Lifespan" | "SessionChatters" | "DisconnectedInSession" | "SaveFailed";
+
+export interface Alert {
+  id: number;
+  kind: AlertKind;
+  controller_id: string; // empty for SaveFailed
+  key: LogicalKey | null;
+  message: string;
+  raised_at: string;
+  acknowledged_at: string | null;
+  resolved_at: string | null; // null while active
+}
+
+export type SleepAction = "End" | "Pause";
+
+export interface SessionHotkey {
+  keys: LogicalKey[];
+  hold_ms: number;
 }
 
 export interface ButtonStats {
@@ -33,9 +80,44 @@ export interface SwitchData {
   last_replaced_at: string | null;
 }
 
+export type InputMethod = "XInput" | "DirectInput" | "Evdev" | "Keyboard" | "Midi";
+
+export interface MidiConfig {
+  note_bits: Record<string, number>; // note number -> bit
+  turntable_cc: number | null;
+  turntable_up_bit: number;
+  turntable_down_bit: number;
+  turntable_hold_ms: number;
+  virtual_port: boolean;
+}
+
+export interface GameDefinition {
+  id: string;
+  display_name: string;
+  process_names: string[];
+  executable_path: string | null;
+  rules: ProcessRule[];
+}
+
+export interface ProcessRule {
+  process_name: string;
+  executable_path: string | null;
+  command_line: string | null;
+  window_title: string | null; // Windows only
+}
+
+export interface GameStats {
+  sessions: number;
+  play_time_secs: number;
+  keys: Record<string, SessionKeyStats>;
+}
+
+export type ButtonLayout = 'StandardButtons' | 'RawIndex';
+
 export interface ButtonMap {
   profile_name: string;
   bindings: Record<string, number>; // LogicalKey -> Button Mask (u32)
+  layout: ButtonLayout;
 }
 
 export interface SwitchHistoryEntry {
@@ -63,14 +145,113 @@ export interface SessionRecord {
   end_time: string;
   duration_secs: number;
   stats?: Record<string, SessionKeyStats>;
+  game_id?: string | null;
+  started_by?: SessionOrigin;
+  merged?: boolean;
+  timeline?: SessionTimeline;
+}
+
+export type ReportFormat = "Markdown" | "Html";
+
+export interface KeyReport {
+  key: string;
+  switch_model_id: string | null;
+  presses: number;
+  chatters: number;
+  chatter_rate_percent: number;
+  average_presses: number | null;
+  average_chatter_rate_percent: number | null;
+  exceeds_alert: boolean;
+  lifespan_used_percent: number | null;
+}
+
+export interface SessionReport {
+  start_time: string;
+  end_time: string;
+  duration_secs: number;
+  game_id: string | null;
+  game_name: string | null;
+  started_by: SessionOrigin;
+  merged: boolean;
+  total_presses: number;
+  total_chatters: number;
+  chatter_rate_percent: number;
+  presses_per_minute: number;
+  average_presses_per_minute: number | null;
+  chatter_alert_percent: number | null; // from the ChatterRate alert rule
+  keys: KeyReport[];
+}
+
+export type RollupPeriod = "Day" | "Week" | "Month";
+
+// Returned by the get_play_stats command
+export interface PlayRollup {
+  period_start: string; // local date, YYYY-MM-DD
+  sessions: number;
+  play_time_secs: number;
+  presses: number; // during sessions
+  chatters: number;
+  all_presses: number; // from counter snapshots, also outside sessions
+  all_chatters: number;
+  keys: { key: string; presses: number; chatters: number }[]; // most pressed first
+}
+
+export interface SessionTimeline {
+  bucket_secs: number; // 60, doubled for long sessions
+  buckets: Record<string, SessionKeyStats>[];
+}
+
+export type SessionOrigin = "Process" | "Manual" | "Hotkey";
+
+export interface ActiveSession {
+  start_time: string;
+  game_id: string | null;
+  started_by: SessionOrigin;
+  paused: boolean;
+  paused_at: string | null;
+  paused_secs: number; // pauses that already ended, left out of the duration
+  exited_at: string | null; // game exited, waiting for a restart
+  merged: boolean;
+  last_activity_at: string | null;
+}
+
+export interface MonitorCounters {
+  polls_total: number;
+  saves_succeeded: number;
+  saves_failed: number;
+}
+
+export interface PollTimingStats {
+  configured_us: number;
+  min_us: number;
+  avg_us: number;
+  p99_us: number;
+  max_us: number;
+  samples: number;
+  overruns: number;
+  rate_degraded: boolean;
+}
+
+export interface MonitoredControllerState {
+  controller_id: string;
+  name: string;
+  profile_name: string;
+  is_active: boolean;
+  is_connected: boolean;
+  raw_button_state: number;
+  current_pressed_keys: LogicalKey[];
+  switches: Record<string, SwitchData>;
 }
 
 export interface MonitorSharedState {
   is_connected: boolean;
   is_game_running: boolean;
+  running_game_id: string | null;
+  current_session: ActiveSession | null;
   config: AppConfig;
   profile_name: string;
   bindings: Record<string, number>;
+  button_layout: ButtonLayout;
   switches: Record<string, SwitchData>;
   switch_history: SwitchHistoryEntry[];
   current_pressed_keys: LogicalKey[]; // HashSet serializes to list
@@ -78,6 +259,11 @@ export interface MonitorSharedState {
   last_status_message: string | null;
   last_save_result: LastSaveResult | null;
   recent_sessions: SessionRecord[];
+  game_stats: Record<string, GameStats>;
+  alerts: Alert[]; // oldest first
   active_controller_id: string;
   connected_controllers: ControllerInfo[];
+  monitored_controllers: MonitoredControllerState[];
+  counters: MonitorCounters;
+  poll_timing: PollTimingStats;
 }